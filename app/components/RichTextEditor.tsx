@@ -784,11 +784,9 @@ const RichTextEditor = forwardRef<RichTextEditorRef, RichTextEditorProps>(({
                                     // Invoke backend command to save image from clipboard
                                     import('@tauri-apps/api/core').then(({ invoke }) => {
                                         invoke<string>('fusen_get_image_from_clipboard', { path: filePath })
-                                            .then((savedPath) => {
-                                                console.log('[EDITOR] Image saved at:', savedPath);
-                                                // Insert markdown: ![image](path)
-                                                // Use "image" as alt text, can be changed later
-                                                const markdown = `![image](${savedPath})`;
+                                            .then((markdown) => {
+                                                // [NEW] バックエンドが ![image](assets/<seq>/...) を返す
+                                                console.log('[EDITOR] Image saved:', markdown);
 
                                                 view.dispatch({
                                                     changes: {
@@ -0,0 +1,93 @@
+'use client';
+
+import React from 'react';
+
+// [NEW] 保存しようとしたメモが外部で変更されていたときの選択肢
+type SaveConflictDialogProps = {
+    isOpen: boolean;
+    title: string;
+    message: string;
+    reloadLabel: string;
+    overwriteLabel: string;
+    saveAsCopyLabel: string;
+    onReload: () => void;
+    onOverwrite: () => void;
+    onSaveAsCopy: () => void;
+};
+
+const buttonStyle: React.CSSProperties = {
+    padding: '8px 16px',
+    fontSize: '0.875rem',
+    fontWeight: 500,
+    border: 'none',
+    borderRadius: '6px',
+    cursor: 'pointer',
+    textAlign: 'left'
+};
+
+export default function SaveConflictDialog({
+    isOpen, title, message, reloadLabel, overwriteLabel, saveAsCopyLabel, onReload, onOverwrite, onSaveAsCopy
+}: SaveConflictDialogProps) {
+    if (!isOpen) return null;
+
+    return (
+        <div style={{
+            position: 'fixed',
+            top: 0,
+            left: 0,
+            right: 0,
+            bottom: 0,
+            zIndex: 10000,
+            display: 'flex',
+            alignItems: 'center',
+            justifyContent: 'center',
+            padding: '16px',
+            backgroundColor: 'rgba(0, 0, 0, 0.5)'
+        }}>
+            <div style={{
+                backgroundColor: '#fff',
+                borderRadius: '8px',
+                boxShadow: '0 20px 25px -5px rgba(0, 0, 0, 0.1), 0 10px 10px -5px rgba(0, 0, 0, 0.04)',
+                maxWidth: '24rem',
+                width: '100%',
+                padding: '24px'
+            }}>
+                <h3 style={{
+                    fontSize: '1.125rem',
+                    fontWeight: 700,
+                    color: '#111827',
+                    marginBottom: '8px'
+                }}>{title}</h3>
+                <p style={{
+                    fontSize: '0.875rem',
+                    color: '#4b5563',
+                    marginBottom: '24px',
+                    whiteSpace: 'pre-wrap',
+                    lineHeight: 1.6
+                }}>
+                    {message}
+                </p>
+                <div style={{ display: 'flex', flexDirection: 'column', gap: '8px' }}>
+                    <button
+                        onClick={onSaveAsCopy}
+                        style={{ ...buttonStyle, color: '#ffffff', backgroundColor: '#2563eb' }}
+                    >
+                        {saveAsCopyLabel}
+                    </button>
+                    <button
+                        onClick={onReload}
+                        style={{ ...buttonStyle, color: '#374151', backgroundColor: '#f3f4f6' }}
+                    >
+                        {reloadLabel}
+                    </button>
+                    <button
+                        onClick={onOverwrite}
+                        style={{ ...buttonStyle, color: '#ffffff', backgroundColor: '#dc2626' }}
+                    >
+                        {overwriteLabel}
+                    </button>
+                </div>
+            </div>
+        </div>
+    );
+}
@@ -2,12 +2,44 @@
 
 import { useState, useEffect, useCallback, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { WebviewWindow } from '@tauri-apps/api/webviewWindow';
 
 type SearchHit = {
     path: string;
     line: number;
     preview: string;
+    // [NEW] preview 内の一致範囲（文字オフセット）と前後の行
+    ranges: { start: number; end: number }[];
+    before: string[];
+    after: string[];
+};
+
+// [NEW] ノート単位の検索結果 (Rust: search::SearchGroup)
+type SearchGroup = {
+    path: string;
+    seq: number;
+    context: string;
+    tags: string[];
+    background_color: string | null;
+    score: number;
+    hits: SearchHit[];
+};
+
+// [NEW] バックグラウンド検索の結果 (Rust: search::SearchBatch)
+type SearchBatch = {
+    request_id: number;
+    groups: SearchGroup[];
+    done: boolean;
+};
+
+// 入力が止まってから検索を始めるまでの待ち時間
+const SEARCH_DEBOUNCE_MS = 250;
+
+// [NEW] クエリ構文エラー (Rust: query::QueryError)
+type QueryError = {
+    position: number;
+    message: string;
 };
 
 type SearchOverlayProps = {
@@ -17,35 +49,89 @@ type SearchOverlayProps = {
 
 export default function SearchOverlay({ onClose, getWindowLabel }: SearchOverlayProps) {
     const [query, setQuery] = useState('');
+    const [groups, setGroups] = useState<SearchGroup[]>([]);
     const [results, setResults] = useState<SearchHit[]>([]);
     const [currentIndex, setCurrentIndex] = useState(0);
     const [isSearching, setIsSearching] = useState(false);
+    const [queryError, setQueryError] = useState<QueryError | null>(null);
     const inputRef = useRef<HTMLInputElement>(null);
+    // [NEW] 最新の検索ジョブの id（古いジョブの結果は捨てる）
+    const requestIdRef = useRef(0);
+    // Enter で検索したときは最初の結果へジャンプする
+    const jumpOnFirstRef = useRef(false);
+    // まだ一度もジャンプしていない結果なら、次へで先頭から
+    const navigatedRef = useRef(false);
 
     useEffect(() => {
         inputRef.current?.focus();
     }, []);
 
-    const handleSearch = useCallback(async () => {
-        if (!query.trim()) return;
+    // [NEW] 検索結果は fusen:search_results で小分けに届く
+    useEffect(() => {
+        const unlisten = listen<SearchBatch>('fusen:search_results', (event) => {
+            const batch = event.payload;
+            if (batch.request_id !== requestIdRef.current) return;
+            setGroups(prev => [...prev, ...batch.groups]);
+            setResults(prev => [...prev, ...batch.groups.flatMap(g => g.hits)]);
+            if (batch.done) {
+                console.log('[SearchOverlay] Search finished:', batch.request_id);
+                setIsSearching(false);
+            }
+        });
+        return () => {
+            unlisten.then(f => f());
+            // 閉じたら実行中の検索も止める
+            invoke('fusen_cancel_search', { requestId: requestIdRef.current }).catch(() => { });
+        };
+    }, []);
+
+    const startSearch = useCallback(async (text: string, jump: boolean) => {
+        const requestId = ++requestIdRef.current;
+        jumpOnFirstRef.current = jump;
+        navigatedRef.current = false;
+        setGroups([]);
+        setResults([]);
+        setCurrentIndex(0);
+        setQueryError(null);
+        if (!text) {
+            setIsSearching(false);
+            invoke('fusen_cancel_search', { requestId: requestId - 1 }).catch(() => { });
+            return;
+        }
         setIsSearching(true);
         try {
-            console.log('[SearchOverlay] Invoking fusen_search_notes with query:', query.trim());
-            const hits = await invoke<SearchHit[]>('fusen_search_notes', { query: query.trim() });
-            console.log('[SearchOverlay] Got results:', hits.length);
-            setResults(hits);
-            setCurrentIndex(0);
-            if (hits.length > 0) {
-                await jumpToHit(hits[0]);
-            }
+            console.log('[SearchOverlay] Invoking fusen_start_search:', requestId, text);
+            await invoke('fusen_start_search', { query: text, requestId });
         } catch (e) {
             console.error('Search failed:', e);
-        } finally {
+            if (requestId !== requestIdRef.current) return;
+            if (e && typeof e === 'object' && 'position' in e) {
+                setQueryError(e as QueryError);
+            }
             setIsSearching(false);
         }
-    }, [query]);
+    }, []);
+
+    const handleSearch = useCallback(() => {
+        if (!query.trim()) return;
+        startSearch(query.trim(), true);
+    }, [query, startSearch]);
+
+    // [NEW] 入力中も検索する（前の検索は Rust 側で打ち切られる）
+    useEffect(() => {
+        const timer = setTimeout(() => startSearch(query.trim(), false), SEARCH_DEBOUNCE_MS);
+        return () => clearTimeout(timer);
+    }, [query, startSearch]);
+
+    useEffect(() => {
+        if (jumpOnFirstRef.current && results.length > 0) {
+            jumpOnFirstRef.current = false;
+            jumpToHit(results[0]);
+        }
+    }, [results]);
 
     const jumpToHit = async (hit: SearchHit) => {
+        navigatedRef.current = true;
         const label = getWindowLabel(hit.path);
         try {
             const existing = await WebviewWindow.getByLabel(label);
@@ -93,6 +179,10 @@ export default function SearchOverlay({ onClose, getWindowLabel }: SearchOverlay
 
     const handleNext = useCallback(async () => {
         if (results.length === 0) return;
+        if (!navigatedRef.current) {
+            await jumpToHit(results[currentIndex]);
+            return;
+        }
         const nextIndex = (currentIndex + 1) % results.length;
         setCurrentIndex(nextIndex);
         await jumpToHit(results[nextIndex]);
@@ -109,7 +199,7 @@ export default function SearchOverlay({ onClose, getWindowLabel }: SearchOverlay
         if (e.key === 'Enter') {
             if (e.shiftKey && results.length > 0) {
                 handlePrev();
-            } else if (results.length > 0 && !isSearching) {
+            } else if (results.length > 0) {
                 handleNext();
             } else {
                 handleSearch();
@@ -131,6 +221,24 @@ export default function SearchOverlay({ onClose, getWindowLabel }: SearchOverlay
         return path.split(/[\\/]/).pop() || path;
     };
 
+    // [NEW] 一致範囲を <mark> で囲む（オフセットは文字単位なので Array.from で分割）
+    const renderPreview = (hit: SearchHit) => {
+        const chars = Array.from(hit.preview);
+        const parts: React.ReactNode[] = [];
+        let pos = 0;
+        hit.ranges.forEach((r, i) => {
+            if (r.start > pos) parts.push(chars.slice(pos, r.start).join(''));
+            parts.push(
+                <mark key={i} className="bg-yellow-200 text-gray-900 rounded-sm">
+                    {chars.slice(r.start, r.end).join('')}
+                </mark>
+            );
+            pos = r.end;
+        });
+        if (pos < chars.length) parts.push(chars.slice(pos).join(''));
+        return parts;
+    };
+
     return (
         <div
             className="fixed top-0 left-0 w-full h-full z-50 bg-white/98 backdrop-blur-md flex flex-col gap-3 p-4 box-border"
@@ -190,31 +298,64 @@ export default function SearchOverlay({ onClose, getWindowLabel }: SearchOverlay
             {/* 結果リスト */}
             {results.length > 0 && (
                 <div className="overflow-y-auto max-h-64 border-t border-gray-100 pt-2">
-                    {results.map((hit, idx) => (
-                        <button
-                            key={`${hit.path}-${hit.line}`}
-                            onClick={() => {
-                                setCurrentIndex(idx);
-                                jumpToHit(hit);
-                            }}
-                            className={`w-full text-left p-2 rounded-lg mb-1 transition-colors ${idx === currentIndex
-                                ? 'bg-blue-100 border border-blue-300'
-                                : 'hover:bg-gray-100'
-                                }`}
-                        >
-                            <div className="text-xs text-gray-500 truncate">
-                                {getFileName(hit.path)} : {hit.line}行目
-                            </div>
-                            <div className="text-sm text-gray-700 truncate">
-                                {hit.preview}
+                    {groups.map(group => (
+                        <div key={group.path} className="mb-2">
+                            {/* ノートごとの見出し */}
+                            <div className="flex items-center gap-2 px-2 py-1 text-xs text-gray-500">
+                                <span
+                                    className="inline-block w-3 h-3 rounded-sm border border-gray-300 shrink-0"
+                                    style={{ backgroundColor: group.background_color ?? '#f7e9b0' }}
+                                />
+                                <span className="font-medium text-gray-700 truncate">
+                                    {group.context || getFileName(group.path)}
+                                </span>
+                                {group.tags.map(tag => (
+                                    <span key={tag} className="px-1 bg-gray-100 rounded text-gray-500">#{tag}</span>
+                                ))}
                             </div>
-                        </button>
+                            {group.hits.map(hit => {
+                                const idx = results.indexOf(hit);
+                                return (
+                                    <button
+                                        key={`${hit.path}-${hit.line}`}
+                                        onClick={() => {
+                                            setCurrentIndex(idx);
+                                            jumpToHit(hit);
+                                        }}
+                                        className={`w-full text-left p-2 rounded-lg mb-1 transition-colors ${idx === currentIndex
+                                            ? 'bg-blue-100 border border-blue-300'
+                                            : 'hover:bg-gray-100'
+                                            }`}
+                                    >
+                                        <div className="text-xs text-gray-500 truncate">
+                                            {getFileName(hit.path)} : {hit.line}行目
+                                        </div>
+                                        {hit.before.map((l, i) => (
+                                            <div key={`b${i}`} className="text-xs text-gray-400 truncate">{l}</div>
+                                        ))}
+                                        <div className="text-sm text-gray-700 truncate">
+                                            {renderPreview(hit)}
+                                        </div>
+                                        {hit.after.map((l, i) => (
+                                            <div key={`a${i}`} className="text-xs text-gray-400 truncate">{l}</div>
+                                        ))}
+                                    </button>
+                                );
+                            })}
+                        </div>
                     ))}
                 </div>
             )}
 
+            {/* クエリの構文エラー */}
+            {queryError && (
+                <div className="text-sm text-red-500 py-2">
+                    {queryError.position + 1}文字目: {queryError.message}
+                </div>
+            )}
+
             {/* 検索結果なし */}
-            {results.length === 0 && query.trim() && !isSearching && (
+            {!queryError && results.length === 0 && query.trim() && !isSearching && (
                 <div className="text-center text-gray-400 py-4">
                     検索結果がありません
                 </div>
@@ -194,6 +194,56 @@ describe('StickyNote Component', () => {
         });
     });
 
+    it('Feature: Save conflict offers reload, overwrite or save as copy', async () => {
+        const actual = { mtime_ms: 2, hash: 'b' };
+        mockInvoke.mockImplementation((cmd, args) => {
+            switch (cmd) {
+                case 'fusen_read_note':
+                    return Promise.resolve({
+                        meta: { path: 'd:/test/note.md', width: 200, height: 200 },
+                        body: '---\ntags: []\n---\nTest Content'
+                    });
+                case 'fusen_save_note':
+                    // 上書きを選ぶまでは競合で拒否される
+                    return args?.expectedVersion
+                        ? Promise.resolve('d:/test/note.md')
+                        : Promise.reject({ kind: 'conflict', path: 'd:/test/note.md', expected: { mtime_ms: 1, hash: 'a' }, actual });
+                case 'fusen_get_all_tags':
+                    return Promise.resolve([]);
+                default:
+                    return Promise.resolve(null);
+            }
+        });
+
+        render(<StickyNote />);
+        await waitFor(() => expect(screen.getAllByText('Test Content').length).toBeGreaterThan(0));
+
+        const texts = screen.getAllByText('Test Content');
+        await act(async () => {
+            fireEvent.doubleClick(texts[0]);
+        });
+        await waitFor(() => {
+            expect(screen.getAllByTestId('rich-text-editor').length).toBeGreaterThan(0);
+        }, { timeout: 3000 });
+        const editors = screen.getAllByTestId('rich-text-editor');
+        await act(async () => {
+            fireEvent.keyDown(editors[0], { key: 'Escape', code: 'Escape' });
+        });
+
+        // [object Object] を記録して黙って捨てるのではなく、選択肢を出す
+        await waitFor(() => expect(screen.getByText('ほかで変更されています')).toBeTruthy());
+        await act(async () => {
+            fireEvent.click(screen.getByText('上書きする'));
+        });
+
+        await waitFor(() => {
+            expect(mockInvoke).toHaveBeenCalledWith('fusen_save_note', expect.objectContaining({
+                expectedVersion: actual
+            }));
+        });
+        expect(screen.queryByText('ほかで変更されています')).toBeNull();
+    });
+
     it('Feature: Header Tag Display', async () => {
         // Mock returning tags in frontmatter
         mockInvoke.mockImplementation((cmd) => {
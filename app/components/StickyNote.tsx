@@ -11,6 +11,7 @@ import { playDeleteSound, playSaveSound } from '../utils/soundManager';
 import { getFontSize } from '../utils/settingsManager';
 import RichTextEditor, { RichTextEditorRef } from './RichTextEditor';
 import ConfirmDialog from './ConfirmDialog';
+import SaveConflictDialog from './SaveConflictDialog';
 import ResizableImage from './ResizableImage';
 import { splitFrontMatter, updateFrontmatterValue } from '../utils/splitFrontMatter';
 
@@ -38,6 +39,18 @@ type Note = {
     meta: NoteMeta;
 };
 
+// [NEW] 保存系コマンドのエラー (Rust の NoteWriteError)
+type NoteVersion = { mtime_ms: number; hash: string };
+type NoteConflictError = { kind: 'conflict'; path: string; expected: NoteVersion; actual: NoteVersion };
+type NoteWriteError = NoteConflictError | { kind: 'failed'; message: string };
+
+function isConflictError(e: unknown): e is NoteConflictError {
+    return typeof e === 'object' && e !== null && (e as NoteWriteError).kind === 'conflict';
+}
+
+// 競合で保存できなかった編集内容（どう解決するか選んでもらう間、持っておく）
+type SaveConflict = { path: string; body: string; frontmatter: string; actual: NoteVersion };
+
 // ユーティリティ関数
 
 
@@ -75,6 +88,7 @@ const StickyNote = memo(function StickyNote() {
     const [isEditing, setIsEditing] = useState(false);
     const [editBody, setEditBody] = useState('');
     const [savePending, setSavePending] = useState(false);
+    const [saveConflict, setSaveConflict] = useState<SaveConflict | null>(null); // [NEW] 外部変更との競合
     const [cursorPosition, setCursorPosition] = useState<number | null>(null);
     const [isNewNote, setIsNewNote] = useState(false); // [NEW] 新規ノートフラグ（state管理）
     const textareaRef = useRef<HTMLTextAreaElement>(null);
@@ -317,7 +331,7 @@ const StickyNote = memo(function StickyNote() {
         }
     }, [selectedFile]);
     // ノート保存
-    const saveNote = useCallback(async (path: string, body: string, frontmatter: string, allowRename: boolean) => {
+    const saveNote = useCallback(async (path: string, body: string, frontmatter: string, allowRename: boolean, expectedVersion?: NoteVersion) => {
         // [Strict Log]
         console.log('[SAVE]', { allowRename, firstLine: body.split('\n')[0], path });
         console.log('[DEBUG] saveNote called:', { path, bodyLength: body.length, allowRename });
@@ -326,7 +340,8 @@ const StickyNote = memo(function StickyNote() {
                 path,
                 body,
                 frontmatterRaw: frontmatter,
-                allowRename
+                allowRename,
+                expectedVersion
             });
             console.log('[DEBUG] saveNote result:', { old: path, new: newPath, renamed: !pathsEqual(newPath, path) });
             if (!pathsEqual(newPath, path)) {
@@ -342,10 +357,68 @@ const StickyNote = memo(function StickyNote() {
                 window.history.replaceState({}, '', url.toString());
             }
         } catch (e) {
-            console.error('save_note failed', e);
+            // [NEW] 外部で変更されていたら、編集内容を持ったまま選んでもらう
+            if (isConflictError(e)) {
+                console.warn('[SAVE] Conflict: changed on disk', e.path);
+                setSaveConflict({ path, body, frontmatter, actual: e.actual });
+                return;
+            }
+            console.error('save_note failed', (e as NoteWriteError)?.kind === 'failed' ? (e as { message: string }).message : e);
         }
     }, []);
 
+    // [NEW] 競合: ディスクの内容を読み直す（編集は捨てる）
+    const reloadFromDisk = useCallback(async (path: string) => {
+        const note = await invoke<Note>('fusen_read_note', { path });
+        const { front, body } = splitFrontMatter(note.body);
+        setRawFrontmatter(front);
+        setContent(body);
+        setEditBody(body);
+    }, []);
+
+    const resolveConflictReload = useCallback(async () => {
+        if (!saveConflict) return;
+        setSaveConflict(null);
+        try {
+            await reloadFromDisk(saveConflict.path);
+        } catch (e) {
+            console.error('[CONFLICT] Failed to reload note:', e);
+        }
+    }, [saveConflict, reloadFromDisk]);
+
+    // [NEW] 競合: 外部の変更を承知の上で上書きする
+    const resolveConflictOverwrite = useCallback(async () => {
+        if (!saveConflict) return;
+        setSaveConflict(null);
+        await saveNote(saveConflict.path, saveConflict.body, saveConflict.frontmatter, false, saveConflict.actual);
+    }, [saveConflict, saveNote]);
+
+    // [NEW] 競合: 編集内容を新しいメモとして保存し、このメモはディスクの内容に戻す
+    const resolveConflictSaveAsCopy = useCallback(async () => {
+        if (!saveConflict) return;
+        const { path, body, frontmatter } = saveConflict;
+        setSaveConflict(null);
+        try {
+            const folderPath = path.substring(0, Math.max(path.lastIndexOf('\\'), path.lastIndexOf('/')));
+            const context = body.split('\n')[0].trim();
+            const copy = await invoke<Note>('fusen_create_note', { folderPath, context });
+            let copyFrontmatter = updateFrontmatterValue(frontmatter, 'seq', copy.meta.seq);
+            // 履歴が元のメモと混ざらないよう、id も新しいメモのものにする
+            const copyId = copy.frontmatter.match(/^id:\s*(.*)$/m)?.[1];
+            if (copyId) copyFrontmatter = updateFrontmatterValue(copyFrontmatter, 'id', copyId);
+            await invoke<string>('fusen_save_note', {
+                path: copy.meta.path,
+                body,
+                frontmatterRaw: copyFrontmatter,
+                allowRename: false
+            });
+            await emit('fusen:open_note', { path: copy.meta.path });
+            await reloadFromDisk(path);
+        } catch (e) {
+            console.error('[CONFLICT] Failed to save as a new note:', e);
+        }
+    }, [saveConflict, reloadFromDisk]);
+
     // 自動保存
     useEffect(() => {
         if (!selectedFile || !savePending) return;
@@ -652,6 +725,34 @@ const StickyNote = memo(function StickyNote() {
         };
     }, [selectedFile]);
 
+    // [NEW] 一括置換で1行目が変わりリネームされたら、パスを差し替えて読み直す
+    useEffect(() => {
+        if (!selectedFile) return;
+
+        const promise = listen<{ old_path: string; new_path: string }>('fusen:note_renamed', async (event) => {
+            if (!pathsEqual(event.payload.old_path, selectedFile.path)) return;
+            const newPath = event.payload.new_path;
+            try {
+                const note = await invoke<Note>('fusen_read_note', { path: newPath });
+                const { front, body } = splitFrontMatter(note.body);
+                setSelectedFile(prev => prev ? { ...prev, path: newPath, context: note.meta.context } : null);
+                setRawFrontmatter(front);
+                setContent(body);
+                setEditBody(body);
+
+                const url = new URL(window.location.href);
+                url.searchParams.set('path', newPath);
+                window.history.replaceState({}, '', url.toString());
+            } catch (error) {
+                console.error('[RENAME] Failed to reload renamed note:', error);
+            }
+        });
+
+        return () => {
+            promise.then(unlisten => unlisten());
+        };
+    }, [selectedFile]);
+
     // [NEW] 全文検索からのジャンプ時にハイライトする
     useEffect(() => {
         if (!selectedFile) return;
@@ -1638,14 +1739,15 @@ const StickyNote = memo(function StickyNote() {
             await new Promise(resolve => setTimeout(resolve, 300));
 
             // 3. キャプチャ実行 (Backend) - Timeout 30s
-            console.log('[CAPTURE_DEBUG] Invoking backend capture for seq:', selectedFile?.seq);
-            const capturePromise = invoke<string>('fusen_capture_screen', { noteSeq: selectedFile?.seq || 0 });
+            console.log('[CAPTURE_DEBUG] Invoking backend capture for note:', selectedFile?.path);
+            // [NEW] ノートの assets/<seq>/ に保存され、ノートからの相対リンクの Markdown が返る
+            const capturePromise = invoke<string>('fusen_capture_screen', { notePath: selectedFile?.path });
             const timeoutPromise = new Promise<string>((_, reject) =>
                 setTimeout(() => reject(new Error('Capture timed out (30s)')), 30000)
             );
 
-            const imagePath = await Promise.race([capturePromise, timeoutPromise]);
-            console.log('[CAPTURE_DEBUG] Backend returned image path:', imagePath);
+            const markdown = await Promise.race([capturePromise, timeoutPromise]);
+            console.log('[CAPTURE_DEBUG] Backend returned markdown:', markdown);
 
             // 4. 自分を表示
             console.log('[CAPTURE_DEBUG] Showing window again...');
@@ -1656,35 +1758,7 @@ const StickyNote = memo(function StickyNote() {
             await new Promise(r => setTimeout(r, 400));
 
             // 5. 画像リンクを挿入
-            // Convert to relative path if possible
-            let storedPath = imagePath;
-            const currentPath = selectedFile?.path;
-            if (currentPath) {
-                const lastSlash = Math.max(currentPath.lastIndexOf('\\'), currentPath.lastIndexOf('/'));
-                const currentDir = lastSlash >= 0 ? currentPath.substring(0, lastSlash) : '';
-
-                // Normalize paths for comparison
-                const normImagePath = imagePath.replace(/\//g, '\\');
-                const normCurrentDir = currentDir.replace(/\//g, '\\');
-
-                if (normImagePath.startsWith(normCurrentDir)) {
-                    // Simple case: subpath
-                    let rel = normImagePath.substring(normCurrentDir.length);
-                    if (rel.startsWith('\\')) rel = rel.substring(1);
-                    // Use forward slashes for Markdown compatibility
-                    storedPath = rel.replace(/\\/g, '/');
-                }
-            }
-            console.log('[CAPTURE_DEBUG] Relative path for markdown:', storedPath);
-
-            // Markdown text to insert: ![filename](path)
-            // Use simple filename as alt?
-            // Extract filename from path
-            const filenameObj = imagePath.split('\\').pop() || 'screenshot';
-            // User requested: filename + scale (initially 1.0 or omitted)
-            // Storing just ![filename](relPath)
-
-            const imageMarkdown = `\n![${filenameObj}](${storedPath})\n`;
+            const imageMarkdown = `\n${markdown}\n`;
 
             console.log('[CAPTURE_DEBUG] Markdown to insert:', imageMarkdown);
             console.log('[CAPTURE_DEBUG] editorRef.current exists?', !!editorRef.current);
@@ -2318,6 +2392,17 @@ const StickyNote = memo(function StickyNote() {
                 )
             }
             {/* Confirmation Dialog for Global Tag Deletion */}
+            <SaveConflictDialog
+                isOpen={!!saveConflict}
+                title={t('conflict.title')}
+                message={t('conflict.message')}
+                reloadLabel={t('conflict.reload')}
+                overwriteLabel={t('conflict.overwrite')}
+                saveAsCopyLabel={t('conflict.saveAsCopy')}
+                onReload={resolveConflictReload}
+                onOverwrite={resolveConflictOverwrite}
+                onSaveAsCopy={resolveConflictSaveAsCopy}
+            />
             <ConfirmDialog
                 isOpen={!!tagToDelete}
                 title="タグの削除"
@@ -456,6 +456,40 @@ function OrchestratorContent() {
     };
   }, []);
 
+  // [NEW] Vault監視: 外部で追加/削除されたノートのウィンドウを自動で開閉
+  useEffect(() => {
+    if (!isMainWindow) return;
+
+    const promises = [
+      listen<string>('fusen:note_created', async (event) => {
+        // [NEW] 復元したノートは保存されていた位置・サイズで開く
+        let geometry: { x?: number, y?: number, width?: number, height?: number } | undefined;
+        try {
+          const note = await invoke<{ meta: { x?: number | null, y?: number | null, width?: number | null, height?: number | null } }>('fusen_read_note', { path: event.payload });
+          geometry = {
+            x: note.meta.x ?? undefined,
+            y: note.meta.y ?? undefined,
+            width: note.meta.width ?? undefined,
+            height: note.meta.height ?? undefined,
+          };
+        } catch (e) {
+          console.warn('[Watcher] Failed to read created note:', e);
+        }
+        openNoteWindow(event.payload, geometry);
+      }),
+      listen<string>('fusen:note_deleted', async (event) => {
+        const { getAllWebviewWindows } = await import('@tauri-apps/api/webviewWindow');
+        const label = getWindowLabel(event.payload);
+        const win = (await getAllWebviewWindows()).find(w => w.label === label);
+        if (win) await win.close();
+      }),
+    ];
+
+    return () => {
+      promises.forEach(p => p.then(u => u()));
+    };
+  }, [isMainWindow]);
+
   // [FIX] メインウィンドウの「閉じる」を「隠す」に変更 (検索ウィンドウ再表示不具合修正)
   useEffect(() => {
     if (!isMainWindow) return;
@@ -1,7 +1,7 @@
 "use client"
 
 import React, { useState, useMemo } from "react"
-import { Monitor, Moon, Sun, Laptop, Save, FolderOpen, Info, Settings, Database, Type, Volume2, Globe, Reply } from "lucide-react"
+import { Monitor, Moon, Sun, Laptop, Save, FolderOpen, Info, Settings, Database, Type, Volume2, Globe, Reply, Trash2, ImageOff, Copy, FolderTree, Archive, ArchiveRestore, GitBranch } from "lucide-react"
 
 // ★さっき作った「倉庫番」をインポート
 import { useSettings, type AppSettings } from "@/lib/settings-store"
@@ -387,6 +387,151 @@ function DataSection({
         }
     }
 
+    // [NEW] ゴミ箱を空にする
+    const handleEmptyTrash = async () => {
+        if (!confirm(t('settings.data.emptyTrashConfirm'))) return
+        try {
+            const { invoke } = await import("@tauri-apps/api/core")
+            const report = await invoke<{ notes: string[]; assets: string[] }>("fusen_empty_trash")
+            alert(t('settings.data.emptyTrashDone')
+                .replace('{notes}', String(report.notes.length))
+                .replace('{assets}', String(report.assets.length)))
+        } catch (e) {
+            console.error("ゴミ箱を空にできませんでした:", e)
+            alert("ゴミ箱を空にできませんでした: " + String(e))
+        }
+    }
+
+    // [NEW] どのノートからも使われていない画像を削除する
+    const handleCleanOrphans = async () => {
+        try {
+            const { invoke } = await import("@tauri-apps/api/core")
+            const orphans = await invoke<{ path: string; size: number }[]>("fusen_list_orphaned_assets")
+            if (orphans.length === 0) {
+                alert(t('settings.data.noOrphans'))
+                return
+            }
+            const kb = Math.ceil(orphans.reduce((sum, o) => sum + o.size, 0) / 1024)
+            const list = orphans.slice(0, 10).map(o => o.path).join("\n") + (orphans.length > 10 ? "\n..." : "")
+            const message = t('settings.data.orphansConfirm')
+                .replace('{count}', String(orphans.length))
+                .replace('{kb}', String(kb))
+            if (!confirm(message + "\n\n" + list)) return
+            const deleted = await invoke<string[]>("fusen_delete_orphaned_assets", { paths: orphans.map(o => o.path) })
+            alert(t('settings.data.orphansDone').replace('{count}', String(deleted.length)))
+        } catch (e) {
+            console.error("未使用の画像を削除できませんでした:", e)
+            alert("未使用の画像を削除できませんでした: " + String(e))
+        }
+    }
+
+    // [NEW] 既存の画像の重複をまとめる（ファイル名を内容のハッシュにそろえる）
+    const handleDedupeAssets = async () => {
+        if (!confirm(t('settings.data.dedupeConfirm'))) return
+        try {
+            const { invoke } = await import("@tauri-apps/api/core")
+            const report = await invoke<{ removed: string[]; renamed: number; notes: string[]; saved_bytes: number }>("fusen_dedupe_assets")
+            alert(t('settings.data.dedupeDone')
+                .replace('{removed}', String(report.removed.length))
+                .replace('{notes}', String(report.notes.length))
+                .replace('{kb}', String(Math.ceil(report.saved_bytes / 1024))))
+        } catch (e) {
+            console.error("画像の重複をまとめられませんでした:", e)
+            alert("画像の重複をまとめられませんでした: " + String(e))
+        }
+    }
+
+    // [NEW] 既存の画像をノートごとのフォルダ (assets/<seq>/) へ移す
+    const handleOrganizeAssets = async () => {
+        if (!confirm(t('settings.data.organizeConfirm'))) return
+        try {
+            const { invoke } = await import("@tauri-apps/api/core")
+            const report = await invoke<{ notes: string[]; cleaned: string[] }>("fusen_organize_assets")
+            alert(t('settings.data.organizeDone').replace('{notes}', String(report.notes.length)))
+        } catch (e) {
+            console.error("画像を整理できませんでした:", e)
+            alert("画像を整理できませんでした: " + String(e))
+        }
+    }
+
+    // [NEW] バックアップ
+    type BackupInfo = { path: string; name: string; created_at: string; size: number }
+    const [backups, setBackups] = useState<BackupInfo[]>([])
+    const [selectedBackup, setSelectedBackup] = useState("")
+
+    const handleSelectBackupDir = async () => {
+        try {
+            const { invoke } = await import("@tauri-apps/api/core")
+            const folder = await invoke<string | null>("fusen_pick_folder")
+            if (folder) onUpdate("backup_dir", folder)
+        } catch (e) {
+            console.error("フォルダ選択に失敗:", e)
+        }
+    }
+
+    const loadBackups = async () => {
+        try {
+            const { invoke } = await import("@tauri-apps/api/core")
+            const list = await invoke<BackupInfo[]>("fusen_list_backups")
+            setBackups(list)
+            setSelectedBackup(list[0]?.path ?? "")
+            if (list.length === 0) alert(t('settings.data.noBackups'))
+        } catch (e) {
+            console.error("バックアップの一覧を取得できませんでした:", e)
+            alert(String(e))
+        }
+    }
+
+    const handleBackupNow = async () => {
+        try {
+            const { invoke } = await import("@tauri-apps/api/core")
+            const info = await invoke<BackupInfo>("fusen_create_backup")
+            alert(t('settings.data.backupDone').replace('{name}', info.name))
+        } catch (e) {
+            console.error("バックアップを作成できませんでした:", e)
+            alert("バックアップを作成できませんでした: " + String(e))
+        }
+    }
+
+    // [NEW] 今すぐ git に同期
+    const handleGitSync = async () => {
+        try {
+            const { invoke } = await import("@tauri-apps/api/core")
+            type SyncReport = { committed: boolean, merged: boolean, pushed: boolean, conflicts: string[] }
+            const report = await invoke<SyncReport>("fusen_git_sync")
+            alert(report.conflicts.length > 0
+                ? t('settings.data.gitSyncConflicts').replace('{count}', String(report.conflicts.length))
+                : t('settings.data.gitSyncDone'))
+        } catch (e) {
+            console.error("同期できませんでした:", e)
+            alert("同期できませんでした: " + String(e))
+        }
+    }
+
+    const handleRestore = async (overVault: boolean) => {
+        const backup = backups.find(b => b.path === selectedBackup)
+        if (!backup) return
+        try {
+            const { invoke } = await import("@tauri-apps/api/core")
+            let target: string | null = null
+            if (overVault) {
+                if (!confirm(t('settings.data.restoreConfirm').replace('{name}', backup.name))) return
+            } else {
+                target = await invoke<string | null>("fusen_pick_folder")
+                if (!target) return
+            }
+            const report = await invoke<{ target: string; files: string[] }>("fusen_restore_backup", {
+                path: backup.path,
+                target,
+                confirm: overVault,
+            })
+            alert(t('settings.data.restoreDone').replace('{count}', String(report.files.length)))
+        } catch (e) {
+            console.error("バックアップから復元できませんでした:", e)
+            alert("バックアップから復元できませんでした: " + String(e))
+        }
+    }
+
     return (
         <div className="space-y-6">
             <div className="mb-8">
@@ -415,6 +560,203 @@ function DataSection({
                     </p>
                 </div>
 
+                {/* [NEW] ゴミ箱の保持期間 */}
+                <div className="grid gap-2">
+                    <Label htmlFor="trash-retention">{t('settings.data.trashRetention')}</Label>
+                    <div className="flex gap-2">
+                        <Input
+                            id="trash-retention"
+                            type="number"
+                            min={0}
+                            value={settings.trash_retention_days}
+                            onChange={(e) => onUpdate("trash_retention_days", Math.max(0, parseInt(e.target.value, 10) || 0))}
+                            className="w-32"
+                        />
+                        <Button variant="outline" onClick={handleEmptyTrash}>
+                            <Trash2 className="mr-2 h-4 w-4" /> {t('settings.data.emptyTrash')}
+                        </Button>
+                    </div>
+                    <p className="text-xs text-muted-foreground">{t('settings.data.trashRetentionDesc')}</p>
+                </div>
+
+                {/* [NEW] 未使用の画像 */}
+                <div className="grid gap-2">
+                    <Label>{t('settings.data.orphans')}</Label>
+                    <div>
+                        <Button variant="outline" onClick={handleCleanOrphans}>
+                            <ImageOff className="mr-2 h-4 w-4" /> {t('settings.data.cleanOrphans')}
+                        </Button>
+                    </div>
+                    <div>
+                        <Button variant="outline" onClick={handleDedupeAssets}>
+                            <Copy className="mr-2 h-4 w-4" /> {t('settings.data.dedupeAssets')}
+                        </Button>
+                    </div>
+                    <div>
+                        <Button variant="outline" onClick={handleOrganizeAssets}>
+                            <FolderTree className="mr-2 h-4 w-4" /> {t('settings.data.organizeAssets')}
+                        </Button>
+                    </div>
+                    <p className="text-xs text-muted-foreground">{t('settings.data.orphansDesc')}</p>
+                </div>
+
+                {/* [NEW] タグ付きノートのアーカイブ先 */}
+                <div className="grid gap-2">
+                    <Label>{t('settings.data.archiveLayout')}</Label>
+                    <div className="flex gap-2">
+                        <Button
+                            variant={settings.archive_layout === "firstTag" ? "default" : "outline"}
+                            className="w-40 justify-start"
+                            onClick={() => onUpdate("archive_layout", "firstTag")}
+                        >
+                            {t('settings.data.archiveFirstTag')}
+                        </Button>
+                        <Button
+                            variant={settings.archive_layout === "allTags" ? "default" : "outline"}
+                            className="w-40 justify-start"
+                            onClick={() => onUpdate("archive_layout", "allTags")}
+                        >
+                            {t('settings.data.archiveAllTags')}
+                        </Button>
+                    </div>
+                    <p className="text-xs text-muted-foreground">{t('settings.data.archiveLayoutDesc')}</p>
+                </div>
+
+                {/* [NEW] 自動バックアップ */}
+                <div className="grid gap-3 rounded-lg border p-4">
+                    <div className="flex items-center justify-between">
+                        <div className="space-y-0.5">
+                            <Label className="text-base">{t('settings.data.backup')}</Label>
+                            <p className="text-xs text-muted-foreground">{t('settings.data.backupDesc')}</p>
+                        </div>
+                        <Switch
+                            checked={settings.backup_enabled}
+                            onCheckedChange={(val) => onUpdate("backup_enabled", val)}
+                        />
+                    </div>
+                    <div className="grid gap-2">
+                        <Label htmlFor="backup-dir">{t('settings.data.backupDir')}</Label>
+                        <div className="flex gap-2">
+                            <Input
+                                id="backup-dir"
+                                value={settings.backup_dir ?? ""}
+                                readOnly
+                                className="font-mono text-sm bg-muted"
+                            />
+                            <Button variant="outline" onClick={handleSelectBackupDir}>
+                                <FolderOpen className="mr-2 h-4 w-4" /> {t('settings.data.browse')}
+                            </Button>
+                        </div>
+                    </div>
+                    <div className="flex gap-4">
+                        <div className="grid gap-2">
+                            <Label htmlFor="backup-interval">{t('settings.data.backupInterval')}</Label>
+                            <Input
+                                id="backup-interval"
+                                type="number"
+                                min={0}
+                                value={settings.backup_interval_hours}
+                                onChange={(e) => onUpdate("backup_interval_hours", Math.max(0, parseInt(e.target.value, 10) || 0))}
+                                className="w-32"
+                            />
+                        </div>
+                        <div className="grid gap-2">
+                            <Label htmlFor="backup-keep">{t('settings.data.backupKeep')}</Label>
+                            <Input
+                                id="backup-keep"
+                                type="number"
+                                min={0}
+                                value={settings.backup_keep}
+                                onChange={(e) => onUpdate("backup_keep", Math.max(0, parseInt(e.target.value, 10) || 0))}
+                                className="w-32"
+                            />
+                        </div>
+                    </div>
+                    <div className="flex items-center justify-between">
+                        <Label>{t('settings.data.backupIncludeArchive')}</Label>
+                        <Switch
+                            checked={settings.backup_include_archive}
+                            onCheckedChange={(val) => onUpdate("backup_include_archive", val)}
+                        />
+                    </div>
+                    <div className="flex items-center justify-between">
+                        <Label>{t('settings.data.backupIncludeTrash')}</Label>
+                        <Switch
+                            checked={settings.backup_include_trash}
+                            onCheckedChange={(val) => onUpdate("backup_include_trash", val)}
+                        />
+                    </div>
+                    <div className="flex gap-2">
+                        <Button variant="outline" disabled={!settings.backup_dir} onClick={handleBackupNow}>
+                            <Archive className="mr-2 h-4 w-4" /> {t('settings.data.backupNow')}
+                        </Button>
+                        <Button variant="outline" disabled={!settings.backup_dir} onClick={loadBackups}>
+                            <ArchiveRestore className="mr-2 h-4 w-4" /> {t('settings.data.restoreBackup')}
+                        </Button>
+                    </div>
+                    {backups.length > 0 && (
+                        <div className="flex gap-2">
+                            <select
+                                value={selectedBackup}
+                                onChange={(e) => setSelectedBackup(e.target.value)}
+                                className="flex-1 rounded-md border px-2 text-sm"
+                            >
+                                {backups.map(b => (
+                                    <option key={b.path} value={b.path}>
+                                        {new Date(b.created_at).toLocaleString()} ({Math.round(b.size / 1024)} KB)
+                                    </option>
+                                ))}
+                            </select>
+                            <Button variant="outline" onClick={() => handleRestore(false)}>
+                                {t('settings.data.restoreToFolder')}
+                            </Button>
+                            <Button variant="destructive" onClick={() => handleRestore(true)}>
+                                {t('settings.data.restoreOverVault')}
+                            </Button>
+                        </div>
+                    )}
+                </div>
+
+                {/* [NEW] git による履歴と同期 */}
+                <div className="grid gap-3 rounded-lg border p-4">
+                    <div className="flex items-center justify-between">
+                        <div className="space-y-0.5">
+                            <Label className="text-base">{t('settings.data.gitSync')}</Label>
+                            <p className="text-xs text-muted-foreground">{t('settings.data.gitSyncDesc')}</p>
+                        </div>
+                        <Switch
+                            checked={settings.git_sync_enabled}
+                            onCheckedChange={(val) => onUpdate("git_sync_enabled", val)}
+                        />
+                    </div>
+                    <div className="grid gap-2">
+                        <Label htmlFor="git-remote">{t('settings.data.gitRemote')}</Label>
+                        <Input
+                            id="git-remote"
+                            value={settings.git_remote ?? ""}
+                            onChange={(e) => onUpdate("git_remote", e.target.value.trim() || null)}
+                            placeholder="git@example.com:me/notes.git"
+                            className="font-mono text-sm"
+                        />
+                    </div>
+                    <div className="grid gap-2">
+                        <Label htmlFor="git-delay">{t('settings.data.gitCommitDelay')}</Label>
+                        <Input
+                            id="git-delay"
+                            type="number"
+                            min={0}
+                            value={settings.git_commit_delay_secs}
+                            onChange={(e) => onUpdate("git_commit_delay_secs", Math.max(0, parseInt(e.target.value, 10) || 0))}
+                            className="w-32"
+                        />
+                    </div>
+                    <div>
+                        <Button variant="outline" onClick={handleGitSync}>
+                            <GitBranch className="mr-2 h-4 w-4" /> {t('settings.data.gitSyncNow')}
+                        </Button>
+                    </div>
+                </div>
+
             </div>
 
             {/* --- インポートセクション --- */}
@@ -8,6 +8,20 @@ import { Page } from '@playwright/test';
  */
 export async function mockTauriAPI(page: Page) {
     await page.addInitScript(() => {
+        // キーワードが含まれているか適当に判定して返す
+        const mockSearchGroups = (query: string) => {
+            if (query && 'テスト'.includes(query)) {
+                return [{
+                    path: 'C:/test/note.md', seq: 1, context: 'note', tags: [], background_color: null, score: 1,
+                    hits: [{
+                        path: 'C:/test/note.md', line: 1, preview: 'これはテスト本文です',
+                        ranges: [{ start: 3, end: 6 }], before: [], after: [], score: 1
+                    }]
+                }];
+            }
+            return [];
+        };
+
         // --- IPC Handler Definition ---
         const handleIpc = (cmd: string, args: any) => {
             console.log('[Mock Tauri] IPC:', cmd, args);
@@ -82,13 +96,19 @@ updated: 2026-01-31
 
                 // 検索 (Release Test用)
                 case 'fusen_search_notes':
-                    // キーワードが含まれているか適当に判定して返す
-                    if (args.query && 'テスト'.includes(args.query)) {
-                        return [
-                            { path: 'C:/test/note.md', content: 'これはテスト本文です', modified: Date.now() }
-                        ];
-                    }
-                    return [];
+                    return mockSearchGroups(args.query);
+
+                // バックグラウンド検索: 結果はイベントで1回にまとめて返す
+                case 'fusen_start_search':
+                    setTimeout(() => {
+                        const handlers = listeners.get('fusen:search_results') || [];
+                        const payload = { request_id: args.requestId, groups: mockSearchGroups(args.query), done: true };
+                        handlers.forEach(h => h({ payload }));
+                    }, 0);
+                    return null;
+
+                case 'fusen_cancel_search':
+                    return null;
 
                 // アーカイブ (Release Test用)
                 case 'fusen_archive_note':
@@ -50,6 +50,49 @@ export const translations = {
         'settings.data.importButton': 'インポート実行',
         'settings.data.importPlaceholder': 'インポート元のフォルダパス...',
         'settings.data.basePathPlaceholder': 'フォルダを選択してください...',
+        'settings.data.trashRetention': 'ゴミ箱の保持期間（日）',
+        'settings.data.trashRetentionDesc': 'この日数を過ぎたゴミ箱のノートは完全に削除されます。0 で削除しません。',
+        'settings.data.emptyTrash': 'ゴミ箱を空にする',
+        'settings.data.emptyTrashConfirm': 'ゴミ箱のノートを完全に削除します。よろしいですか？',
+        'settings.data.emptyTrashDone': 'ノート {notes} 件、画像 {assets} 件を削除しました',
+        'settings.data.orphans': '未使用の画像',
+        'settings.data.orphansDesc': 'どのノート（ゴミ箱内も含む）からも参照されていない assets/ の画像を探して削除します。',
+        'settings.data.cleanOrphans': '未使用の画像を削除',
+        'settings.data.noOrphans': '未使用の画像はありません',
+        'settings.data.orphansConfirm': '未使用の画像が {count} 件（約 {kb} KB）あります。削除しますか？',
+        'settings.data.orphansDone': '画像 {count} 件を削除しました',
+        'settings.data.dedupeAssets': '重複した画像をまとめる',
+        'settings.data.dedupeConfirm': '同じ内容の画像を1つにまとめ、ノートのリンクを書き換えます。よろしいですか？',
+        'settings.data.dedupeDone': '重複 {removed} 件をまとめ（約 {kb} KB）、ノート {notes} 件のリンクを更新しました',
+        'settings.data.organizeAssets': '画像をノートごとのフォルダに整理',
+        'settings.data.organizeConfirm': '画像を各ノートの assets/<番号>/ フォルダへ移し、リンクを書き換えます。よろしいですか？',
+        'settings.data.organizeDone': 'ノート {notes} 件の画像を整理しました',
+        'settings.data.archiveLayout': 'タグ付きノートのアーカイブ先',
+        'settings.data.archiveFirstTag': '最初のタグのみ',
+        'settings.data.archiveAllTags': 'すべてのタグ',
+        'settings.data.archiveLayoutDesc': '「すべてのタグ」では本体を最初のタグのフォルダに置き、他のタグのフォルダにはリンクを作ります。',
+        'settings.data.backup': '自動バックアップ',
+        'settings.data.backupDesc': 'Vault を zip にまとめて保存します（指定した間隔と終了時、変更があったときだけ）。',
+        'settings.data.backupDir': 'バックアップの保存先',
+        'settings.data.backupInterval': '間隔（時間、0 = 終了時のみ）',
+        'settings.data.backupKeep': '残す数（0 = すべて）',
+        'settings.data.backupIncludeTrash': 'ゴミ箱も含める',
+        'settings.data.backupIncludeArchive': 'アーカイブも含める',
+        'settings.data.backupNow': '今すぐバックアップ',
+        'settings.data.backupDone': 'バックアップを作成しました: {name}',
+        'settings.data.restoreBackup': 'バックアップから復元',
+        'settings.data.noBackups': 'バックアップはありません',
+        'settings.data.restoreToFolder': '新しいフォルダへ復元',
+        'settings.data.restoreOverVault': 'Vault に上書き復元',
+        'settings.data.restoreConfirm': 'Vault を {name} の内容で上書きします（上書き前の状態もバックアップします）。よろしいですか？',
+        'settings.data.restoreDone': '{count} 件のファイルを復元しました',
+        'settings.data.gitSync': 'git で履歴を残す',
+        'settings.data.gitSyncDesc': '保存してしばらくしたら Vault を git にコミットし、リモートがあれば同期します（git が必要です）。衝突したノートは「(conflict)」付きの別のノートとして残ります。',
+        'settings.data.gitRemote': 'リモート（URL またはパス、空欄 = コミットのみ）',
+        'settings.data.gitCommitDelay': '最後の保存からコミットまで（秒）',
+        'settings.data.gitSyncNow': '今すぐ同期',
+        'settings.data.gitSyncDone': '同期しました',
+        'settings.data.gitSyncConflicts': '{count} 件の衝突を別のノートとして保存しました',
 
         // 設定画面 - フッター
         'settings.save': '設定完了',
@@ -79,6 +122,13 @@ export const translations = {
         'menu.colors.yellow': '黄',
         'menu.noTags': 'タグがありません',
 
+        // 保存時の競合
+        'conflict.title': 'ほかで変更されています',
+        'conflict.message': 'このメモは開いた後に外部（同期や別のアプリ）で書き換えられました。どうしますか？',
+        'conflict.reload': '読み直す（編集を捨てる）',
+        'conflict.overwrite': '上書きする',
+        'conflict.saveAsCopy': '別のメモとして保存',
+
         // 共通
         'common.loading': '読み込み中...',
         'common.save': '保存',
@@ -120,6 +170,49 @@ export const translations = {
         'settings.data.importButton': 'Import',
         'settings.data.importPlaceholder': 'Import folder path...',
         'settings.data.basePathPlaceholder': 'Select a folder...',
+        'settings.data.trashRetention': 'Trash retention (days)',
+        'settings.data.trashRetentionDesc': 'Notes older than this in the Trash are deleted permanently. 0 keeps them forever.',
+        'settings.data.emptyTrash': 'Empty Trash',
+        'settings.data.emptyTrashConfirm': 'Permanently delete all notes in the Trash?',
+        'settings.data.emptyTrashDone': 'Deleted {notes} notes and {assets} images',
+        'settings.data.orphans': 'Unused images',
+        'settings.data.orphansDesc': 'Find and delete images in assets/ folders that no note (including the Trash) refers to.',
+        'settings.data.cleanOrphans': 'Delete unused images',
+        'settings.data.noOrphans': 'No unused images found',
+        'settings.data.orphansConfirm': '{count} unused images (about {kb} KB) found. Delete them?',
+        'settings.data.orphansDone': 'Deleted {count} images',
+        'settings.data.dedupeAssets': 'Merge duplicate images',
+        'settings.data.dedupeConfirm': 'Merge images with identical content and update the links in your notes?',
+        'settings.data.dedupeDone': 'Merged {removed} duplicates (about {kb} KB) and updated links in {notes} notes',
+        'settings.data.organizeAssets': 'Move images into per-note folders',
+        'settings.data.organizeConfirm': 'Move images into each note\'s assets/<seq>/ folder and update the links?',
+        'settings.data.organizeDone': 'Organized images for {notes} notes',
+        'settings.data.archiveLayout': 'Archive tagged notes into',
+        'settings.data.archiveFirstTag': 'First tag only',
+        'settings.data.archiveAllTags': 'Every tag',
+        'settings.data.archiveLayoutDesc': 'With "Every tag", the note is kept in the first tag folder and the other tag folders get links to it.',
+        'settings.data.backup': 'Automatic backups',
+        'settings.data.backupDesc': 'Zip the vault on a schedule and at shutdown (only when something changed).',
+        'settings.data.backupDir': 'Backup folder',
+        'settings.data.backupInterval': 'Interval (hours, 0 = at shutdown only)',
+        'settings.data.backupKeep': 'Backups to keep (0 = all)',
+        'settings.data.backupIncludeTrash': 'Include the Trash',
+        'settings.data.backupIncludeArchive': 'Include archived notes',
+        'settings.data.backupNow': 'Back up now',
+        'settings.data.backupDone': 'Backup created: {name}',
+        'settings.data.restoreBackup': 'Restore from backup',
+        'settings.data.noBackups': 'No backups yet',
+        'settings.data.restoreToFolder': 'Restore into a new folder',
+        'settings.data.restoreOverVault': 'Restore over the vault',
+        'settings.data.restoreConfirm': 'Overwrite the vault with {name}? The current state is backed up first.',
+        'settings.data.restoreDone': 'Restored {count} files',
+        'settings.data.gitSync': 'Keep history with git',
+        'settings.data.gitSyncDesc': 'Commit the vault to git shortly after saving, and sync with a remote if one is set (requires git). Conflicting notes are kept as separate "(conflict)" notes.',
+        'settings.data.gitRemote': 'Remote (URL or path, empty = commit only)',
+        'settings.data.gitCommitDelay': 'Seconds after the last save before committing',
+        'settings.data.gitSyncNow': 'Sync now',
+        'settings.data.gitSyncDone': 'Synced',
+        'settings.data.gitSyncConflicts': 'Saved {count} conflicts as separate notes',
 
         // Settings - Footer
         'settings.save': 'Save Settings',
@@ -149,6 +242,13 @@ export const translations = {
         'menu.colors.yellow': 'Yellow',
         'menu.noTags': 'No tags',
 
+        // Save conflict
+        'conflict.title': 'Changed elsewhere',
+        'conflict.message': 'This note was changed outside this window (sync or another app) after it was opened. What do you want to do?',
+        'conflict.reload': 'Reload (discard my edits)',
+        'conflict.overwrite': 'Overwrite',
+        'conflict.saveAsCopy': 'Save as a new note',
+
         // Common
         'common.loading': 'Loading...',
         'common.save': 'Save',
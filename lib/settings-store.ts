@@ -10,6 +10,17 @@ export type AppSettings = {
     auto_start: boolean
     font_size: number
     sound_enabled: boolean
+    trash_retention_days: number
+    archive_layout: "firstTag" | "allTags"
+    backup_enabled: boolean
+    backup_dir: string | null
+    backup_interval_hours: number
+    backup_keep: number
+    backup_include_trash: boolean
+    backup_include_archive: boolean
+    git_sync_enabled: boolean
+    git_remote: string | null
+    git_commit_delay_secs: number
 }
 
 // デフォルト値
@@ -19,6 +30,17 @@ const DEFAULT_SETTINGS: AppSettings = {
     auto_start: false,
     font_size: 16,
     sound_enabled: true,
+    trash_retention_days: 30,
+    archive_layout: "firstTag",
+    backup_enabled: false,
+    backup_dir: null,
+    backup_interval_hours: 24,
+    backup_keep: 10,
+    backup_include_trash: false,
+    backup_include_archive: true,
+    git_sync_enabled: false,
+    git_remote: null,
+    git_commit_delay_secs: 60,
 }
 
 // --- 2. 倉庫番（保存ロジック） ---
@@ -65,6 +87,17 @@ export function useSettings() {
                         auto_start: parsed.auto_start ?? parsed.autoStart ?? DEFAULT_SETTINGS.auto_start,
                         font_size: parsed.font_size ?? parsed.fontSize ?? DEFAULT_SETTINGS.font_size,
                         sound_enabled: parsed.sound_enabled ?? parsed.soundEnabled ?? DEFAULT_SETTINGS.sound_enabled,
+                        trash_retention_days: parsed.trash_retention_days ?? parsed.trashRetentionDays ?? DEFAULT_SETTINGS.trash_retention_days,
+                        archive_layout: parsed.archive_layout ?? parsed.archiveLayout ?? DEFAULT_SETTINGS.archive_layout,
+                        backup_enabled: parsed.backup_enabled ?? DEFAULT_SETTINGS.backup_enabled,
+                        backup_dir: parsed.backup_dir ?? DEFAULT_SETTINGS.backup_dir,
+                        backup_interval_hours: parsed.backup_interval_hours ?? DEFAULT_SETTINGS.backup_interval_hours,
+                        backup_keep: parsed.backup_keep ?? DEFAULT_SETTINGS.backup_keep,
+                        backup_include_trash: parsed.backup_include_trash ?? DEFAULT_SETTINGS.backup_include_trash,
+                        backup_include_archive: parsed.backup_include_archive ?? DEFAULT_SETTINGS.backup_include_archive,
+                        git_sync_enabled: parsed.git_sync_enabled ?? DEFAULT_SETTINGS.git_sync_enabled,
+                        git_remote: parsed.git_remote ?? DEFAULT_SETTINGS.git_remote,
+                        git_commit_delay_secs: parsed.git_commit_delay_secs ?? DEFAULT_SETTINGS.git_commit_delay_secs,
                     }
                     setSettings(migrated)
                 }
@@ -80,6 +113,17 @@ export function useSettings() {
                     auto_start: loaded.auto_start,
                     font_size: loaded.font_size,
                     sound_enabled: loaded.sound_enabled,
+                    trash_retention_days: loaded.trash_retention_days,
+                    archive_layout: loaded.archive_layout,
+                    backup_enabled: loaded.backup_enabled,
+                    backup_dir: loaded.backup_dir,
+                    backup_interval_hours: loaded.backup_interval_hours,
+                    backup_keep: loaded.backup_keep,
+                    backup_include_trash: loaded.backup_include_trash,
+                    backup_include_archive: loaded.backup_include_archive,
+                    git_sync_enabled: loaded.git_sync_enabled,
+                    git_remote: loaded.git_remote,
+                    git_commit_delay_secs: loaded.git_commit_delay_secs,
                 }
                 setSettings({ ...DEFAULT_SETTINGS, ...normalized })
             }
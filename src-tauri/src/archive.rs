@@ -0,0 +1,307 @@
+// [NEW] アーカイブの一覧と復元
+// fusen_archive_note はノートを Archive/（タグ無し）か tags/<最初のタグ>/ へ移す。
+// ここではそれらを一覧し、Vault 直下へ付箋として戻す。
+// seq と位置・サイズはフロントマターごとそのまま（seq が埋まっていた場合だけ振り直す）。
+//
+// ArchiveLayout::AllTags のときは、他のタグのフォルダに本体へのリンクを置く。
+// リンクはシンボリックリンク（../<最初のタグ>/<ファイル名>）。作れない環境（Windows の権限など）では
+// 同じ相対パスを書いた "<ファイル名>.link" を置く。タグが変わったら sync_links で張り直す。
+// フォルダ名にできないタグ（".." や "a/b" など）にはフォルダもリンクも作らない (is_folder_tag)。
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::assets::AssetRefs;
+use crate::frontmatter::NoteFrontmatter;
+use crate::logic;
+use crate::state::{ArchiveLayout, NoteMeta};
+use crate::storage;
+
+const LINK_EXT: &str = "link";
+
+#[derive(serde::Serialize, Clone)]
+pub struct ArchivedNote {
+    pub path: String,
+    pub meta: NoteMeta,
+    /// "Archive" または "tags/<タグ>"
+    pub folder: String,
+}
+
+/// tags/<タグ>/ のフォルダ名にできるタグか（1階層の普通の名前だけ。tags/ の外や下の階層を指さない）
+pub fn is_folder_tag(tag: &str) -> bool {
+    let mut components = Path::new(tag).components();
+    matches!((components.next(), components.next()), (Some(Component::Normal(_)), None))
+        && !tag.contains(['/', '\\'])
+}
+
+// (表示名, フォルダ) の一覧: Archive/ と tags/ の下の各フォルダ
+fn archive_folders(vault: &Path) -> Vec<(String, PathBuf)> {
+    let mut folders = vec![("Archive".to_string(), vault.join("Archive"))];
+    if let Ok(entries) = fs::read_dir(vault.join("tags")) {
+        let mut tags: Vec<(String, PathBuf)> = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .map(|e| (format!("tags/{}", e.file_name().to_string_lossy()), e.path()))
+            .collect();
+        tags.sort();
+        folders.extend(tags);
+    }
+    folders
+}
+
+/// アーカイブされたノート。更新日の新しい順。
+pub fn list_archived(vault: &Path) -> Vec<ArchivedNote> {
+    let mut notes = Vec::new();
+    for (folder, dir) in archive_folders(vault) {
+        let Ok(entries) = fs::read_dir(&dir) else { continue };
+        for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+            // 他のタグフォルダへのリンクは本体と重複するので数えない
+            let is_file = fs::symlink_metadata(&path).is_ok_and(|m| m.is_file());
+            if !is_file || path.extension().and_then(|ext| ext.to_str()) != Some("md") {
+                continue;
+            }
+            let Ok(content) = fs::read_to_string(&path) else { continue };
+            let path = path.to_string_lossy().to_string();
+            notes.push(ArchivedNote { meta: logic::build_note_meta(&path, &content), path, folder: folder.clone() });
+        }
+    }
+    notes.sort_by(|a, b| b.meta.updated.cmp(&a.meta.updated).then(a.path.cmp(&b.path)));
+    notes
+}
+
+/// アーカイブから Vault 直下へ戻す。戻したパスを返す。
+pub fn unarchive(vault: &Path, archived_path: &Path) -> Result<PathBuf, String> {
+    let parent = archived_path.parent().ok_or("No parent")?;
+    if !archive_folders(vault).iter().any(|(_, dir)| dir == parent) {
+        return Err(format!("アーカイブ内のノートではありません: {}", archived_path.display()));
+    }
+    let original = vault.join(archived_path.file_name().ok_or("Invalid filename")?);
+    restore_to(vault, archived_path, &original)
+}
+
+/// アーカイブのノートを original へ戻す（リンクは消す）。埋まっていれば seq を振り直す。戻したパスを返す。
+pub fn restore_to(vault: &Path, archived_path: &Path, original: &Path) -> Result<PathBuf, String> {
+    remove_links(vault, archived_path)?;
+    let refs = AssetRefs::scan(vault);
+    storage::move_note_back(vault, archived_path, original, |asset| refs.shared_with_others(asset, archived_path), |_| {})
+}
+
+/// tags/<タグ>/ の直下に置かれたアーカイブ本体か（リンクは除く）
+pub fn is_tag_archived(vault: &Path, path: &Path) -> bool {
+    let in_tag_dir = path.parent().and_then(|p| p.parent()) == Some(vault.join("tags").as_path());
+    in_tag_dir && fs::symlink_metadata(path).is_ok_and(|m| m.is_file())
+}
+
+/// primary（tags/<タグ>/ の本体）のリンクを、今のタグに合わせて張り直す。
+/// AllTags なら本体のフォルダ以外の各タグへリンクを置き、不要になったリンクは消す。
+/// old_primary はリネーム前のパス（それを指すリンクも消す）。
+pub fn sync_links(vault: &Path, primary: &Path, old_primary: Option<&Path>, layout: ArchiveLayout) -> Result<(), String> {
+    let tags = match layout {
+        ArchiveLayout::AllTags => {
+            let content = fs::read_to_string(primary).map_err(|e| e.to_string())?;
+            NoteFrontmatter::from_content(&content).tags
+        }
+        ArchiveLayout::FirstTag => Vec::new(),
+    };
+    let primary_dir = primary.parent().ok_or("No parent")?;
+    let mut wanted: Vec<PathBuf> = tags
+        .iter()
+        .filter(|tag| is_folder_tag(tag))
+        .map(|tag| vault.join("tags").join(tag))
+        .filter(|dir| dir != primary_dir)
+        .collect();
+    // 同じタグが2回書かれていてもリンクは1つ
+    wanted.sort();
+    wanted.dedup();
+
+    let current = storage::normalize_path(primary);
+    let mut targets = vec![current.clone()];
+    targets.extend(old_primary.map(storage::normalize_path));
+    for (dir, link, target) in find_links(vault, &targets) {
+        if target == current && wanted.contains(&dir) {
+            // 今の本体を指していて、まだ必要なリンクは残す
+            wanted.retain(|w| w != &dir);
+        } else {
+            fs::remove_file(&link).map_err(|e| e.to_string())?;
+        }
+    }
+    for dir in wanted {
+        let tag = dir.file_name().ok_or("Invalid tag")?.to_string_lossy().to_string();
+        let tag_dir = storage::ensure_tag_dir(vault, &tag)?;
+        create_link(primary, &tag_dir)?;
+    }
+    Ok(())
+}
+
+/// path を指すリンクを全部消す（復元・削除の前に）
+pub fn remove_links(vault: &Path, path: &Path) -> Result<(), String> {
+    for (_, link, _) in find_links(vault, &[storage::normalize_path(path)]) {
+        fs::remove_file(&link).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// tags/ の下で targets のどれかを指すリンク: (フォルダ, リンク, 指す先)
+fn find_links(vault: &Path, targets: &[PathBuf]) -> Vec<(PathBuf, PathBuf, PathBuf)> {
+    let mut links = Vec::new();
+    let Ok(entries) = fs::read_dir(vault.join("tags")) else { return links };
+    for dir in entries.filter_map(|e| e.ok()).map(|e| e.path()).filter(|p| p.is_dir()) {
+        let Ok(files) = fs::read_dir(&dir) else { continue };
+        for link in files.filter_map(|e| e.ok()).map(|e| e.path()) {
+            if let Some(target) = link_target(&link).filter(|t| targets.contains(t)) {
+                links.push((dir.clone(), link, target));
+            }
+        }
+    }
+    links.sort();
+    links
+}
+
+// シンボリックリンクかポインタファイルなら、その指す先（正規化済み）
+fn link_target(link: &Path) -> Option<PathBuf> {
+    let meta = fs::symlink_metadata(link).ok()?;
+    let target = if meta.file_type().is_symlink() {
+        fs::read_link(link).ok()?
+    } else if link.extension().and_then(|ext| ext.to_str()) == Some(LINK_EXT) {
+        PathBuf::from(fs::read_to_string(link).ok()?.trim())
+    } else {
+        return None;
+    };
+    Some(storage::normalize_path(&link.parent()?.join(target)))
+}
+
+fn create_link(primary: &Path, tag_dir: &Path) -> Result<(), String> {
+    let name = primary.file_name().ok_or("Invalid filename")?.to_string_lossy().to_string();
+    let folder = primary.parent().and_then(|p| p.file_name()).ok_or("Invalid folder")?.to_string_lossy().to_string();
+    let target = format!("../{}/{}", folder, name);
+    let link = tag_dir.join(&name);
+    // 同名の別ノートがある場合もポインタファイルにする
+    let symlinked = fs::symlink_metadata(&link).is_err()
+        && crate::import::create_symlink(Path::new(&target), &link)
+            .map_err(|e| eprintln!("[Archive] Symlink failed ({}), writing a pointer file instead", e))
+            .is_ok();
+    if !symlinked {
+        storage::atomic_write(&tag_dir.join(format!("{}.{}", name, LINK_EXT)), target.as_bytes())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontmatter::NoteFrontmatter;
+
+    #[test]
+    fn list_covers_archive_and_tag_folders() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir_all(vault.join("Archive")).unwrap();
+        fs::create_dir_all(vault.join("tags/work")).unwrap();
+        fs::write(vault.join("Archive/0001_2026-01-01_A.md"), "a").unwrap();
+        fs::write(vault.join("tags/work/0002_2026-01-02_B.md"), "---\ntags: [work]\n---\n\nb").unwrap();
+        fs::write(vault.join("0003_2026-01-03_Live.md"), "live").unwrap();
+
+        let notes = list_archived(vault);
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[0].folder, "tags/work");
+        assert_eq!(notes[1].folder, "Archive");
+    }
+
+    #[test]
+    fn unarchive_keeps_seq_geometry_and_assets() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        let tag_dir = vault.join("tags/work");
+        fs::create_dir_all(tag_dir.join("assets/7")).unwrap();
+        fs::write(tag_dir.join("assets/7/a.png"), b"png").unwrap();
+        let archived = tag_dir.join("0007_2026-01-01_Memo.md");
+        let content = "---\nseq: 7\nx: 320\ny: 40\nwidth: 500\ntags: [work]\n---\n\nMemo\n![img](assets/7/a.png)";
+        fs::write(&archived, content).unwrap();
+
+        let restored = unarchive(vault, &archived).unwrap();
+        assert_eq!(restored, vault.join("0007_2026-01-01_Memo.md"));
+        assert_eq!(fs::read_to_string(&restored).unwrap(), content);
+        let fm = NoteFrontmatter::from_content(content);
+        assert_eq!((fm.seq, fm.x, fm.width), (Some(7), Some(320.0), Some(500.0)));
+        assert!(vault.join("assets/7/a.png").exists());
+        assert!(!archived.exists());
+
+        // アーカイブ外のノートは対象外
+        assert!(unarchive(vault, &restored).is_err());
+    }
+
+    #[test]
+    fn all_tags_layout_links_other_tag_folders() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        let primary = storage::ensure_tag_dir(vault, "a").unwrap().join("0001_2026-01-01_Memo.md");
+        fs::write(&primary, "---\ntags: [a, b, c]\n---\n\nMemo").unwrap();
+        assert!(is_tag_archived(vault, &primary));
+
+        sync_links(vault, &primary, None, ArchiveLayout::AllTags).unwrap();
+        let link_b = vault.join("tags/b/0001_2026-01-01_Memo.md");
+        assert_eq!(fs::read_to_string(&link_b).unwrap(), "---\ntags: [a, b, c]\n---\n\nMemo");
+        assert!(!is_tag_archived(vault, &link_b));
+        assert!(vault.join("tags/c/0001_2026-01-01_Memo.md").exists());
+        // リンクは一覧に重複して出ない
+        assert_eq!(list_archived(vault).len(), 1);
+
+        // タグが減ったらそのリンクだけ消える
+        fs::write(&primary, "---\ntags: [a, c]\n---\n\nMemo").unwrap();
+        sync_links(vault, &primary, None, ArchiveLayout::AllTags).unwrap();
+        assert!(fs::symlink_metadata(&link_b).is_err());
+        assert!(vault.join("tags/c/0001_2026-01-01_Memo.md").exists());
+
+        // リネームされたら古いリンクを張り替える
+        let renamed = vault.join("tags/a/0001_2026-01-01_Renamed.md");
+        fs::rename(&primary, &renamed).unwrap();
+        sync_links(vault, &renamed, Some(&primary), ArchiveLayout::AllTags).unwrap();
+        assert!(fs::symlink_metadata(vault.join("tags/c/0001_2026-01-01_Memo.md")).is_err());
+        assert!(vault.join("tags/c/0001_2026-01-01_Renamed.md").exists());
+
+        // FirstTag に戻したらリンクは全部消える
+        sync_links(vault, &renamed, None, ArchiveLayout::FirstTag).unwrap();
+        assert!(fs::read_dir(vault.join("tags/c")).unwrap().next().is_none());
+    }
+
+    #[test]
+    fn unsafe_and_duplicate_tags_get_no_extra_links() {
+        assert!(is_folder_tag("work"));
+        assert!(is_folder_tag("開発"));
+        for tag in ["..", ".", "a/b", "a\\b", "/abs", ""] {
+            assert!(!is_folder_tag(tag), "{}", tag);
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        let primary = storage::ensure_tag_dir(vault, "a").unwrap().join("0001_2026-01-01_Memo.md");
+        fs::write(&primary, "---\ntags: [a, b, b, .., x/y]\n---\n\nMemo").unwrap();
+
+        sync_links(vault, &primary, None, ArchiveLayout::AllTags).unwrap();
+        let names: Vec<String> = fs::read_dir(vault.join("tags/b")).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().to_string()).collect();
+        assert_eq!(names, vec!["0001_2026-01-01_Memo.md".to_string()]);
+        let mut folders: Vec<String> = fs::read_dir(vault.join("tags")).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().to_string()).collect();
+        folders.sort();
+        assert_eq!(folders, vec!["a".to_string(), "b".to_string()]);
+        assert!(!vault.join("0001_2026-01-01_Memo.md").exists());
+    }
+
+    #[test]
+    fn pointer_file_is_used_when_name_is_taken_and_removed_on_unarchive() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        let primary = storage::ensure_tag_dir(vault, "a").unwrap().join("0001_2026-01-01_Memo.md");
+        fs::write(&primary, "---\ntags: [a, b]\n---\n\nMemo").unwrap();
+        let other = storage::ensure_tag_dir(vault, "b").unwrap().join("0001_2026-01-01_Memo.md");
+        fs::write(&other, "another note").unwrap();
+
+        sync_links(vault, &primary, None, ArchiveLayout::AllTags).unwrap();
+        let pointer = vault.join("tags/b/0001_2026-01-01_Memo.md.link");
+        assert_eq!(fs::read_to_string(&pointer).unwrap(), "../a/0001_2026-01-01_Memo.md");
+        assert_eq!(fs::read_to_string(&other).unwrap(), "another note");
+
+        unarchive(vault, &primary).unwrap();
+        assert!(!pointer.exists());
+        assert!(other.exists());
+    }
+}
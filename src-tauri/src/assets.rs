@@ -0,0 +1,621 @@
+// [NEW] 画像 (assets/) の参照カウント
+// コピー&ペーストなどで複数のノートが同じ画像を埋め込むことがあるので、
+// ノートを移動・削除するときは「他に使っているノートが無い画像」だけを動かす。
+// ノートごとの参照先は .ore-no-fusen/assets.json に mtime + サイズと一緒に保存しておき、
+// 変わったノートだけを読み直す（index.rs と同じ方式）。
+//
+// 貼り付け・キャプチャした画像はノートのフォルダの assets/<seq>/ に、内容の SHA-256 から決まる
+// 名前 (<先頭16桁>.png) で保存する。どのノートの画像かはフォルダで分かる。
+// 同じ画像が assets/ 直下や他のノートのフォルダに既にあれば、新しく作らずにそれへリンクする
+// （同じスクリーンショットを何枚のノートに貼っても1ファイル）。
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+use crate::index;
+use crate::logic::{self, Effect};
+use crate::storage;
+
+const REFS_FILE: &str = "assets.json";
+const REFS_VERSION: u32 = 1;
+
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+struct RefsCache {
+    version: u32,
+    /// Vault からの相対パス -> エントリ
+    notes: BTreeMap<String, NoteRefs>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+struct NoteRefs {
+    mtime_ms: i64,
+    size: u64,
+    /// 参照している画像（Vault からの相対パス）
+    assets: Vec<String>,
+}
+
+/// 画像 -> 参照しているノート
+pub struct AssetRefs {
+    vault: PathBuf,
+    users: BTreeMap<String, Vec<String>>,
+}
+
+/// どのノートからも参照されていない画像
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct OrphanedAsset {
+    pub path: String,
+    pub size: u64,
+}
+
+fn cache_path(vault: &Path) -> PathBuf {
+    index::app_dir(vault).join(REFS_FILE)
+}
+
+// Vault からの相対パス（区切りは "/"）
+fn vault_key(vault: &Path, path: &Path) -> Option<String> {
+    let path = storage::normalize_path(path);
+    let rel = path.strip_prefix(storage::normalize_path(vault)).ok()?;
+    Some(rel.to_string_lossy().replace('\\', "/"))
+}
+
+// 隠しフォルダ以外を辿る（アーカイブのリンクはファイルとして数えない）
+fn walk(vault: &Path) -> impl Iterator<Item = walkdir::DirEntry> {
+    WalkDir::new(vault)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+}
+
+impl AssetRefs {
+    /// Vault 全体の参照を集める。変わったノートだけ読み直してキャッシュを更新する。
+    pub fn scan(vault: &Path) -> Self {
+        let mut cache = fs::read_to_string(cache_path(vault))
+            .ok()
+            .and_then(|s| serde_json::from_str::<RefsCache>(&s).ok())
+            .filter(|c| c.version == REFS_VERSION)
+            .unwrap_or_default();
+        let mut next = RefsCache { version: REFS_VERSION, notes: BTreeMap::new() };
+        let mut changed = cache.version != REFS_VERSION;
+
+        for entry in walk(vault) {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("md") {
+                continue;
+            }
+            let (Some(key), Ok(metadata)) = (vault_key(vault, path), entry.metadata()) else { continue };
+            let (mtime_ms, size) = (storage::mtime_ms(&metadata), metadata.len());
+
+            let cached = cache.notes.remove(&key).filter(|e| e.mtime_ms == mtime_ms && e.size == size);
+            let refs = cached.unwrap_or_else(|| {
+                changed = true;
+                let content = fs::read_to_string(path).unwrap_or_default();
+                let dir = path.parent().unwrap_or(vault);
+                let mut assets: Vec<String> = storage::asset_refs(&content)
+                    .into_iter()
+                    .filter_map(|rel| vault_key(vault, &dir.join(rel)))
+                    .collect();
+                assets.sort();
+                assets.dedup();
+                NoteRefs { mtime_ms, size, assets }
+            });
+            next.notes.insert(key, refs);
+        }
+        changed |= !cache.notes.is_empty();
+
+        if changed {
+            let saved = fs::create_dir_all(index::app_dir(vault))
+                .map_err(|e| e.to_string())
+                .and_then(|_| serde_json::to_string(&next).map_err(|e| e.to_string()))
+                .and_then(|json| storage::atomic_write(&cache_path(vault), json.as_bytes()));
+            if let Err(e) = saved {
+                crate::logger::log_warn(&format!("画像の参照キャッシュの保存に失敗しました: {}", e));
+            }
+        }
+
+        let mut users: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (note, refs) in next.notes {
+            for asset in refs.assets {
+                users.entry(asset).or_default().push(note.clone());
+            }
+        }
+        Self { vault: vault.to_path_buf(), users }
+    }
+
+    /// asset を参照しているノート（Vault からの相対パス）
+    pub fn users(&self, asset: &Path) -> &[String] {
+        vault_key(&self.vault, asset)
+            .and_then(|key| self.users.get(&key))
+            .map_or(&[], |v| v.as_slice())
+    }
+
+    /// note 以外にも asset を使っているノートがあるか
+    pub fn shared_with_others(&self, asset: &Path, note: &Path) -> bool {
+        let note = vault_key(&self.vault, note);
+        self.users(asset).iter().any(|user| Some(user) != note.as_ref())
+    }
+}
+
+/// assets/ フォルダにあって、どのノート（ゴミ箱内も含む）からも参照されていない画像
+pub fn list_orphans(vault: &Path) -> Vec<OrphanedAsset> {
+    let refs = AssetRefs::scan(vault);
+    let mut orphans: Vec<OrphanedAsset> = walk(vault)
+        .filter(|e| is_in_assets_dir(vault, e.path()) && refs.users(e.path()).is_empty())
+        .map(|e| OrphanedAsset {
+            path: e.path().to_string_lossy().to_string(),
+            size: e.metadata().map(|m| m.len()).unwrap_or(0),
+        })
+        .collect();
+    orphans.sort_by(|a, b| a.path.cmp(&b.path));
+    orphans
+}
+
+/// 一覧で選んだ画像を削除する。その間に参照されたものや assets/ 以外のファイルは消さない。
+/// 削除したパスを返す。
+pub fn delete_orphans(vault: &Path, paths: &[String]) -> Result<Vec<String>, String> {
+    let refs = AssetRefs::scan(vault);
+    let mut deleted = Vec::new();
+    for path in paths {
+        let asset = Path::new(path);
+        if !is_in_assets_dir(vault, asset) || !asset.is_file() {
+            eprintln!("[Assets] Skipped (not an asset in the vault): {}", path);
+            continue;
+        }
+        if !refs.users(asset).is_empty() {
+            eprintln!("[Assets] Skipped (now referenced): {}", path);
+            continue;
+        }
+        fs::remove_file(asset).map_err(|e| e.to_string())?;
+        deleted.push(path.clone());
+    }
+    Ok(deleted)
+}
+
+/// 内容から決まるファイル名（SHA-256 の先頭16桁 + 拡張子）
+pub fn content_name(bytes: &[u8], ext: &str) -> String {
+    let hash = format!("{:x}", Sha256::digest(bytes));
+    format!("{}.{}", &hash[..16], ext.to_lowercase())
+}
+
+/// 画像を内容のハッシュ名で assets_dir に保存する。同じ内容のファイルがあればそれを使う。
+/// 保存した（または既存の）ファイル名を返す。
+pub fn store(assets_dir: &Path, bytes: &[u8], ext: &str) -> Result<String, String> {
+    fs::create_dir_all(assets_dir).map_err(|e| e.to_string())?;
+    let name = content_name(bytes, ext);
+    let mut path = assets_dir.join(&name);
+    if path.exists() {
+        if fs::read(&path).ok().as_deref() == Some(bytes) {
+            return Ok(name);
+        }
+        // 先頭16桁だけ一致した別の画像
+        path = storage::unique_path(assets_dir, &name);
+    }
+    storage::atomic_write(&path, bytes)?;
+    Ok(path.file_name().unwrap_or_default().to_string_lossy().to_string())
+}
+
+/// ノートに貼る画像を assets/<seq>/ に保存し、本文に入れる Markdown を返す。
+/// 同じ内容の画像がこのフォルダの assets/ のどこかにあれば、それへのリンクを返す。
+pub fn store_for_note(note_path: &Path, bytes: &[u8], ext: &str) -> Result<String, String> {
+    let note_dir = note_path.parent().ok_or("Invalid note path")?;
+    let file_name = note_path.file_name().ok_or("Invalid note path")?.to_string_lossy().to_string();
+    let rel_dir = storage::note_assets_rel(logic::parse_filename(&file_name).0);
+    if let Some(rel) = find_stored(note_dir, &rel_dir, bytes, ext) {
+        return Ok(format!("![image]({})", rel));
+    }
+    let name = store(&note_dir.join(&rel_dir), bytes, ext)?;
+    Ok(format!("![image]({}/{})", rel_dir, name))
+}
+
+// note_dir/assets/ と、その下の各ノートのフォルダから同じ内容の画像を探す（自分のフォルダを優先）
+fn find_stored(note_dir: &Path, own_rel_dir: &str, bytes: &[u8], ext: &str) -> Option<String> {
+    let name = content_name(bytes, ext);
+    let assets_root = note_dir.join("assets");
+    let mut dirs: Vec<PathBuf> = fs::read_dir(&assets_root)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|e| e.path())
+        .collect();
+    dirs.sort();
+    dirs.insert(0, assets_root);
+    dirs.insert(0, note_dir.join(own_rel_dir));
+    dirs.into_iter()
+        .map(|dir| dir.join(&name))
+        .find(|path| fs::read(path).ok().as_deref() == Some(bytes))
+        .and_then(|path| vault_key(note_dir, &path))
+}
+
+/// RGBA8 の生画像を PNG にする（同じ画素なら同じバイト列になる）
+pub fn encode_png(width: u32, height: u32, rgba: Vec<u8>) -> Result<Vec<u8>, String> {
+    let buffer = image::RgbaImage::from_raw(width, height, rgba).ok_or("Failed to create image buffer")?;
+    let mut bytes = Vec::new();
+    buffer
+        .write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+/// 既存の assets/ をハッシュ名にそろえた結果
+#[derive(serde::Serialize, Clone, Debug, Default)]
+pub struct DedupeReport {
+    /// 同じ内容のファイルがあったので消したもの
+    pub removed: Vec<String>,
+    /// ハッシュ名に変えたファイルの数
+    pub renamed: usize,
+    /// リンクを書き換えたノート
+    pub notes: Vec<String>,
+    pub saved_bytes: u64,
+}
+
+/// Vault 内の各 assets/（と assets/<seq>/）フォルダの画像をハッシュ名に変え、
+/// 同じ内容のものを1つにまとめる。同じフォルダのノートの ![](assets/...) も書き換える。
+pub fn dedupe(vault: &Path) -> Result<DedupeReport, String> {
+    let mut report = DedupeReport::default();
+    let assets_dirs: Vec<PathBuf> = WalkDir::new(vault)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_dir())
+        .map(|e| e.into_path())
+        .filter(|p| p.file_name().is_some_and(|n| n == "assets") || p.parent().and_then(|p| p.file_name()).is_some_and(|n| n == "assets"))
+        .collect();
+
+    for assets_dir in assets_dirs {
+        // リンクは "assets/..." の相対パスなので、書き換えるのは assets/ と同じフォルダのノートだけ
+        let assets_root = if assets_dir.file_name().is_some_and(|n| n == "assets") {
+            assets_dir.as_path()
+        } else {
+            assets_dir.parent().unwrap_or(vault)
+        };
+        let note_dir = assets_root.parent().unwrap_or(vault);
+        let prefix = assets_dir.strip_prefix(note_dir).unwrap_or(&assets_dir).to_string_lossy().replace('\\', "/");
+
+        let mut files: Vec<PathBuf> = fs::read_dir(&assets_dir)
+            .map_err(|e| e.to_string())?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_ok_and(|t| t.is_file()))
+            .map(|e| e.path())
+            .filter(|p| is_image(p))
+            .collect();
+        files.sort();
+
+        // 画像の移動・削除とノートの書き換えを1つの Batch にして、途中で失敗したら全部戻す
+        let mut effects = Vec::new();
+        let mut renamed = 0;
+        let mut removed: Vec<(String, u64)> = Vec::new();
+        // このフォルダで移し先になる予定の名前 → その中身
+        let mut planned: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+        let mut renames: Vec<(String, String)> = Vec::new();
+        for file in files {
+            let old_name = file.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let ext = file.extension().unwrap_or_default().to_string_lossy().to_string();
+            let bytes = fs::read(&file).map_err(|e| e.to_string())?;
+            let name = content_name(&bytes, &ext);
+            if name == old_name {
+                continue;
+            }
+            let target = assets_dir.join(&name);
+            let existing = match planned.get(&name) {
+                Some(planned) => Some(planned.clone()),
+                None => fs::read(&target).ok(),
+            };
+            match existing {
+                None => {
+                    effects.push(Effect::MoveFile { from: file.to_string_lossy().to_string(), to: target.to_string_lossy().to_string() });
+                    planned.insert(name.clone(), bytes);
+                    renamed += 1;
+                }
+                Some(existing) if existing == bytes => {
+                    effects.push(Effect::DeleteFile { path: file.to_string_lossy().to_string() });
+                    removed.push((file.to_string_lossy().to_string(), bytes.len() as u64));
+                }
+                Some(_) => continue,
+            }
+            renames.push((old_name, name));
+        }
+        if renames.is_empty() {
+            continue;
+        }
+
+        let mut notes = Vec::new();
+        for entry in fs::read_dir(note_dir).map_err(|e| e.to_string())?.filter_map(|e| e.ok()) {
+            let note = entry.path();
+            let is_note = entry.file_type().is_ok_and(|t| t.is_file())
+                && note.extension().and_then(|ext| ext.to_str()) == Some("md");
+            if !is_note {
+                continue;
+            }
+            let content = fs::read_to_string(&note).map_err(|e| e.to_string())?;
+            let mut relinked = content.clone();
+            for (old, new) in &renames {
+                relinked = relinked.replace(&format!("]({}/{})", prefix, old), &format!("]({}/{})", prefix, new));
+            }
+            if relinked != content {
+                effects.push(Effect::WriteNote { path: note.to_string_lossy().to_string(), content: relinked });
+                notes.push(note.to_string_lossy().to_string());
+            }
+        }
+        crate::transaction::run(Effect::Batch(effects))?;
+
+        report.renamed += renamed;
+        for (path, size) in removed {
+            report.removed.push(path);
+            report.saved_bytes += size;
+        }
+        report.notes.extend(notes);
+    }
+    Ok(report)
+}
+
+/// 画像をノートごとのフォルダへ整理した結果
+#[derive(serde::Serialize, Clone, Debug, Default)]
+pub struct OrganizeReport {
+    /// リンクを書き換えたノート
+    pub notes: Vec<String>,
+    /// 移し先に同じ内容の画像が既にあったので消した元の画像
+    pub cleaned: Vec<String>,
+}
+
+/// assets/ 直下の画像を、それを使っているノートの assets/<seq>/ へ移し、リンクを書き換える（1回だけ実行する移行用）。
+/// 複数のノートが使っている画像は最初のノートのフォルダへ1回だけ移し、他のノートはそこへリンクし直す（コピーは作らない）。
+/// すでに assets/<seq>/ にある画像はそのまま。
+pub fn organize(vault: &Path) -> Result<OrganizeReport, String> {
+    let mut report = OrganizeReport::default();
+    // 移した画像: 元の場所 -> 移し先
+    let mut moved: BTreeMap<PathBuf, PathBuf> = BTreeMap::new();
+    let mut notes: Vec<PathBuf> = walk(vault)
+        .map(|e| e.into_path())
+        .filter(|p| p.extension().and_then(|ext| ext.to_str()) == Some("md"))
+        .collect();
+    notes.sort();
+
+    for note in notes {
+        let seq = logic::parse_filename(&note.file_name().unwrap_or_default().to_string_lossy()).0;
+        // seq が無いノートは assets/ 直下のまま
+        if seq <= 0 {
+            continue;
+        }
+        let content = fs::read_to_string(&note).map_err(|e| e.to_string())?;
+        let dir = note.parent().unwrap_or(vault);
+        let dest_dir = dir.join(storage::note_assets_rel(seq));
+        let mut relinked = content.clone();
+        let mut effects: Vec<Effect> = Vec::new();
+
+        let mut rels = storage::asset_refs(&content);
+        rels.sort();
+        rels.dedup();
+        for rel in rels {
+            // assets/ 直下の画像だけ
+            if rel.matches('/').count() != 1 {
+                continue;
+            }
+            let src = storage::normalize_path(&dir.join(&rel));
+            let dest = match moved.get(&src) {
+                Some(dest) => dest.clone(),
+                None => {
+                    if !src.is_file() {
+                        continue;
+                    }
+                    let name = src.file_name().unwrap_or_default().to_string_lossy().to_string();
+                    let existing = dest_dir.join(&name);
+                    let dest = if existing.exists() && fs::read(&existing).ok() == fs::read(&src).ok() {
+                        effects.push(Effect::DeleteFile { path: src.to_string_lossy().to_string() });
+                        report.cleaned.push(src.to_string_lossy().to_string());
+                        existing
+                    } else {
+                        let dest = storage::unique_path(&dest_dir, &name);
+                        effects.push(Effect::MoveFile {
+                            from: src.to_string_lossy().to_string(),
+                            to: dest.to_string_lossy().to_string(),
+                        });
+                        dest
+                    };
+                    moved.insert(src.clone(), dest.clone());
+                    dest
+                }
+            };
+            let Some(new_rel) = vault_key(dir, &dest) else { continue };
+            relinked = relinked.replace(&format!("]({})", rel), &format!("]({})", new_rel));
+        }
+
+        // 画像の移動とリンクの書き換えはノートごとにまとめて行う（途中で失敗したらそのノートの分は戻す）
+        let changed = relinked != content;
+        if changed {
+            effects.push(Effect::WriteNote { path: note.to_string_lossy().to_string(), content: relinked });
+        }
+        crate::transaction::run(Effect::Batch(effects))?;
+        if changed {
+            report.notes.push(note.to_string_lossy().to_string());
+        }
+    }
+    Ok(report)
+}
+
+fn is_image(path: &Path) -> bool {
+    let ext = path.extension().map(|ext| ext.to_string_lossy().to_lowercase()).unwrap_or_default();
+    matches!(ext.as_str(), "png" | "jpg" | "jpeg" | "gif" | "webp" | "svg")
+}
+
+// Vault 内の assets/ の下（assets/<seq>/ も含む）にあるファイルか
+fn is_in_assets_dir(vault: &Path, path: &Path) -> bool {
+    vault_key(vault, path).is_some_and(|key| {
+        let dirs: Vec<&str> = key.split('/').collect();
+        dirs[..dirs.len() - 1].contains(&"assets")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refs_count_every_note_and_follow_edits() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir_all(vault.join("assets")).unwrap();
+        fs::create_dir_all(vault.join("tags/work")).unwrap();
+        let a = vault.join("0001_2026-01-01_A.md");
+        let b = vault.join("0002_2026-01-01_B.md");
+        fs::write(&a, "A\n![x](assets/shared.png)\n![y](assets/own.png)").unwrap();
+        fs::write(&b, "B\n![x](assets/shared.png)").unwrap();
+        fs::write(vault.join("tags/work/0003_2026-01-01_C.md"), "C\n![x](assets/shared.png)").unwrap();
+
+        let refs = AssetRefs::scan(vault);
+        let shared = vault.join("assets/shared.png");
+        assert_eq!(refs.users(&shared).len(), 2);
+        assert!(refs.shared_with_others(&shared, &a));
+        assert!(!refs.shared_with_others(&vault.join("assets/own.png"), &a));
+        // 別フォルダの assets/ は別の画像
+        assert_eq!(refs.users(&vault.join("tags/work/assets/shared.png")).len(), 1);
+        assert!(cache_path(vault).exists());
+
+        // B から参照を外すと A だけになる
+        fs::write(&b, "B only text, longer than before").unwrap();
+        let refs = AssetRefs::scan(vault);
+        assert!(!refs.shared_with_others(&shared, &a));
+    }
+
+    #[test]
+    fn orphans_are_listed_and_only_unreferenced_ones_deleted() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir_all(vault.join("assets")).unwrap();
+        fs::create_dir_all(vault.join("Trash/assets")).unwrap();
+        fs::write(vault.join("assets/used.png"), b"u").unwrap();
+        fs::write(vault.join("assets/unused.png"), b"unused").unwrap();
+        fs::write(vault.join("Trash/assets/trashed.png"), b"t").unwrap();
+        fs::write(vault.join("0001_2026-01-01_A.md"), "![u](assets/used.png)").unwrap();
+        fs::write(vault.join("Trash/0002_2026-01-01_B.md"), "![t](assets/trashed.png)").unwrap();
+
+        let orphans = list_orphans(vault);
+        assert_eq!(orphans.len(), 1);
+        assert!(orphans[0].path.ends_with("unused.png"));
+        assert_eq!(orphans[0].size, 6);
+
+        let used = vault.join("assets/used.png").to_string_lossy().to_string();
+        let note = vault.join("0001_2026-01-01_A.md").to_string_lossy().to_string();
+        let deleted = delete_orphans(vault, &[orphans[0].path.clone(), used.clone(), note.clone()]).unwrap();
+        assert_eq!(deleted, vec![orphans[0].path.clone()]);
+        assert!(Path::new(&used).exists() && Path::new(&note).exists());
+    }
+
+    #[test]
+    fn store_reuses_identical_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let assets_dir = dir.path().join("assets");
+        let png = encode_png(1, 1, vec![255, 0, 0, 255]).unwrap();
+
+        let first = store(&assets_dir, &png, "png").unwrap();
+        let second = store(&assets_dir, &encode_png(1, 1, vec![255, 0, 0, 255]).unwrap(), "png").unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first, content_name(&png, "png"));
+        assert_eq!(first.len(), "0123456789abcdef.png".len());
+
+        let other = store(&assets_dir, &encode_png(1, 1, vec![0, 0, 255, 255]).unwrap(), "png").unwrap();
+        assert_ne!(first, other);
+        assert_eq!(fs::read_dir(&assets_dir).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn dedupe_merges_copies_and_rewrites_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir_all(vault.join("assets")).unwrap();
+        fs::write(vault.join("assets/pasted_1.png"), b"same").unwrap();
+        fs::write(vault.join("assets/pasted_2.png"), b"same").unwrap();
+        fs::write(vault.join("assets/other.jpg"), b"other").unwrap();
+        fs::write(vault.join("assets/notes.txt"), b"same").unwrap();
+        let a = vault.join("0001_2026-01-01_A.md");
+        let b = vault.join("0002_2026-01-01_B.md");
+        fs::write(&a, "A\n![x](assets/pasted_1.png)\n![y](assets/other.jpg)").unwrap();
+        fs::write(&b, "B\n![x](assets/pasted_2.png)").unwrap();
+        fs::write(vault.join("0003_2026-01-01_C.md"), "no images").unwrap();
+
+        let report = dedupe(vault).unwrap();
+        let same = content_name(b"same", "png");
+        let other = content_name(b"other", "jpg");
+        assert_eq!(report.removed.len(), 1);
+        assert_eq!(report.renamed, 2);
+        assert_eq!(report.notes.len(), 2);
+        assert_eq!(report.saved_bytes, 4);
+        assert_eq!(fs::read_to_string(&a).unwrap(), format!("A\n![x](assets/{})\n![y](assets/{})", same, other));
+        assert_eq!(fs::read_to_string(&b).unwrap(), format!("B\n![x](assets/{})", same));
+        assert!(vault.join("assets/notes.txt").exists());
+
+        // 2回目は何もしない
+        let again = dedupe(vault).unwrap();
+        assert_eq!((again.removed.len(), again.renamed, again.notes.len()), (0, 0, 0));
+    }
+
+    #[test]
+    fn dedupe_leaves_images_alone_when_a_note_cannot_be_rewritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir_all(vault.join("assets")).unwrap();
+        fs::write(vault.join("assets/pasted_1.png"), b"same").unwrap();
+        fs::write(vault.join("assets/pasted_2.png"), b"same").unwrap();
+        let a = vault.join("0001_2026-01-01_A.md");
+        fs::write(&a, "A\n![x](assets/pasted_1.png)").unwrap();
+        // 読めないノート（UTF-8 でない）
+        fs::write(vault.join("0002_2026-01-01_B.md"), [0xff, 0xfe, 0x00]).unwrap();
+
+        assert!(dedupe(vault).is_err());
+        assert!(vault.join("assets/pasted_1.png").exists());
+        assert!(vault.join("assets/pasted_2.png").exists());
+        assert!(!vault.join("assets").join(content_name(b"same", "png")).exists());
+        assert_eq!(fs::read_to_string(&a).unwrap(), "A\n![x](assets/pasted_1.png)");
+    }
+
+    #[test]
+    fn store_for_note_uses_the_seq_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let note = dir.path().join("0012_2026-01-01_Memo.md");
+        let png = encode_png(1, 1, vec![1, 2, 3, 255]).unwrap();
+
+        let markdown = store_for_note(&note, &png, "png").unwrap();
+        let name = content_name(&png, "png");
+        assert_eq!(markdown, format!("![image](assets/12/{})", name));
+        assert!(dir.path().join("assets/12").join(&name).exists());
+
+        // 同じ画像を別のノートに貼っても新しいファイルは作らない
+        let other = dir.path().join("0013_2026-01-01_Other.md");
+        assert_eq!(store_for_note(&other, &png, "png").unwrap(), markdown);
+        assert!(!dir.path().join("assets/13").exists());
+        let different = encode_png(1, 1, vec![9, 9, 9, 255]).unwrap();
+        assert!(store_for_note(&other, &different, "png").unwrap().starts_with("![image](assets/13/"));
+    }
+
+    #[test]
+    fn organize_moves_assets_into_note_folders() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir_all(vault.join("assets")).unwrap();
+        fs::write(vault.join("assets/pasted_1.png"), b"own").unwrap();
+        fs::write(vault.join("assets/shared.png"), b"shared").unwrap();
+        fs::write(vault.join("assets/unused.png"), b"unused").unwrap();
+        let a = vault.join("0001_2026-01-01_A.md");
+        let b = vault.join("0002_2026-01-01_B.md");
+        fs::write(&a, "A\n![x](assets/pasted_1.png)\n![s](assets/shared.png)").unwrap();
+        fs::write(&b, "B\n![s](assets/shared.png)").unwrap();
+
+        let report = organize(vault).unwrap();
+        assert_eq!(fs::read_to_string(&a).unwrap(), "A\n![x](assets/1/pasted_1.png)\n![s](assets/1/shared.png)");
+        // 共有の画像は1回だけ移し、コピーは作らない
+        assert_eq!(fs::read_to_string(&b).unwrap(), "B\n![s](assets/1/shared.png)");
+        assert_eq!(fs::read(vault.join("assets/1/shared.png")).unwrap(), b"shared");
+        assert!(!vault.join("assets/2").exists());
+        assert_eq!(report.notes.len(), 2);
+        assert!(report.cleaned.is_empty());
+        assert!(!vault.join("assets/pasted_1.png").exists() && !vault.join("assets/shared.png").exists());
+        // どのノートも使っていなかった画像はそのまま（未使用の画像の一覧で扱う）
+        assert!(vault.join("assets/unused.png").exists());
+
+        let again = organize(vault).unwrap();
+        assert!(again.notes.is_empty() && again.cleaned.is_empty());
+    }
+}
@@ -0,0 +1,343 @@
+// [NEW] Vault のバックアップ
+// Vault を zip にまとめて Settings.backup_dir に置く（ノート・画像・履歴。ゴミ箱とアーカイブは設定で選ぶ）。
+// backup_interval_hours ごとと終了時に、前回のバックアップより後に変更があったときだけ作り、
+// backup_keep を超えた古いものから消す。
+// 書き込み中は .partial の名前にしておき、書き終えてから名前を変える（作りかけを一覧に出さない）。
+// アーカイブのシンボリックリンクは含めない（ノートを保存すれば sync_links で張り直される）。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeZone};
+use tauri::{AppHandle, Manager, Runtime};
+use walkdir::WalkDir;
+
+use crate::index;
+use crate::state::{AppState, Settings};
+use crate::storage;
+
+const FILE_PREFIX: &str = "ore-no-fusen-";
+const NAME_FORMAT: &str = "%Y%m%d-%H%M%S";
+const PARTIAL_EXT: &str = "zip.partial";
+// 予定時刻を過ぎたかの確認間隔（起動時にも1回確認する）
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+pub struct BackupOptions {
+    pub dir: PathBuf,
+    pub interval_hours: u32,
+    pub keep: u32,
+    pub include_trash: bool,
+    pub include_archive: bool,
+}
+
+impl BackupOptions {
+    pub fn from_settings(settings: &Settings) -> Result<Self, String> {
+        let dir = settings.backup_dir.as_deref().filter(|d| !d.is_empty()).ok_or("バックアップの保存先が設定されていません")?;
+        Ok(Self {
+            dir: PathBuf::from(dir),
+            interval_hours: settings.backup_interval_hours,
+            keep: settings.backup_keep,
+            include_trash: settings.backup_include_trash,
+            include_archive: settings.backup_include_archive,
+        })
+    }
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct BackupInfo {
+    pub path: String,
+    pub name: String,
+    pub created_at: String,
+    pub size: u64,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct RestoreReport {
+    /// 展開したフォルダ
+    pub target: String,
+    /// 展開したファイル
+    pub files: Vec<String>,
+    /// Vault に上書きする前に作ったバックアップ
+    pub safety_backup: Option<String>,
+}
+
+// ファイル名の日時 (ore-no-fusen-YYYYMMDD-HHMMSS[ (n)].zip)
+fn created_at(name: &str) -> Option<DateTime<FixedOffset>> {
+    let stamp = name.strip_prefix(FILE_PREFIX)?.strip_suffix(".zip")?.get(..15)?;
+    let naive = NaiveDateTime::parse_from_str(stamp, NAME_FORMAT).ok()?;
+    Local.from_local_datetime(&naive).earliest().map(|t| t.fixed_offset())
+}
+
+/// バックアップの一覧。新しい順。
+pub fn list(dir: &Path) -> Vec<BackupInfo> {
+    let Ok(entries) = fs::read_dir(dir) else { return Vec::new() };
+    let mut backups: Vec<(DateTime<FixedOffset>, BackupInfo)> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            let time = created_at(&name)?;
+            let size = e.metadata().ok().filter(|m| m.is_file())?.len();
+            let info = BackupInfo {
+                path: e.path().to_string_lossy().to_string(),
+                created_at: time.to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+                name,
+                size,
+            };
+            Some((time, info))
+        })
+        .collect();
+    backups.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.name.cmp(&a.1.name)));
+    backups.into_iter().map(|(_, info)| info).collect()
+}
+
+/// Vault を zip にまとめる
+pub fn create(vault: &Path, options: &BackupOptions, now: DateTime<FixedOffset>) -> Result<BackupInfo, String> {
+    fs::create_dir_all(&options.dir).map_err(|e| e.to_string())?;
+    let name = format!("{}{}.zip", FILE_PREFIX, now.format(NAME_FORMAT));
+    let path = storage::unique_path(&options.dir, &name);
+    let partial = path.with_extension(PARTIAL_EXT);
+
+    if let Err(e) = write_zip(vault, options, &partial) {
+        let _ = fs::remove_file(&partial);
+        return Err(format!("バックアップを作成できません: {}", e));
+    }
+    fs::rename(&partial, &path).map_err(|e| e.to_string())?;
+    Ok(BackupInfo {
+        name: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+        created_at: now.to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        size: fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+        path: path.to_string_lossy().to_string(),
+    })
+}
+
+fn write_zip(vault: &Path, options: &BackupOptions, dest: &Path) -> Result<(), String> {
+    let file = fs::File::create(dest).map_err(|e| e.to_string())?;
+    let mut zip = zip::ZipWriter::new(file);
+    let file_options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for path in files(vault, options) {
+        let rel = path.strip_prefix(vault).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+        zip.start_file(rel, file_options).map_err(|e| e.to_string())?;
+        let mut src = fs::File::open(&path).map_err(|e| e.to_string())?;
+        std::io::copy(&mut src, &mut zip).map_err(|e| e.to_string())?;
+    }
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// バックアップに含めるファイル
+fn files<'a>(vault: &'a Path, options: &'a BackupOptions) -> impl Iterator<Item = PathBuf> + 'a {
+    WalkDir::new(vault)
+        .into_iter()
+        .filter_entry(move |e| is_included(vault, e.path(), options))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+}
+
+fn is_included(vault: &Path, path: &Path, options: &BackupOptions) -> bool {
+    // 保存先を Vault の中にした場合、バックアップ自体は含めない
+    if path == options.dir {
+        return false;
+    }
+    let Ok(rel) = path.strip_prefix(vault) else { return false };
+    let name = rel.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    if name.ends_with(".fusen-tmp") || name.ends_with(".fusen-del") {
+        return false;
+    }
+    let parts: Vec<String> = rel.components().map(|c| c.as_os_str().to_string_lossy().to_string()).collect();
+    match parts.first().map(String::as_str) {
+        None => true,
+        // アプリの隠しフォルダは履歴だけ（インデックスは作り直せる）
+        Some(index::APP_DIR) => parts.get(1).map_or(true, |p| p == "history"),
+        Some(".git") => false,
+        Some("Trash") => options.include_trash,
+        Some("Archive") | Some("tags") => options.include_archive,
+        Some(_) => true,
+    }
+}
+
+/// keep を超えた古いバックアップを消す。消したパスを返す。
+pub fn prune(dir: &Path, keep: u32) -> Vec<String> {
+    if keep == 0 {
+        return Vec::new();
+    }
+    list(dir)
+        .into_iter()
+        .skip(keep as usize)
+        .filter(|b| fs::remove_file(&b.path).is_ok())
+        .map(|b| b.path)
+        .collect()
+}
+
+/// 作る時期か。前回のバックアップより後に変更が無ければ作らない。
+/// at_exit なら間隔に関係なく、変更があれば作る。
+pub fn is_due(vault: &Path, options: &BackupOptions, now: DateTime<FixedOffset>, at_exit: bool) -> bool {
+    let Some(last) = list(&options.dir).first().and_then(|b| DateTime::parse_from_rfc3339(&b.created_at).ok()) else {
+        return true;
+    };
+    if !at_exit && (options.interval_hours == 0 || now - last < chrono::Duration::hours(options.interval_hours as i64)) {
+        return false;
+    }
+    let last_ms = last.timestamp_millis();
+    files(vault, options).any(|p| fs::metadata(&p).is_ok_and(|m| storage::mtime_ms(&m) >= last_ms))
+}
+
+/// zip を target に展開する。展開したファイルを返す。
+pub fn extract(zip_path: &Path, target: &Path) -> Result<Vec<String>, String> {
+    let file = fs::File::open(zip_path).map_err(|e| e.to_string())?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| e.to_string())?;
+    let mut files = Vec::new();
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(|e| e.to_string())?;
+        // Vault の外を指すパス (../ など) は展開しない
+        let Some(rel) = entry.enclosed_name() else {
+            eprintln!("[Backup] Skipped unsafe entry: {}", entry.name());
+            continue;
+        };
+        let dest = target.join(rel);
+        if entry.is_dir() {
+            fs::create_dir_all(&dest).map_err(|e| e.to_string())?;
+            continue;
+        }
+        fs::create_dir_all(dest.parent().ok_or("No parent")?).map_err(|e| e.to_string())?;
+        let mut content = Vec::new();
+        std::io::Read::read_to_end(&mut entry, &mut content).map_err(|e| e.to_string())?;
+        storage::atomic_write(&dest, &content)?;
+        files.push(dest.to_string_lossy().to_string());
+    }
+    Ok(files)
+}
+
+/// 新しい（空の）フォルダへ戻す
+pub fn restore_to_folder(zip_path: &Path, target: &Path) -> Result<RestoreReport, String> {
+    let is_empty = fs::read_dir(target).map(|mut entries| entries.next().is_none()).unwrap_or(true);
+    if !is_empty {
+        return Err(format!("空のフォルダを指定してください: {}", target.display()));
+    }
+    fs::create_dir_all(target).map_err(|e| e.to_string())?;
+    let files = extract(zip_path, target)?;
+    Ok(RestoreReport { target: target.to_string_lossy().to_string(), files, safety_backup: None })
+}
+
+/// Vault に上書きして戻す。先に今の Vault をバックアップしておく。
+/// バックアップより後に作ったノートは消さずに残る。
+pub fn restore_over_vault(vault: &Path, zip_path: &Path, options: &BackupOptions, now: DateTime<FixedOffset>) -> Result<RestoreReport, String> {
+    let safety = create(vault, options, now)?;
+    let files = extract(zip_path, vault)?;
+    Ok(RestoreReport { target: vault.to_string_lossy().to_string(), files, safety_backup: Some(safety.path) })
+}
+
+/// 時期が来ていればバックアップを作って古いものを消す（自動バックアップが有効なときだけ）
+pub fn run_if_due<R: Runtime>(app: &AppHandle<R>, at_exit: bool) {
+    let Ok(settings) = storage::load_settings() else { return };
+    if !settings.backup_enabled {
+        return;
+    }
+    let Ok(options) = BackupOptions::from_settings(&settings) else { return };
+    let base_path = {
+        let state = app.state::<Mutex<AppState>>();
+        let app_state = state.lock().unwrap();
+        app_state.base_path.clone().or(app_state.folder_path.clone())
+    };
+    let Some(base) = base_path else { return };
+    let vault = Path::new(&base);
+    let now = Local::now().fixed_offset();
+    if !is_due(vault, &options, now, at_exit) {
+        return;
+    }
+    match create(vault, &options, now) {
+        Ok(info) => {
+            let removed = prune(&options.dir, options.keep);
+            crate::logger::log_info(&format!("[Backup] バックアップを作成しました: {} (古いもの {} 件を削除)", info.name, removed.len()));
+        }
+        Err(e) => crate::logger::log_warn(&format!("[Backup] {}", e)),
+    }
+}
+
+/// 起動時と CHECK_INTERVAL ごとに run_if_due を呼ぶスレッドを開始する
+pub fn start_scheduler<R: Runtime>(app: AppHandle<R>) {
+    std::thread::spawn(move || loop {
+        run_if_due(&app, false);
+        std::thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u32) -> DateTime<FixedOffset> {
+        Local.with_ymd_and_hms(2026, 3, 1, hour, 0, 0).unwrap().fixed_offset()
+    }
+
+    fn options(dir: &Path) -> BackupOptions {
+        BackupOptions { dir: dir.to_path_buf(), interval_hours: 6, keep: 2, include_trash: false, include_archive: true }
+    }
+
+    fn vault_with_notes(vault: &Path) {
+        fs::create_dir_all(vault.join("assets/1")).unwrap();
+        fs::create_dir_all(vault.join("Trash")).unwrap();
+        fs::create_dir_all(vault.join("tags/work")).unwrap();
+        fs::create_dir_all(index::app_dir(vault).join("history/1")).unwrap();
+        fs::write(vault.join("0001_2026-03-01_Memo.md"), "Memo").unwrap();
+        fs::write(vault.join("assets/1/a.png"), b"png").unwrap();
+        fs::write(vault.join("Trash/0002_2026-03-01_Old.md"), "Old").unwrap();
+        fs::write(vault.join("tags/work/0003_2026-03-01_Work.md"), "Work").unwrap();
+        fs::write(index::app_dir(vault).join("history/1/1.md"), "Mem").unwrap();
+        fs::write(index::app_dir(vault).join("index.json"), "{}").unwrap();
+    }
+
+    #[test]
+    fn backup_roundtrip_into_new_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path().join("vault");
+        vault_with_notes(&vault);
+        let opts = options(&dir.path().join("backups"));
+
+        let info = create(&vault, &opts, at(9)).unwrap();
+        assert_eq!(info.name, "ore-no-fusen-20260301-090000.zip");
+        assert_eq!(list(&opts.dir).len(), 1);
+
+        let target = dir.path().join("restored");
+        let report = restore_to_folder(Path::new(&info.path), &target).unwrap();
+        assert_eq!(report.files.len(), 4);
+        assert_eq!(fs::read_to_string(target.join("0001_2026-03-01_Memo.md")).unwrap(), "Memo");
+        assert!(target.join("assets/1/a.png").exists());
+        assert!(target.join("tags/work/0003_2026-03-01_Work.md").exists());
+        assert!(index::app_dir(&target).join("history/1/1.md").exists());
+        assert!(!target.join("Trash").exists());
+        assert!(!index::app_dir(&target).join("index.json").exists());
+
+        // 空でないフォルダには戻さない
+        assert!(restore_to_folder(Path::new(&info.path), &target).is_err());
+    }
+
+    #[test]
+    fn schedule_skips_unchanged_vault_and_prunes_old_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path().join("vault");
+        vault_with_notes(&vault);
+        let opts = options(&dir.path().join("backups"));
+        assert!(is_due(&vault, &opts, at(9), false));
+
+        for hour in [1, 2, 3] {
+            create(&vault, &opts, at(hour)).unwrap();
+        }
+        let removed = prune(&opts.dir, opts.keep);
+        assert_eq!(removed.len(), 1);
+        let names: Vec<String> = list(&opts.dir).into_iter().map(|b| b.name).collect();
+        assert_eq!(names, vec!["ore-no-fusen-20260301-030000.zip", "ore-no-fusen-20260301-020000.zip"]);
+
+        // 間隔が経っていない
+        assert!(!is_due(&vault, &opts, at(4), false));
+        // 間隔は経ったが、ファイルの更新日時（今）は前回のバックアップ (3時) より後なので作る
+        assert!(is_due(&vault, &opts, at(10), false));
+        // 前回のバックアップより後に変更が無ければ作らない
+        let future = Local::now().fixed_offset() + chrono::Duration::hours(1);
+        create(&vault, &opts, future).unwrap();
+        assert!(!is_due(&vault, &opts, future + chrono::Duration::hours(7), false));
+        assert!(!is_due(&vault, &opts, future, true));
+    }
+}
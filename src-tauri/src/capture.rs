@@ -1,135 +1,342 @@
-use std::path::PathBuf;
-use std::process::Command;
-use std::thread;
-use std::time::Duration;
-use std::fs;
-use tauri::{State, command};
-use std::sync::Mutex;
-use arboard::Clipboard;
-use image::ImageBuffer;
-use crate::state::AppState;
+use std::path::Path;
+use tauri::command;
 
-#[command]
-pub async fn fusen_capture_screen(state: State<'_, Mutex<AppState>>, note_seq: i32) -> Result<String, String> {
-    eprintln!("[CAPTURE_DEBUG] === Starting screen capture for note seq: {} ===", note_seq);
-    
-    // 1. Resolve Base Path
-    let app_state = state.lock().unwrap();
-    let base_path_str = app_state.base_path.clone()
-        .or(app_state.folder_path.clone())
-        .ok_or("Base path not set")?;
-    drop(app_state); // Unlock early
-    
-    let base_path = PathBuf::from(&base_path_str);
-    eprintln!("[CAPTURE_DEBUG] Base path: {:?}", base_path);
-    
-    // Assets directory: <base>/assets
-    let assets_dir = base_path.join("assets");
-    eprintln!("[CAPTURE_DEBUG] Assets directory: {:?}", assets_dir);
-    
-    if !assets_dir.exists() {
-        eprintln!("[CAPTURE_DEBUG] Assets dir does not exist, creating...");
-        fs::create_dir_all(&assets_dir).map_err(|e| format!("Failed to create assets dir: {}", e))?;
+// --- キャプチャ範囲 ---
+
+/// フロントエンドから `{ mode: "region", x, y, width, height }` のように指定する。
+/// 座標はすべて仮想デスクトップ（全モニタを並べた空間）の論理ピクセル（OS の拡大率を適用した後の座標。
+/// screenshots の DisplayInfo と同じ）。取り込んだ画像はモニタの物理ピクセルになる。
+#[derive(serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "mode", rename_all = "camelCase")]
+pub enum CaptureTarget {
+    /// 全モニタをつなげた画像
+    FullScreen,
+    /// 1枚のモニタ (Screen::all() の並び順)
+    Monitor { index: usize },
+    /// 指定した矩形（モニタをまたいでもよい）
+    Region { x: i32, y: i32, width: u32, height: u32 },
+    /// ユーザーに範囲を選ばせる（Windows: Snipping Tool）
+    Interactive,
+}
+
+impl CaptureTarget {
+    // 指定が無い場合: Windows は従来どおり Snipping Tool、それ以外は全画面
+    fn platform_default() -> Self {
+        if cfg!(target_os = "windows") {
+            CaptureTarget::Interactive
+        } else {
+            CaptureTarget::FullScreen
+        }
     }
-    
-    // 2. Prepare Clipboard
-    {
-        eprintln!("[CAPTURE_DEBUG] Clearing clipboard...");
-        let mut clipboard = Clipboard::new().map_err(|e| format!("Failed to access clipboard: {}", e))?;
-        let _ = clipboard.clear();
-    } // clipboard is dropped here, releasing any potential locks
-    
-    // 3. Launch Snipping Tool (ms-screenclip:)
+}
+
+/// RGBA8 の生画像
+pub struct CapturedImage {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+pub trait CaptureBackend {
+    fn capture(&self, target: &CaptureTarget) -> Result<CapturedImage, String>;
+}
+
+/// OSごとの標準バックエンド
+pub fn default_backend() -> Box<dyn CaptureBackend + Send> {
     #[cfg(target_os = "windows")]
     {
-        eprintln!("[CAPTURE_DEBUG] Launching Snipping Tool via ms-screenclip:");
-        Command::new("explorer")
-            .arg("ms-screenclip:")
-            .spawn()
-            .map_err(|e| format!("Failed to launch Snipping Tool: {}", e))?;
+        Box::new(SnippingToolBackend)
     }
     #[cfg(not(target_os = "windows"))]
     {
-        return Err("Screen capture is only supported on Windows".to_string());
+        Box::new(ScreenshotsBackend)
     }
-    
-    // 4. Loop and Wait for Image in Clipboard
-    let start_time = std::time::Instant::now();
-    let timeout = Duration::from_secs(60); 
-    
-    let mut caught_image: Option<arboard::ImageData> = None;
-    
-    eprintln!("[CAPTURE_DEBUG] Waiting for clipboard update (timeout: 60s)...");
-    let mut poll_count = 0;
-    while start_time.elapsed() < timeout {
-        thread::sleep(Duration::from_millis(200));
-        poll_count += 1;
-        
-        if poll_count % 10 == 0 {
-            eprintln!("[CAPTURE_DEBUG] Still waiting... ({}s elapsed)", start_time.elapsed().as_secs());
+}
+
+// --- screenshots クレートによるプロセス内キャプチャ（全OS共通） ---
+
+pub struct ScreenshotsBackend;
+
+impl CaptureBackend for ScreenshotsBackend {
+    fn capture(&self, target: &CaptureTarget) -> Result<CapturedImage, String> {
+        let screens = screenshots::Screen::all().map_err(|e| format!("Failed to enumerate screens: {}", e))?;
+        if screens.is_empty() {
+            return Err("No screen found".to_string());
         }
-        
-        if let Ok(mut clipboard) = Clipboard::new() {
-             match clipboard.get_image() {
-                Ok(img) => {
-                    eprintln!("[CAPTURE_DEBUG] ✓ Image found in clipboard! Size: {}x{}", img.width, img.height);
-                    caught_image = Some(img);
-                    break;
-                },
-                Err(e) => {
-                    // 通常はエラーが正常（画像がまだない）
-                    if poll_count == 1 {
-                        eprintln!("[CAPTURE_DEBUG] No image yet (expected). Error: {}", e);
+
+        match target {
+            CaptureTarget::FullScreen => {
+                let mut parts = Vec::new();
+                for screen in &screens {
+                    let info = screen.display_info;
+                    parts.push(Part { x: info.x, y: info.y, scale: info.scale_factor, image: capture_screen(screen)? });
+                }
+                Ok(compose(parts))
+            }
+            CaptureTarget::Monitor { index } => {
+                let screen = screens.get(*index).ok_or_else(|| format!("Monitor {} not found", index))?;
+                capture_screen(screen)
+            }
+            CaptureTarget::Region { x, y, width, height } => {
+                let rect = Rect { x: *x, y: *y, width: *width, height: *height };
+                let mut parts = Vec::new();
+                for screen in &screens {
+                    let info = screen.display_info;
+                    let display = Rect { x: info.x, y: info.y, width: info.width, height: info.height };
+                    if let Some(area) = intersect(&rect, &display) {
+                        // capture_area はモニタ内の論理座標で指定し、物理ピクセルの画像を返す
+                        let img = screen
+                            .capture_area(area.x - display.x, area.y - display.y, area.width, area.height)
+                            .map_err(|e| format!("Failed to capture area: {}", e))?;
+                        parts.push(Part { x: area.x, y: area.y, scale: info.scale_factor, image: from_screenshot(img) });
                     }
                 }
+                if parts.is_empty() {
+                    return Err("Region is outside of all screens".to_string());
+                }
+                Ok(compose(parts))
             }
+            CaptureTarget::Interactive => Err("Interactive capture is only supported on Windows".to_string()),
+        }
+    }
+}
+
+fn capture_screen(screen: &screenshots::Screen) -> Result<CapturedImage, String> {
+    screen
+        .capture()
+        .map(from_screenshot)
+        .map_err(|e| format!("Failed to capture screen: {}", e))
+}
+
+// screenshots は別バージョンの image を使っているので生バイトで受け渡す
+fn from_screenshot(img: screenshots::image::RgbaImage) -> CapturedImage {
+    CapturedImage {
+        width: img.width(),
+        height: img.height(),
+        rgba: img.into_raw(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rect {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+fn intersect(a: &Rect, b: &Rect) -> Option<Rect> {
+    let left = a.x.max(b.x);
+    let top = a.y.max(b.y);
+    let right = (a.x + a.width as i32).min(b.x + b.width as i32);
+    let bottom = (a.y + a.height as i32).min(b.y + b.height as i32);
+    if right <= left || bottom <= top {
+        return None;
+    }
+    Some(Rect { x: left, y: top, width: (right - left) as u32, height: (bottom - top) as u32 })
+}
+
+/// 1枚のモニタから取り込んだ画像。x, y は論理座標、image は物理ピクセル (= 論理サイズ × scale)
+struct Part {
+    x: i32,
+    y: i32,
+    scale: f32,
+    image: CapturedImage,
+}
+
+// 複数の画像を仮想デスクトップ座標に並べて1枚にする（隙間は透明）。
+// 位置は論理座標なので、いちばん大きい拡大率にそろえて物理ピクセルに直し、
+// 拡大率の小さいモニタの画像はその大きさに拡大してから並べる。
+fn compose(parts: Vec<Part>) -> CapturedImage {
+    if parts.len() == 1 {
+        return parts.into_iter().next().map(|part| part.image).unwrap();
+    }
+    let scale = parts.iter().map(|p| p.scale).fold(1.0_f32, f32::max);
+    let to_px = |logical: f32| (logical * scale).round() as u32;
+    let min_x = parts.iter().map(|p| p.x).min().unwrap_or(0);
+    let min_y = parts.iter().map(|p| p.y).min().unwrap_or(0);
+
+    // (物理ピクセルでの位置, 拡大率をそろえた画像)
+    let placed: Vec<(u32, u32, CapturedImage)> = parts
+        .into_iter()
+        .map(|p| {
+            let own_scale = if p.scale > 0.0 { p.scale } else { 1.0 };
+            let width = to_px(p.image.width as f32 / own_scale);
+            let height = to_px(p.image.height as f32 / own_scale);
+            (to_px((p.x - min_x) as f32), to_px((p.y - min_y) as f32), resize(p.image, width, height))
+        })
+        .collect();
+    let width = placed.iter().map(|(x, _, img)| x + img.width).max().unwrap_or(0);
+    let height = placed.iter().map(|(_, y, img)| y + img.height).max().unwrap_or(0);
+
+    let mut rgba = vec![0u8; (width * height * 4) as usize];
+    for (x, y, img) in &placed {
+        let row_len = img.width as usize * 4;
+        for row in 0..img.height as usize {
+            let src = row * row_len;
+            let dst = ((*y as usize + row) * width as usize + *x as usize) * 4;
+            rgba[dst..dst + row_len].copy_from_slice(&img.rgba[src..src + row_len]);
         }
     }
-    
-    let img_data = caught_image.ok_or_else(|| {
-        eprintln!("[CAPTURE_DEBUG] ✗ Capture timed out or no image selected");
-        "Capture timed out or no image selected".to_string()
-    })?;
-    
-    // 5. Convert to ImageBuffer
-    eprintln!("[CAPTURE_DEBUG] Converting image to buffer...");
-    let width = img_data.width as u32;
-    let height = img_data.height as u32;
-    let buffer: ImageBuffer<image::Rgba<u8>, Vec<u8>> = ImageBuffer::from_raw(width, height, img_data.bytes.into_owned())
-        .ok_or("Failed to create ImageBuffer")?;
-    
-    // 6. Generate Filename (NoteSeq_YYYYMMDD_NN.png)
-    let today = chrono::Local::now().format("%Y%m%d").to_string();
-    let prefix = format!("{}_{}_", note_seq, today);
-    
-    // Count existing files to find next serial (NN)
-    let mut max_serial = 0;
-    if let Ok(entries) = fs::read_dir(&assets_dir) {
-        for entry in entries.filter_map(|e| e.ok()) {
-            let name = entry.file_name().to_string_lossy().to_string();
-            // Format: {NoteSeq}_{YYYYMMDD}_{Serial}.png
-            if name.starts_with(&prefix) && name.ends_with(".png") {
-                // Extract Serial portion
-                let stem = name.replace(".png", "");
-                let parts: Vec<&str> = stem.split('_').collect();
-                if let Some(serial_str) = parts.last() {
-                    if let Ok(num) = serial_str.parse::<i32>() {
-                        if num > max_serial { max_serial = num; }
+    CapturedImage { width, height, rgba }
+}
+
+fn resize(img: CapturedImage, width: u32, height: u32) -> CapturedImage {
+    if (img.width, img.height) == (width, height) {
+        return img;
+    }
+    let Some(buffer) = image::RgbaImage::from_raw(img.width, img.height, img.rgba.clone()) else { return img };
+    let resized = image::imageops::resize(&buffer, width, height, image::imageops::FilterType::Triangle);
+    CapturedImage { width, height, rgba: resized.into_raw() }
+}
+
+// --- Windows: Snipping Tool (ms-screenclip:) + クリップボード監視 ---
+
+#[cfg(target_os = "windows")]
+pub struct SnippingToolBackend;
+
+#[cfg(target_os = "windows")]
+impl CaptureBackend for SnippingToolBackend {
+    fn capture(&self, target: &CaptureTarget) -> Result<CapturedImage, String> {
+        use arboard::Clipboard;
+        use std::process::Command;
+        use std::thread;
+        use std::time::Duration;
+
+        // 範囲指定済みのキャプチャはプロセス内で行う
+        if *target != CaptureTarget::Interactive {
+            return ScreenshotsBackend.capture(target);
+        }
+
+        // 1. Prepare Clipboard
+        {
+            eprintln!("[CAPTURE_DEBUG] Clearing clipboard...");
+            let mut clipboard = Clipboard::new().map_err(|e| format!("Failed to access clipboard: {}", e))?;
+            let _ = clipboard.clear();
+        } // clipboard is dropped here, releasing any potential locks
+
+        // 2. Launch Snipping Tool (ms-screenclip:)
+        eprintln!("[CAPTURE_DEBUG] Launching Snipping Tool via ms-screenclip:");
+        Command::new("explorer")
+            .arg("ms-screenclip:")
+            .spawn()
+            .map_err(|e| format!("Failed to launch Snipping Tool: {}", e))?;
+
+        // 3. Loop and Wait for Image in Clipboard
+        let start_time = std::time::Instant::now();
+        let timeout = Duration::from_secs(60);
+
+        let mut caught_image: Option<arboard::ImageData> = None;
+
+        eprintln!("[CAPTURE_DEBUG] Waiting for clipboard update (timeout: 60s)...");
+        let mut poll_count = 0;
+        while start_time.elapsed() < timeout {
+            thread::sleep(Duration::from_millis(200));
+            poll_count += 1;
+
+            if poll_count % 10 == 0 {
+                eprintln!("[CAPTURE_DEBUG] Still waiting... ({}s elapsed)", start_time.elapsed().as_secs());
+            }
+
+            if let Ok(mut clipboard) = Clipboard::new() {
+                match clipboard.get_image() {
+                    Ok(img) => {
+                        eprintln!("[CAPTURE_DEBUG] ✓ Image found in clipboard! Size: {}x{}", img.width, img.height);
+                        caught_image = Some(img);
+                        break;
+                    },
+                    Err(e) => {
+                        // 通常はエラーが正常（画像がまだない）
+                        if poll_count == 1 {
+                            eprintln!("[CAPTURE_DEBUG] No image yet (expected). Error: {}", e);
+                        }
                     }
                 }
             }
         }
+
+        let img_data = caught_image.ok_or_else(|| {
+            eprintln!("[CAPTURE_DEBUG] ✗ Capture timed out or no image selected");
+            "Capture timed out or no image selected".to_string()
+        })?;
+
+        Ok(CapturedImage {
+            width: img_data.width as u32,
+            height: img_data.height as u32,
+            rgba: img_data.bytes.into_owned(),
+        })
+    }
+}
+
+// --- 保存 ---
+
+/// キャプチャしてノートの assets/<seq>/ に保存し、本文に入れる Markdown (![image](assets/<seq>/...)) を返す
+#[command]
+pub async fn fusen_capture_screen(
+    note_path: String,
+    target: Option<CaptureTarget>,
+) -> Result<String, String> {
+    eprintln!("[CAPTURE_DEBUG] === Starting screen capture for note: {} ===", note_path);
+
+    // 1. Capture
+    let target = target.unwrap_or_else(CaptureTarget::platform_default);
+    eprintln!("[CAPTURE_DEBUG] Target: {:?}", target);
+    let captured = default_backend().capture(&target)?;
+
+    // 2. Encode and save next to the note ([NEW] 貼り付けと同じ assets/<seq>/<ハッシュ>.png)
+    eprintln!("[CAPTURE_DEBUG] Encoding image...");
+    let png = crate::assets::encode_png(captured.width, captured.height, captured.rgba)
+        .map_err(|e| format!("Failed to encode image: {}", e))?;
+    let markdown = crate::assets::store_for_note(Path::new(&note_path), &png, "png")
+        .map_err(|e| format!("Failed to save image: {}", e))?;
+
+    eprintln!("[CAPTURE_DEBUG] ✓ Capture successful! {}", markdown);
+    Ok(markdown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(width: u32, height: u32, v: u8) -> CapturedImage {
+        CapturedImage { width, height, rgba: vec![v; (width * height * 4) as usize] }
+    }
+
+    #[test]
+    fn intersect_clips_to_display() {
+        let display = Rect { x: 1920, y: 0, width: 1920, height: 1080 };
+        let region = Rect { x: 1800, y: 100, width: 300, height: 200 };
+        assert_eq!(intersect(&region, &display), Some(Rect { x: 1920, y: 100, width: 180, height: 200 }));
+        assert_eq!(intersect(&Rect { x: 0, y: 0, width: 100, height: 100 }, &display), None);
+    }
+
+    fn part(x: i32, y: i32, scale: f32, image: CapturedImage) -> Part {
+        Part { x, y, scale, image }
+    }
+
+    #[test]
+    fn compose_places_parts_side_by_side() {
+        // 左モニタが負の座標にある配置
+        let img = compose(vec![part(-2, 0, 1.0, solid(2, 1, 1)), part(0, 0, 1.0, solid(1, 2, 2))]);
+        assert_eq!((img.width, img.height), (3, 2));
+        assert_eq!(img.rgba[0], 1); // (0,0) 左
+        assert_eq!(img.rgba[2 * 4], 2); // (2,0) 右
+        assert_eq!(img.rgba[3 * 4], 0); // (0,1) 隙間
+    }
+
+    #[test]
+    fn compose_converts_logical_positions_with_scale_factor() {
+        // 拡大率 2.0: 論理 2x1 のモニタは 4x2 の画像、論理 1x2 のモニタは 2x4 の画像
+        let img = compose(vec![part(-2, 0, 2.0, solid(4, 2, 1)), part(0, 0, 2.0, solid(2, 4, 2))]);
+        assert_eq!((img.width, img.height), (6, 4));
+        assert_eq!(img.rgba[3 * 4], 1); // (3,0) 左のモニタの右端
+        assert_eq!(img.rgba[4 * 4], 2); // (4,0) 右のモニタの左端
+        assert_eq!(img.rgba[(2 * 6) * 4], 0); // (0,2) 隙間
+
+        // 拡大率の違うモニタは大きい方にそろえる（1.0 の 1x1 は 2x2 に拡大）
+        let img = compose(vec![part(0, 0, 2.0, solid(2, 2, 3)), part(1, 0, 1.0, solid(1, 1, 4))]);
+        assert_eq!((img.width, img.height), (4, 2));
+        assert_eq!(img.rgba[2 * 4], 4);
+        assert_eq!(img.rgba[(4 + 3) * 4], 4);
     }
-    let next_serial = max_serial + 1;
-    let filename = format!("{}{:02}.png", prefix, next_serial);
-    let target_path = assets_dir.join(&filename);
-    
-    eprintln!("[CAPTURE_DEBUG] Saving to: {:?}", target_path);
-    
-    // 7. Save Image
-    buffer.save(&target_path).map_err(|e| format!("Failed to save image: {}", e))?;
-    
-    eprintln!("[CAPTURE_DEBUG] ✓ Capture successful! Path: {:?}", target_path);
-    
-    Ok(target_path.to_string_lossy().to_string())
 }
@@ -0,0 +1,570 @@
+use crate::state::NoteMeta;
+
+// フロントマター層: ノート先頭の YAML ブロックを型付きで読み書きする
+//
+// 既存のノートは厳密な YAML ではない (例: `backgroundColor: #f7e9b0` は YAML だとコメント扱い)
+// ため、汎用 YAML パーサではなく行単位の寛容なパーサで扱う。
+// 既知のキーは型付きフィールドに展開し、未知のキー・コメント・ネストしたブロックは
+// 元のテキストのまま保持して、保存時にそのまま書き戻す。
+
+/// 既知のフロントマター項目
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Type,
+    Seq,
+    Id,
+    Context,
+    Created,
+    Updated,
+    BackgroundColor,
+    AlwaysOnTop,
+    Tags,
+    X,
+    Y,
+    Width,
+    Height,
+    FontFamily,
+    FontSize,
+    LineHeight,
+    TrashedFrom,
+    TrashedAt,
+}
+
+/// 新規キーを追加するときの出力順 (generate_frontmatter と同じ並び)
+const CANONICAL_ORDER: [Field; 18] = [
+    Field::Type,
+    Field::Seq,
+    Field::Id,
+    Field::Context,
+    Field::Created,
+    Field::Updated,
+    Field::BackgroundColor,
+    Field::Tags,
+    Field::X,
+    Field::Y,
+    Field::Width,
+    Field::Height,
+    Field::FontFamily,
+    Field::FontSize,
+    Field::LineHeight,
+    Field::AlwaysOnTop,
+    Field::TrashedFrom,
+    Field::TrashedAt,
+];
+
+impl Field {
+    fn from_key(key: &str) -> Option<Field> {
+        match key {
+            "type" => Some(Field::Type),
+            "seq" => Some(Field::Seq),
+            "id" => Some(Field::Id),
+            "context" => Some(Field::Context),
+            "created" => Some(Field::Created),
+            "updated" => Some(Field::Updated),
+            "backgroundColor" => Some(Field::BackgroundColor),
+            "alwaysOnTop" => Some(Field::AlwaysOnTop),
+            "tags" => Some(Field::Tags),
+            "x" => Some(Field::X),
+            "y" => Some(Field::Y),
+            // 短縮形 w: / h: も受け付ける (書き戻しは元のキー名のまま)
+            "width" | "w" => Some(Field::Width),
+            "height" | "h" => Some(Field::Height),
+            "fontFamily" => Some(Field::FontFamily),
+            "fontSize" => Some(Field::FontSize),
+            "lineHeight" => Some(Field::LineHeight),
+            "trashedFrom" => Some(Field::TrashedFrom),
+            "trashedAt" => Some(Field::TrashedAt),
+            _ => None,
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            Field::Type => "type",
+            Field::Seq => "seq",
+            Field::Id => "id",
+            Field::Context => "context",
+            Field::Created => "created",
+            Field::Updated => "updated",
+            Field::BackgroundColor => "backgroundColor",
+            Field::AlwaysOnTop => "alwaysOnTop",
+            Field::Tags => "tags",
+            Field::X => "x",
+            Field::Y => "y",
+            Field::Width => "width",
+            Field::Height => "height",
+            Field::FontFamily => "fontFamily",
+            Field::FontSize => "fontSize",
+            Field::LineHeight => "lineHeight",
+            Field::TrashedFrom => "trashedFrom",
+            Field::TrashedAt => "trashedAt",
+        }
+    }
+}
+
+/// 型付きの値 (書き戻し判定と整形に使う)
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Str(String),
+    Int(i32),
+    Num(f64),
+    Bool(bool),
+    List(Vec<String>),
+}
+
+impl Value {
+    fn render(&self) -> String {
+        match self {
+            Value::Str(s) => s.clone(),
+            Value::Int(i) => i.to_string(),
+            Value::Num(n) => format_number(*n),
+            Value::Bool(b) => b.to_string(),
+            Value::List(items) => format!("[{}]", items.join(", ")),
+        }
+    }
+}
+
+/// フロントマター内の1行 (またはキー + 継続行のまとまり)
+#[derive(Debug, Clone, PartialEq)]
+enum Entry {
+    /// `key:` の後ろの生テキスト。継続行 (インデント・ブロックリスト) は改行区切りで含む
+    Pair { key: String, raw: String },
+    /// コメント・空行など、キーを持たない行
+    Verbatim(String),
+}
+
+/// ノートのフロントマター
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NoteFrontmatter {
+    pub note_type: Option<String>,
+    pub seq: Option<i32>,
+    /// 作成時に振る変わらない id。seq は使い回されるので、履歴はこれで紐づける
+    pub id: Option<String>,
+    pub context: Option<String>,
+    pub created: Option<String>,
+    pub updated: Option<String>,
+    pub background_color: Option<String>,
+    pub always_on_top: Option<bool>,
+    pub tags: Vec<String>,
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    pub font_family: Option<String>,
+    pub font_size: Option<f64>,
+    pub line_height: Option<f64>,
+    /// ゴミ箱に入れる前の場所 (Vault からの相対パス)。ゴミ箱の中のノートだけが持つ
+    pub trashed_from: Option<String>,
+    /// ゴミ箱に入れた日時 (RFC 3339)
+    pub trashed_at: Option<String>,
+    entries: Vec<Entry>,
+}
+
+impl NoteFrontmatter {
+    /// フェンス (`---`) の内側のテキストを解析する
+    pub fn parse(inner: &str) -> Self {
+        let mut fm = NoteFrontmatter::default();
+
+        for line in inner.lines() {
+            let is_continuation = line.starts_with(' ') || line.starts_with('\t') || line.starts_with('-');
+            if is_continuation {
+                if let Some(Entry::Pair { raw, .. }) = fm.entries.last_mut() {
+                    raw.push('\n');
+                    raw.push_str(line);
+                    continue;
+                }
+            }
+            match split_key(line) {
+                Some((key, raw)) => fm.entries.push(Entry::Pair { key: key.to_string(), raw: raw.to_string() }),
+                None => fm.entries.push(Entry::Verbatim(line.to_string())),
+            }
+        }
+
+        // 既知キーを型付きフィールドに展開 (同じ項目が複数あれば最初のものを採用)
+        let mut seen: Vec<Field> = Vec::new();
+        let pairs: Vec<(Field, String)> = fm.entries.iter()
+            .filter_map(|e| match e {
+                Entry::Pair { key, raw } => Field::from_key(key).map(|f| (f, raw.clone())),
+                Entry::Verbatim(_) => None,
+            })
+            .collect();
+        for (field, raw) in pairs {
+            if seen.contains(&field) {
+                continue;
+            }
+            seen.push(field);
+            if let Some(value) = parse_value(field, &raw) {
+                fm.set_value(field, value);
+            }
+        }
+
+        fm
+    }
+
+    /// ノート全文からフロントマターを取り出して解析する (無ければ空)
+    pub fn from_content(content: &str) -> Self {
+        match split(content) {
+            Some((inner, _)) => Self::parse(inner),
+            None => Self::default(),
+        }
+    }
+
+    /// フェンス付きのフロントマターを出力する (末尾の改行なし)
+    pub fn render(&self) -> String {
+        let mut lines: Vec<String> = Vec::new();
+        let mut rendered: Vec<Field> = Vec::new();
+
+        for entry in &self.entries {
+            match entry {
+                Entry::Verbatim(line) => lines.push(line.clone()),
+                Entry::Pair { key, raw } => {
+                    let field = match Field::from_key(key) {
+                        Some(f) if !rendered.contains(&f) => f,
+                        // 未知キー・重複キーは元のまま
+                        _ => {
+                            lines.push(format!("{}:{}", key, raw));
+                            continue;
+                        }
+                    };
+                    rendered.push(field);
+                    let current = self.value(field);
+                    if current == parse_value(field, raw) {
+                        // 変更なし: 元の書式をそのまま維持
+                        lines.push(format!("{}:{}", key, raw));
+                    } else if let Some(value) = current {
+                        lines.push(format_line(key, &value));
+                    }
+                    // current が None なら (項目の削除) 行ごと落とす
+                }
+            }
+        }
+
+        for field in CANONICAL_ORDER {
+            if rendered.contains(&field) {
+                continue;
+            }
+            if let Some(value) = self.value(field) {
+                if field == Field::Tags && self.tags.is_empty() {
+                    continue;
+                }
+                lines.push(format_line(field.key(), &value));
+            }
+        }
+
+        if lines.is_empty() {
+            "---\n---".to_string()
+        } else {
+            format!("---\n{}\n---", lines.join("\n"))
+        }
+    }
+
+    /// 本文を変えずにフロントマターだけ差し替えたノート全文を返す
+    pub fn apply_to_content(&self, content: &str) -> String {
+        match split_offsets(content) {
+            Some((_, _, fence_end)) => format!("{}{}", self.render(), &content[fence_end..]),
+            None => format!("{}\n\n{}", self.render(), content),
+        }
+    }
+
+    /// ファイル名由来の情報 (path/seq/context/updated) はそのままに、本文由来のメタ情報を反映する
+    pub fn fill_meta(&self, meta: &mut NoteMeta) {
+        meta.x = self.x;
+        meta.y = self.y;
+        meta.width = self.width;
+        meta.height = self.height;
+        meta.background_color = self.background_color.clone();
+        meta.always_on_top = self.always_on_top;
+        meta.tags = self.tags.clone();
+        meta.created = self.created.clone();
+        meta.font_family = self.font_family.clone();
+        meta.font_size = self.font_size;
+        meta.line_height = self.line_height;
+    }
+
+    fn value(&self, field: Field) -> Option<Value> {
+        match field {
+            Field::Type => self.note_type.clone().map(Value::Str),
+            Field::Seq => self.seq.map(Value::Int),
+            Field::Id => self.id.clone().map(Value::Str),
+            Field::Context => self.context.clone().map(Value::Str),
+            Field::Created => self.created.clone().map(Value::Str),
+            Field::Updated => self.updated.clone().map(Value::Str),
+            Field::BackgroundColor => self.background_color.clone().map(Value::Str),
+            Field::AlwaysOnTop => self.always_on_top.map(Value::Bool),
+            Field::Tags => {
+                // tags は「キーが存在する」か「値がある」場合のみ出力対象
+                let present = self.entries.iter().any(|e| matches!(e, Entry::Pair { key, .. } if key == "tags"));
+                if present || !self.tags.is_empty() { Some(Value::List(self.tags.clone())) } else { None }
+            },
+            Field::X => self.x.map(Value::Num),
+            Field::Y => self.y.map(Value::Num),
+            Field::Width => self.width.map(Value::Num),
+            Field::Height => self.height.map(Value::Num),
+            Field::FontFamily => self.font_family.clone().map(Value::Str),
+            Field::FontSize => self.font_size.map(Value::Num),
+            Field::LineHeight => self.line_height.map(Value::Num),
+            Field::TrashedFrom => self.trashed_from.clone().map(Value::Str),
+            Field::TrashedAt => self.trashed_at.clone().map(Value::Str),
+        }
+    }
+
+    fn set_value(&mut self, field: Field, value: Value) {
+        match (field, value) {
+            (Field::Type, Value::Str(s)) => self.note_type = Some(s),
+            (Field::Seq, Value::Int(i)) => self.seq = Some(i),
+            (Field::Id, Value::Str(s)) => self.id = Some(s),
+            (Field::Context, Value::Str(s)) => self.context = Some(s),
+            (Field::Created, Value::Str(s)) => self.created = Some(s),
+            (Field::Updated, Value::Str(s)) => self.updated = Some(s),
+            (Field::BackgroundColor, Value::Str(s)) => self.background_color = Some(s),
+            (Field::AlwaysOnTop, Value::Bool(b)) => self.always_on_top = Some(b),
+            (Field::Tags, Value::List(l)) => self.tags = l,
+            (Field::X, Value::Num(n)) => self.x = Some(n),
+            (Field::Y, Value::Num(n)) => self.y = Some(n),
+            (Field::Width, Value::Num(n)) => self.width = Some(n),
+            (Field::Height, Value::Num(n)) => self.height = Some(n),
+            (Field::FontFamily, Value::Str(s)) => self.font_family = Some(s),
+            (Field::FontSize, Value::Num(n)) => self.font_size = Some(n),
+            (Field::LineHeight, Value::Num(n)) => self.line_height = Some(n),
+            (Field::TrashedFrom, Value::Str(s)) => self.trashed_from = Some(s),
+            (Field::TrashedAt, Value::Str(s)) => self.trashed_at = Some(s),
+            _ => {}
+        }
+    }
+}
+
+/// ノート全文を (フェンス内側, 閉じフェンス以降) に分割する
+pub fn split(content: &str) -> Option<(&str, &str)> {
+    split_offsets(content).map(|(start, end, fence_end)| (&content[start..end], &content[fence_end..]))
+}
+
+/// (内側の開始, 内側の終了, 閉じフェンス直後) のバイト位置を返す
+fn split_offsets(content: &str) -> Option<(usize, usize, usize)> {
+    let without_bom = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let leading = content.len() - without_bom.trim_start().len();
+    let rest = &content[leading..];
+
+    let first_line_end = rest.find('\n').unwrap_or(rest.len());
+    if rest[..first_line_end].trim_end() != "---" {
+        return None;
+    }
+    let inner_start = leading + (first_line_end + 1).min(rest.len());
+
+    let mut pos = inner_start;
+    while pos <= content.len() {
+        let line_end = content[pos..].find('\n').map(|i| pos + i).unwrap_or(content.len());
+        let line = content[pos..line_end].trim_end();
+        if line == "---" || line == "..." {
+            let inner_end = if pos > inner_start { pos - 1 } else { inner_start };
+            return Some((inner_start, inner_end.max(inner_start), pos + 3));
+        }
+        if line_end == content.len() {
+            break;
+        }
+        pos = line_end + 1;
+    }
+    None
+}
+
+/// `key: value` を分割する。キー行でなければ None
+fn split_key(line: &str) -> Option<(&str, &str)> {
+    if line.is_empty() || line.starts_with('#') || line.starts_with(' ') || line.starts_with('\t') {
+        return None;
+    }
+    let colon = line.find(':')?;
+    let key = &line[..colon];
+    let raw = &line[colon + 1..];
+    // YAML と同様、コロンの直後は空白か行末のときだけキーとみなす (`http://...` 等を除外)
+    if key.is_empty() || !(raw.is_empty() || raw.starts_with(' ') || raw.starts_with('\t')) {
+        return None;
+    }
+    Some((key.trim_end(), raw))
+}
+
+fn parse_value(field: Field, raw: &str) -> Option<Value> {
+    let mut lines = raw.split('\n');
+    let first = lines.next().unwrap_or("").trim();
+    let rest: Vec<&str> = lines.collect();
+
+    match field {
+        Field::Tags => {
+            if !rest.is_empty() {
+                // ブロック形式のリスト
+                //   tags:
+                //     - work
+                if !first.is_empty() {
+                    return None;
+                }
+                let mut items = Vec::new();
+                for line in rest {
+                    let item = line.trim().strip_prefix('-')?;
+                    let item = unquote(item.trim());
+                    if !item.is_empty() {
+                        items.push(item.to_string());
+                    }
+                }
+                return Some(Value::List(items));
+            }
+            let inner = first.strip_prefix('[').and_then(|s| s.strip_suffix(']')).unwrap_or(first);
+            Some(Value::List(
+                inner.split(',')
+                    .map(|s| unquote(s.trim()).to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect(),
+            ))
+        },
+        _ if !rest.is_empty() => None, // ネストしたブロックは既知項目として扱わない
+        Field::Seq => first.parse::<i32>().ok().map(Value::Int),
+        Field::AlwaysOnTop => match first.to_ascii_lowercase().as_str() {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        Field::X | Field::Y | Field::Width | Field::Height | Field::FontSize | Field::LineHeight => {
+            unquote(first).parse::<f64>().ok().filter(|n| n.is_finite()).map(Value::Num)
+        },
+        Field::Type | Field::Id | Field::Context | Field::Created | Field::Updated | Field::BackgroundColor | Field::FontFamily
+        | Field::TrashedFrom | Field::TrashedAt => {
+            Some(Value::Str(unquote(first).to_string()))
+        },
+    }
+}
+
+fn unquote(s: &str) -> &str {
+    for q in ['"', '\''] {
+        if s.len() >= 2 && s.starts_with(q) && s.ends_with(q) {
+            return &s[1..s.len() - 1];
+        }
+    }
+    s
+}
+
+fn format_line(key: &str, value: &Value) -> String {
+    let rendered = value.render();
+    if rendered.is_empty() {
+        format!("{}:", key)
+    } else {
+        format!("{}: {}", key, rendered)
+    }
+}
+
+/// 整数値は小数点なしで出力する (x: 100)
+fn format_number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        format!("{}", n as i64)
+    } else {
+        n.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_known_fields() {
+        let fm = NoteFrontmatter::from_content("---\ntype: sticky\nseq: 12\ncontext: メモ\ncreated: 2026-01-10\nupdated: 2026-01-12\nbackgroundColor: #ffcdd2\nx: 150\ny: 200.5\nwidth: 400\nheight: 300\nfontFamily: BIZ UDGothic\nfontSize: 8\nlineHeight: 1.0\nalwaysOnTop: true\ntags: [work, 開発]\n---\n\n本文");
+
+        assert_eq!(fm.note_type.as_deref(), Some("sticky"));
+        assert_eq!(fm.seq, Some(12));
+        assert_eq!(fm.context.as_deref(), Some("メモ"));
+        assert_eq!(fm.created.as_deref(), Some("2026-01-10"));
+        assert_eq!(fm.updated.as_deref(), Some("2026-01-12"));
+        assert_eq!(fm.background_color.as_deref(), Some("#ffcdd2"));
+        assert_eq!(fm.x, Some(150.0));
+        assert_eq!(fm.y, Some(200.5));
+        assert_eq!(fm.font_family.as_deref(), Some("BIZ UDGothic"));
+        assert_eq!(fm.font_size, Some(8.0));
+        assert_eq!(fm.line_height, Some(1.0));
+        assert_eq!(fm.always_on_top, Some(true));
+        assert_eq!(fm.tags, vec!["work", "開発"]);
+    }
+
+    #[test]
+    fn parse_negative_coordinates() {
+        // マルチモニタ環境では左側のディスプレイで負の座標になる
+        let fm = NoteFrontmatter::from_content("---\nx: -1280\ny: -35.5\n---");
+        assert_eq!(fm.x, Some(-1280.0));
+        assert_eq!(fm.y, Some(-35.5));
+    }
+
+    #[test]
+    fn parse_block_style_tags() {
+        let fm = NoteFrontmatter::from_content("---\ntags:\n  - work\n  - \"personal\"\nx: 1\n---");
+        assert_eq!(fm.tags, vec!["work", "personal"]);
+        assert_eq!(fm.x, Some(1.0));
+    }
+
+    #[test]
+    fn body_lines_are_not_metadata() {
+        // 本文中の `x: 5` をメタデータとして誤読しない
+        let fm = NoteFrontmatter::from_content("---\nseq: 1\n---\n\nx: 5\ntags: [body]");
+        assert_eq!(fm.x, None);
+        assert!(fm.tags.is_empty());
+    }
+
+    #[test]
+    fn roundtrip_is_byte_identical_when_unchanged() {
+        let content = "---\ntype: sticky\ncontext:\nbackgroundColor: \"#f7e9b0\"\nx: 100.0\n# comment\nplugin:\n  nested: true\n  list:\n    - a\nw: 500\ntags: [a, b]\n---\n\n本文";
+        let fm = NoteFrontmatter::from_content(content);
+        assert_eq!(fm.apply_to_content(content), content);
+    }
+
+    #[test]
+    fn unknown_keys_survive_edits() {
+        let content = "---\nseq: 1\ncustomKey: keep me\nplugin:\n  nested: [1, 2]\nx: 100\n---\n\n本文";
+        let mut fm = NoteFrontmatter::from_content(content);
+
+        fm.x = Some(-20.0);
+        fm.tags.push("new".to_string());
+        let out = fm.apply_to_content(content);
+
+        assert!(out.contains("customKey: keep me"));
+        assert!(out.contains("plugin:\n  nested: [1, 2]\n"));
+        assert!(out.contains("x: -20"));
+        assert!(out.contains("tags: [new]"));
+        assert!(out.ends_with("---\n\n本文"));
+    }
+
+    #[test]
+    fn short_keys_are_written_back_under_their_own_name() {
+        let content = "---\nw: 500\nh: 250\n---";
+        let mut fm = NoteFrontmatter::from_content(content);
+        assert_eq!(fm.width, Some(500.0));
+        fm.height = Some(260.0);
+        let out = fm.render();
+        assert!(out.contains("w: 500"));
+        assert!(out.contains("h: 260"));
+        assert!(!out.contains("height"));
+    }
+
+    #[test]
+    fn closing_fence_inside_value_is_not_a_fence() {
+        let content = "---\ncontext: a---b\n---\n本文";
+        let (inner, body) = split(content).unwrap();
+        assert_eq!(inner, "context: a---b");
+        assert_eq!(body, "\n本文");
+    }
+
+    #[test]
+    fn missing_frontmatter_is_created() {
+        let mut fm = NoteFrontmatter::from_content("ただの本文");
+        fm.x = Some(10.0);
+        assert_eq!(fm.apply_to_content("ただの本文"), "---\nx: 10\n---\n\nただの本文");
+    }
+
+    #[test]
+    fn trash_fields_are_added_and_removed() {
+        let mut fm = NoteFrontmatter::parse("seq: 3");
+        fm.trashed_from = Some("tags/work/0003_2026-01-01_Memo.md".to_string());
+        fm.trashed_at = Some("2026-02-01T10:00:00+09:00".to_string());
+        let out = fm.render();
+        assert_eq!(out, "---\nseq: 3\ntrashedFrom: tags/work/0003_2026-01-01_Memo.md\ntrashedAt: 2026-02-01T10:00:00+09:00\n---");
+
+        let mut parsed = NoteFrontmatter::from_content(&out);
+        assert_eq!(parsed.trashed_at.as_deref(), Some("2026-02-01T10:00:00+09:00"));
+        parsed.trashed_from = None;
+        parsed.trashed_at = None;
+        assert_eq!(parsed.render(), "---\nseq: 3\n---");
+    }
+}
@@ -0,0 +1,433 @@
+// [NEW] git による Vault の履歴と同期
+// Settings.git_sync_enabled のとき、保存のたびに note_saved で予約しておき、
+// 最後の保存から git_commit_delay_secs 経ったら Vault 全体をまとめてコミットする（メッセージはノートの context から）。
+// git_remote があれば origin として fetch → merge → push する。git は PATH にあるものを使う。
+// マージで衝突したファイルは、こちらの内容をそのまま残し、相手の内容を別のノート（コンフリクトノート）として保存する。
+// アプリの隠しフォルダ (.ore-no-fusen/) と一時ファイルは .gitignore に入れてコミットしない。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::frontmatter::NoteFrontmatter;
+use crate::logic;
+use crate::state::AppState;
+use crate::storage;
+
+const REMOTE: &str = "origin";
+const IGNORE_LINES: [&str; 3] = [".ore-no-fusen/", "*.fusen-tmp", "*.fusen-del"];
+// コミット予定の確認間隔
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// 最後の保存と、まだコミットしていないノートの context
+struct Pending {
+    last_saved: Instant,
+    contexts: Vec<String>,
+}
+
+static PENDING: Mutex<Option<Pending>> = Mutex::new(None);
+// 自動の同期と手動の同期が同時に git を動かさないように
+static SYNC_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(serde::Serialize, Clone, Debug, Default, PartialEq)]
+pub struct SyncReport {
+    pub committed: bool,
+    /// リモートの変更を取り込んだ
+    pub merged: bool,
+    pub pushed: bool,
+    /// 衝突した相手側の内容を保存したファイル
+    pub conflicts: Vec<String>,
+    /// マージで書き換わったファイル（衝突を解決したものとコンフリクトノートも含む）
+    pub changed: Vec<String>,
+}
+
+// Vault で git を実行する。名前とメールが未設定なら仮のものを渡す（ユーザーの設定は上書きしない）
+struct Git {
+    dir: PathBuf,
+    config: Vec<String>,
+}
+
+impl Git {
+    fn open(vault: &Path) -> Self {
+        let mut git = Git { dir: vault.to_path_buf(), config: Vec::new() };
+        for (key, fallback) in [("user.name", "ore-no-fusen"), ("user.email", "ore-no-fusen@localhost")] {
+            if !git.check(&["config", key]).unwrap_or(false) {
+                git.config.extend(["-c".to_string(), format!("{}={}", key, fallback)]);
+            }
+        }
+        git
+    }
+
+    fn output(&self, args: &[&str]) -> Result<Output, String> {
+        let mut cmd = Command::new("git");
+        cmd.args(&self.config)
+            .args(args)
+            .current_dir(&self.dir)
+            .stdin(Stdio::null())
+            // 認証の入力待ちで止まらないように
+            .env("GIT_TERMINAL_PROMPT", "0");
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+            cmd.creation_flags(CREATE_NO_WINDOW);
+        }
+        cmd.output().map_err(|e| format!("git を実行できません: {}", e))
+    }
+
+    /// 失敗したら stderr をエラーにする
+    fn run(&self, args: &[&str]) -> Result<String, String> {
+        let out = self.output(args)?;
+        if !out.status.success() {
+            return Err(format!("git {}: {}", args.join(" "), String::from_utf8_lossy(&out.stderr).trim()));
+        }
+        Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
+    }
+
+    /// 終了コードだけを見る
+    fn check(&self, args: &[&str]) -> Result<bool, String> {
+        Ok(self.output(args)?.status.success())
+    }
+
+    // インデックスのステージ (:2: = こちら, :3: = 相手) の内容。無ければ None
+    fn show_stage(&self, stage: u8, rel: &str) -> Result<Option<Vec<u8>>, String> {
+        let out = self.output(&["show", &format!(":{}:{}", stage, rel)])?;
+        Ok(out.status.success().then_some(out.stdout))
+    }
+}
+
+/// ノートを保存したら呼ぶ。最後の保存から git_commit_delay_secs 経ったら start_worker のスレッドがコミットする。
+pub fn note_saved(note_path: &str) {
+    let name = Path::new(note_path).file_name().unwrap_or_default().to_string_lossy().to_string();
+    let context = logic::parse_filename(&name).2.trim_end_matches(".md").to_string();
+    let mut pending = PENDING.lock().unwrap();
+    let pending = pending.get_or_insert_with(|| Pending { last_saved: Instant::now(), contexts: Vec::new() });
+    pending.last_saved = Instant::now();
+    if !pending.contexts.contains(&context) {
+        pending.contexts.push(context);
+    }
+}
+
+/// コミットメッセージ（保存したノートの context から）
+pub fn commit_message(contexts: &[String]) -> String {
+    match contexts {
+        [] => "Update vault".to_string(),
+        [one] => format!("Update \"{}\"", one),
+        [first, rest @ ..] => format!("Update \"{}\" and {} more notes", first, rest.len()),
+    }
+}
+
+/// .git が無ければ作り、.gitignore と origin を設定する
+pub fn ensure_repo(vault: &Path, remote: Option<&str>) -> Result<(), String> {
+    let git = Git::open(vault);
+    if !vault.join(".git").exists() {
+        git.run(&["init"])?;
+    }
+
+    let ignore_path = vault.join(".gitignore");
+    let current = fs::read_to_string(&ignore_path).unwrap_or_default();
+    let missing: Vec<&str> = IGNORE_LINES.iter().copied().filter(|line| !current.lines().any(|l| l.trim() == *line)).collect();
+    if !missing.is_empty() {
+        let mut content = current.clone();
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        for line in missing {
+            content.push_str(line);
+            content.push('\n');
+        }
+        storage::atomic_write(&ignore_path, content.as_bytes())?;
+    }
+
+    if let Some(url) = remote {
+        match git.run(&["remote", "get-url", REMOTE]) {
+            Ok(current) if current == url => {}
+            Ok(_) => {
+                git.run(&["remote", "set-url", REMOTE, url])?;
+            }
+            Err(_) => {
+                git.run(&["remote", "add", REMOTE, url])?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 変更をすべてコミットする。コミットしたら true
+pub fn commit_all(vault: &Path, message: &str) -> Result<bool, String> {
+    let git = Git::open(vault);
+    git.run(&["add", "-A"])?;
+    // diff --cached はまだコミットが無いリポジトリで使えないので status で見る
+    if git.run(&["status", "--porcelain"])?.is_empty() {
+        return Ok(false);
+    }
+    git.run(&["commit", "-q", "-m", message])?;
+    Ok(true)
+}
+
+/// コミットしてから origin と同期する（remote が無ければコミットだけ）
+pub fn sync(vault: &Path, remote: Option<&str>, message: &str) -> Result<SyncReport, String> {
+    ensure_repo(vault, remote)?;
+    let mut report = SyncReport { committed: commit_all(vault, message)?, ..Default::default() };
+    if remote.is_none() {
+        return Ok(report);
+    }
+
+    let git = Git::open(vault);
+    git.run(&["fetch", "-q", REMOTE])?;
+    let branch = git.run(&["symbolic-ref", "--short", "HEAD"])?;
+    let upstream = format!("{}/{}", REMOTE, branch);
+
+    if git.check(&["rev-parse", "--verify", "--quiet", &upstream])? {
+        let before = git.run(&["rev-parse", "-q", "--verify", "HEAD"]).ok();
+        let merge = git.output(&["merge", "-q", "--no-edit", "--allow-unrelated-histories", &upstream])?;
+        // 衝突したファイルは内容が変わらなくても git が一度書き換えている
+        let mut resolved = Vec::new();
+        if !merge.status.success() {
+            // 衝突以外の理由（未追跡ファイルが上書きされる等）で止まったら、マージをやめてエラーにする
+            if !vault.join(".git").join("MERGE_HEAD").exists() {
+                return Err(format!("git merge: {}", String::from_utf8_lossy(&merge.stderr).trim()));
+            }
+            let (conflicts, unmerged) = resolve_conflicts(&git, vault)?;
+            report.conflicts = conflicts;
+            resolved = unmerged;
+            git.run(&["add", "-A"])?;
+            let message = format!("Merge {} (conflicts kept as conflict notes)", upstream);
+            git.run(&["commit", "-q", "-m", &message])?;
+        }
+        report.merged = before != git.run(&["rev-parse", "-q", "--verify", "HEAD"]).ok();
+        if report.merged {
+            let changed = match &before {
+                Some(before) => git.run(&["diff", "--name-only", "-z", before, "HEAD"])?,
+                // まだコミットが無かった = すべて新しく取り込んだ
+                None => git.run(&["ls-files", "-z"])?,
+            };
+            let mut changed: Vec<String> = changed.split('\0').filter(|s| !s.is_empty()).map(String::from).collect();
+            changed.extend(resolved);
+            changed.sort();
+            changed.dedup();
+            report.changed = changed.iter().map(|rel| vault.join(rel).to_string_lossy().to_string()).collect();
+        }
+    }
+
+    // まだ何もコミットしていなければ push するものが無い
+    if git.check(&["rev-parse", "-q", "--verify", "HEAD"])? {
+        git.run(&["push", "-q", "-u", REMOTE, &branch])?;
+        report.pushed = true;
+    }
+    Ok(report)
+}
+
+// 衝突したファイルは、こちらの内容を元の場所に残し、相手の内容をコンフリクトノートとして保存する。
+// 片方で削除されていた場合は、残っている方の内容を使う（編集を失わないように）。
+// 作ったコンフリクトノートと、衝突していたファイル (Vault からの相対パス) を返す
+fn resolve_conflicts(git: &Git, vault: &Path) -> Result<(Vec<String>, Vec<String>), String> {
+    let unmerged = git.run(&["diff", "--name-only", "-z", "--diff-filter=U"])?;
+    let unmerged: Vec<String> = unmerged.split('\0').filter(|s| !s.is_empty()).map(String::from).collect();
+    let mut created = Vec::new();
+    for rel in &unmerged {
+        let rel = rel.as_str();
+        let path = vault.join(rel);
+        match (git.show_stage(2, rel)?, git.show_stage(3, rel)?) {
+            (Some(ours), Some(theirs)) => {
+                storage::atomic_write(&path, &ours)?;
+                if ours != theirs {
+                    let copy = write_conflict_copy(&path, &theirs)?;
+                    created.push(copy.to_string_lossy().to_string());
+                }
+            }
+            (Some(content), None) | (None, Some(content)) => storage::atomic_write(&path, &content)?,
+            (None, None) => {
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+    Ok((created, unmerged))
+}
+
+// 相手側の内容を同じフォルダに保存する。ノートなら新しい seq を振り、context に "(conflict)" を付ける
+fn write_conflict_copy(path: &Path, theirs: &[u8]) -> Result<PathBuf, String> {
+    let dir = path.parent().ok_or("No parent")?;
+    let name = path.file_name().ok_or("Invalid filename")?.to_string_lossy().to_string();
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) => (stem.to_string(), format!(".{}", ext)),
+        None => (name.clone(), String::new()),
+    };
+
+    if ext != ".md" {
+        let copy = storage::unique_path(dir, &format!("{} (conflict){}", stem, ext));
+        storage::atomic_write(&copy, theirs)?;
+        return Ok(copy);
+    }
+
+    let (seq, date, context) = logic::parse_filename(&name);
+    let content = String::from_utf8_lossy(theirs).to_string();
+    let (filename, content) = if seq > 0 {
+        let new_seq = storage::get_next_seq(&dir.to_string_lossy());
+        let mut fm = NoteFrontmatter::from_content(&content);
+        if fm.seq.is_some() {
+            fm.seq = Some(new_seq);
+        }
+        // 別のノートになるので、履歴が混ざらないよう id も振り直す
+        if fm.id.is_some() {
+            fm.id = Some(storage::new_note_id());
+        }
+        let context = format!("{} (conflict)", context);
+        (logic::generate_filename(new_seq, &date, &context), fm.apply_to_content(&content))
+    } else {
+        (format!("{} (conflict).md", stem), content)
+    };
+    let copy = storage::unique_path(dir, &filename);
+    storage::atomic_write(&copy, content.as_bytes())?;
+    Ok(copy)
+}
+
+/// 予約分をまとめてコミットし、push が true でリモートがあれば同期する。
+/// リモートの変更を取り込んだら一覧を読み直し、書き換わったノートのバージョンを覚え直して
+/// 開いているウィンドウに読み直させる（そのまま保存すると「外部で変更された」になるか、取り込んだ内容を上書きしてしまうため）。
+pub fn sync_now<R: Runtime>(app: &AppHandle<R>, push: bool) -> Result<SyncReport, String> {
+    let _guard = SYNC_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let settings = storage::load_settings()?;
+    let contexts = PENDING.lock().unwrap().take().map(|p| p.contexts).unwrap_or_default();
+    let vault = {
+        let state = app.state::<Mutex<AppState>>();
+        let app_state = state.lock().unwrap();
+        app_state.base_path.clone().or(app_state.folder_path.clone()).ok_or("base_path is not set")?
+    };
+    let remote = settings.git_remote.as_deref().filter(|r| push && !r.trim().is_empty());
+    let report = sync(Path::new(&vault), remote, &commit_message(&contexts))?;
+
+    if report.merged {
+        let notes: Vec<&String> = report.changed.iter().filter(|f| f.ends_with(".md")).collect();
+        {
+            let state = app.state::<Mutex<AppState>>();
+            let mut app_state = state.lock().unwrap();
+            app_state.notes = storage::list_notes(&vault);
+            for note in &notes {
+                crate::remember_version(&mut app_state, note);
+            }
+        }
+        for note in notes {
+            let _ = app.emit("fusen:reload_note", note);
+        }
+        let _ = app.emit("fusen:notes_updated", ());
+        let _ = crate::tray::refresh_tray_menu(app);
+    }
+    if !report.conflicts.is_empty() {
+        crate::logger::log_warn(&format!("[Git] 衝突した {} 件を別のノートとして保存しました", report.conflicts.len()));
+    }
+    Ok(report)
+}
+
+fn is_enabled() -> bool {
+    storage::load_settings().is_ok_and(|s| s.git_sync_enabled)
+}
+
+/// 予約されたコミットを待つスレッドを開始する
+pub fn start_worker<R: Runtime>(app: AppHandle<R>) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let last_saved = match PENDING.lock().unwrap().as_ref() {
+            Some(pending) => pending.last_saved,
+            None => continue,
+        };
+        let Ok(settings) = storage::load_settings() else { continue };
+        if !settings.git_sync_enabled {
+            // 無効の間の保存は溜めない（有効にした後の最初のコミットにまとめて入る）
+            PENDING.lock().unwrap().take();
+            continue;
+        }
+        if last_saved.elapsed() < Duration::from_secs(settings.git_commit_delay_secs as u64) {
+            continue;
+        }
+        if let Err(e) = sync_now(&app, true) {
+            crate::logger::log_warn(&format!("[Git] 同期に失敗しました: {}", e));
+        }
+    });
+}
+
+/// 終了時: 予約分があればコミットだけする（ネットワークは待たない）
+pub fn flush_on_exit<R: Runtime>(app: &AppHandle<R>) {
+    if PENDING.lock().unwrap().is_none() || !is_enabled() {
+        return;
+    }
+    if let Err(e) = sync_now(app, false) {
+        crate::logger::log_warn(&format!("[Git] 終了時のコミットに失敗しました: {}", e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index;
+
+    fn git_available() -> bool {
+        Command::new("git").arg("--version").output().is_ok_and(|o| o.status.success())
+    }
+
+    // 空の bare リポジトリと、それを origin にした Vault 2つ
+    fn setup(root: &Path) -> (String, PathBuf, PathBuf) {
+        let remote = root.join("remote.git");
+        fs::create_dir_all(&remote).unwrap();
+        Git::open(&remote).run(&["init", "-q", "--bare"]).unwrap();
+        let (a, b) = (root.join("a"), root.join("b"));
+        fs::create_dir_all(&a).unwrap();
+        fs::create_dir_all(&b).unwrap();
+        (remote.to_string_lossy().to_string(), a, b)
+    }
+
+    #[test]
+    fn commit_message_uses_note_context() {
+        assert_eq!(commit_message(&[]), "Update vault");
+        assert_eq!(commit_message(&["Memo".to_string()]), "Update \"Memo\"");
+        assert_eq!(commit_message(&["Memo".to_string(), "Todo".to_string(), "Idea".to_string()]), "Update \"Memo\" and 2 more notes");
+    }
+
+    #[test]
+    fn sync_pushes_pulls_and_turns_conflicts_into_notes() {
+        assert!(git_available(), "this test needs git on PATH");
+        let dir = tempfile::tempdir().unwrap();
+        let (remote, a, b) = setup(dir.path());
+        let note = "0001_2026-03-01_Memo.md";
+
+        fs::write(a.join(note), "---\nseq: 1\n---\n\nMemo\nfrom a").unwrap();
+        fs::create_dir_all(index::app_dir(&a)).unwrap();
+        fs::write(index::app_dir(&a).join("index.json"), "{}").unwrap();
+        let report = sync(&a, Some(&remote), "Update \"Memo\"").unwrap();
+        assert!(report.committed && report.pushed);
+        assert_eq!(Git::open(&a).run(&["log", "-1", "--format=%s"]).unwrap(), "Update \"Memo\"");
+        // 隠しフォルダはコミットしない
+        assert!(Git::open(&a).run(&["ls-files"]).unwrap().lines().all(|f| !f.starts_with(".ore-no-fusen")));
+
+        // 空の Vault で同期すると取り込まれる
+        let report = sync(&b, Some(&remote), "Update vault").unwrap();
+        assert!(report.merged);
+        assert!(report.changed.iter().any(|f| f.ends_with(note)));
+        assert_eq!(fs::read_to_string(b.join(note)).unwrap(), "---\nseq: 1\n---\n\nMemo\nfrom a");
+
+        // 両方で同じノートを編集する
+        fs::write(a.join(note), "---\nseq: 1\n---\n\nMemo\nedited in a").unwrap();
+        sync(&a, Some(&remote), "Update \"Memo\"").unwrap();
+        fs::write(b.join(note), "---\nseq: 1\n---\n\nMemo\nedited in b").unwrap();
+        let report = sync(&b, Some(&remote), "Update \"Memo\"").unwrap();
+        assert!(report.pushed);
+        assert_eq!(report.conflicts.len(), 1);
+        // 内容はこちらのままでも、衝突したノートは読み直す対象に入る
+        assert!(report.changed.iter().any(|f| f.ends_with(note)));
+        assert!(report.changed.contains(&report.conflicts[0]));
+        assert_eq!(fs::read_to_string(b.join(note)).unwrap(), "---\nseq: 1\n---\n\nMemo\nedited in b");
+        let conflict = PathBuf::from(&report.conflicts[0]);
+        assert_eq!(conflict.file_name().unwrap().to_string_lossy(), "0002_2026-03-01_Memo (conflict).md");
+        assert_eq!(fs::read_to_string(&conflict).unwrap(), "---\nseq: 2\n---\n\nMemo\nedited in a");
+
+        // もう一方にはコンフリクトノートごと届く
+        let report = sync(&a, Some(&remote), "Update vault").unwrap();
+        assert!(report.merged && report.conflicts.is_empty());
+        assert!(a.join("0002_2026-03-01_Memo (conflict).md").exists());
+        assert_eq!(fs::read_to_string(a.join(note)).unwrap(), "---\nseq: 1\n---\n\nMemo\nedited in b");
+    }
+}
@@ -0,0 +1,354 @@
+// [NEW] ノートごとの履歴（リビジョン）
+// 保存で本文が変わるたびに、書き換える前の内容を Vault の隠しフォルダ
+// (.ore-no-fusen/history/id/<ノートの id>/<ミリ秒>.md) に残しておく。
+// seq は使い回される（ゴミ箱やアーカイブのノートも seq を持ったまま、復元すると振り直す）ので、
+// 作成時にフロントマターへ書く id で分ける。id の無い古いノートだけ history/<seq>/ を使い、
+// ゴミ箱などから戻すときに seq を振り直すなら、id を振って引き継ぐ (adopt_legacy)。
+// 自動保存は入力中に何度も走るので、前のスナップショットから COALESCE_WINDOW 以内なら
+// まとめて1つにする（ただし本文が半分以下になるような大きな削除は必ず残す）。
+// ノートごとに MAX_REVISIONS 件を超えたら古いものから消す。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, FixedOffset, TimeZone};
+
+use crate::frontmatter::NoteFrontmatter;
+use crate::index;
+use crate::logic;
+use crate::storage;
+
+const HISTORY_DIR: &str = "history";
+const BY_ID_DIR: &str = "id";
+const COALESCE_WINDOW_MS: i64 = 5 * 60 * 1000;
+const MAX_REVISIONS: usize = 50;
+// これより大きい差分は行単位の LCS を取らずに丸ごと削除+追加として返す
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct Revision {
+    /// 保存した時刻（ミリ秒）。diff / restore で指定する
+    pub id: String,
+    pub saved_at: String,
+    pub size: u64,
+    /// 本文の1行目
+    pub preview: String,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct DiffLine {
+    pub kind: DiffKind,
+    pub text: String,
+    /// 古い側の行番号（1始まり。追加行は None）
+    pub old_line: Option<usize>,
+    /// 新しい側の行番号（1始まり。削除行は None）
+    pub new_line: Option<usize>,
+}
+
+// ノートの履歴フォルダ。content（ノート全文）のフロントマターの id で分ける
+fn note_dir(vault: &Path, note_path: &Path, content: &str) -> PathBuf {
+    match NoteFrontmatter::from_content(content).id {
+        Some(id) if is_valid_id(&id) => id_dir(vault, &id),
+        _ => legacy_dir(vault, note_path),
+    }
+}
+
+// 今ファイルにある内容で決める
+fn current_dir(vault: &Path, note_path: &Path) -> PathBuf {
+    let content = fs::read_to_string(note_path).unwrap_or_default();
+    note_dir(vault, note_path, &content)
+}
+
+fn id_dir(vault: &Path, id: &str) -> PathBuf {
+    index::app_dir(vault).join(HISTORY_DIR).join(BY_ID_DIR).join(id)
+}
+
+// フォルダ名に使うので、英数字と - _ だけ
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+// id の無い古いノートは seq で分ける（リネームしても変わらない）
+fn legacy_dir(vault: &Path, note_path: &Path) -> PathBuf {
+    let file_name = note_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let key = match logic::parse_filename(&file_name).0 {
+        seq if seq > 0 => seq.to_string(),
+        _ => format!("_{}", file_name.trim_end_matches(".md")),
+    };
+    index::app_dir(vault).join(HISTORY_DIR).join(key)
+}
+
+fn revision_ids(dir: &Path) -> Vec<i64> {
+    let Ok(entries) = fs::read_dir(dir) else { return Vec::new() };
+    let mut ids: Vec<i64> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.file_name().to_string_lossy().strip_suffix(".md")?.parse().ok())
+        .collect();
+    ids.sort_unstable();
+    ids
+}
+
+/// old_content（書き換える前の内容）を残す。本文が変わらない保存（位置・色だけ）は残さない。
+/// 残したら true。
+pub fn record(vault: &Path, note_path: &Path, old_content: &str, new_body: &str, now: DateTime<FixedOffset>) -> Result<bool, String> {
+    let (_, old_body) = logic::split_frontmatter(old_content);
+    if old_body.trim() == new_body.trim() {
+        return Ok(false);
+    }
+    let dir = note_dir(vault, note_path, old_content);
+    let now_ms = now.timestamp_millis();
+    let ids = revision_ids(&dir);
+    let big_deletion = new_body.len() * 2 < old_body.len();
+    if let Some(&latest) = ids.last() {
+        if now_ms - latest < COALESCE_WINDOW_MS && !big_deletion {
+            return Ok(false);
+        }
+    }
+    snapshot(&dir, old_content, now_ms)?;
+    prune(&dir);
+    Ok(true)
+}
+
+/// まとめずに必ず残す（復元の直前など）
+pub fn record_now(vault: &Path, note_path: &Path, content: &str, now: DateTime<FixedOffset>) -> Result<(), String> {
+    let dir = note_dir(vault, note_path, content);
+    snapshot(&dir, content, now.timestamp_millis())?;
+    prune(&dir);
+    Ok(())
+}
+
+fn snapshot(dir: &Path, content: &str, now_ms: i64) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    // 同じミリ秒に2回来たら後ろへずらす
+    let id = (now_ms..).find(|id| !dir.join(format!("{}.md", id)).exists()).unwrap_or(now_ms);
+    storage::atomic_write(&dir.join(format!("{}.md", id)), content.as_bytes())
+}
+
+fn prune(dir: &Path) {
+    let ids = revision_ids(dir);
+    if ids.len() > MAX_REVISIONS {
+        for id in &ids[..ids.len() - MAX_REVISIONS] {
+            let _ = fs::remove_file(dir.join(format!("{}.md", id)));
+        }
+    }
+}
+
+/// id の無い古いノートに id を振ったとき、seq で分けていた履歴をその id へ移す。
+/// note_path は id を振る前の（seq の入った）パス
+pub fn adopt_legacy(vault: &Path, note_path: &Path, id: &str) -> Result<(), String> {
+    let from = legacy_dir(vault, note_path);
+    let to = id_dir(vault, id);
+    if !is_valid_id(id) || !from.is_dir() || to.exists() {
+        return Ok(());
+    }
+    fs::create_dir_all(to.parent().ok_or("No parent")?).map_err(|e| e.to_string())?;
+    fs::rename(&from, &to).map_err(|e| e.to_string())
+}
+
+/// ノートの履歴。新しい順。
+pub fn list(vault: &Path, note_path: &Path) -> Vec<Revision> {
+    let dir = current_dir(vault, note_path);
+    let mut revisions: Vec<Revision> = revision_ids(&dir)
+        .into_iter()
+        .rev()
+        .filter_map(|id| {
+            let path = dir.join(format!("{}.md", id));
+            let content = fs::read_to_string(&path).ok()?;
+            let (_, body) = logic::split_frontmatter(&content);
+            let saved_at = chrono::Local.timestamp_millis_opt(id).single()?;
+            Some(Revision {
+                id: id.to_string(),
+                saved_at: saved_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+                size: content.len() as u64,
+                preview: body.lines().find(|l| !l.trim().is_empty()).unwrap_or("").trim().to_string(),
+            })
+        })
+        .collect();
+    revisions.truncate(MAX_REVISIONS);
+    revisions
+}
+
+/// リビジョンの内容（フロントマター込み）
+pub fn read(vault: &Path, note_path: &Path, id: &str) -> Result<String, String> {
+    let id: i64 = id.parse().map_err(|_| format!("不正なリビジョンです: {}", id))?;
+    let path = current_dir(vault, note_path).join(format!("{}.md", id));
+    fs::read_to_string(&path).map_err(|_| format!("リビジョンが見つかりません: {}", id))
+}
+
+/// 本文どうしの行単位の差分
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // 前後の共通部分は LCS の外で処理する
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let (a, b) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+
+    let mut out = Vec::new();
+    let mut push = |kind: DiffKind, text: &str, old_line: Option<usize>, new_line: Option<usize>| {
+        out.push(DiffLine { kind, text: text.to_string(), old_line, new_line });
+    };
+    for (n, line) in old[..prefix].iter().enumerate() {
+        push(DiffKind::Equal, line, Some(n + 1), Some(n + 1));
+    }
+
+    let (mut i, mut j) = (0, 0);
+    if a.len() * b.len() <= MAX_DIFF_CELLS {
+        // lcs[i][j] = a[i..] と b[j..] の LCS の長さ
+        let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+        for x in (0..a.len()).rev() {
+            for y in (0..b.len()).rev() {
+                lcs[x][y] = if a[x] == b[y] { lcs[x + 1][y + 1] + 1 } else { lcs[x + 1][y].max(lcs[x][y + 1]) };
+            }
+        }
+        while i < a.len() && j < b.len() {
+            if a[i] == b[j] {
+                push(DiffKind::Equal, a[i], Some(prefix + i + 1), Some(prefix + j + 1));
+                i += 1;
+                j += 1;
+            } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+                push(DiffKind::Delete, a[i], Some(prefix + i + 1), None);
+                i += 1;
+            } else {
+                push(DiffKind::Insert, b[j], None, Some(prefix + j + 1));
+                j += 1;
+            }
+        }
+    }
+    for (x, line) in a.iter().enumerate().skip(i) {
+        push(DiffKind::Delete, line, Some(prefix + x + 1), None);
+    }
+    for (y, line) in b.iter().enumerate().skip(j) {
+        push(DiffKind::Insert, line, None, Some(prefix + y + 1));
+    }
+
+    let (old_start, new_start) = (old.len() - suffix, new.len() - suffix);
+    for (k, line) in old[old_start..].iter().enumerate() {
+        push(DiffKind::Equal, line, Some(old_start + k + 1), Some(new_start + k + 1));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(minutes: i64) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339("2026-03-01T10:00:00+09:00").unwrap() + chrono::Duration::minutes(minutes)
+    }
+
+    #[test]
+    fn record_coalesces_rapid_edits_but_keeps_big_deletions() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        let note = vault.join("0003_2026-03-01_Memo.md");
+        let v1 = "---\nx: 1\n---\n\nMemo\nline one\nline two";
+
+        // 位置だけの保存は残さない
+        assert!(!record(vault, &note, v1, "Memo\nline one\nline two", at(0)).unwrap());
+        assert!(record(vault, &note, v1, "Memo\nline one\nline two!", at(0)).unwrap());
+        assert!(!record(vault, &note, "Memo\nline one\nline two!", "Memo\nline one\nline two!!", at(1)).unwrap());
+        // 全選択して削除
+        assert!(record(vault, &note, "Memo\nline one\nline two!!", "", at(2)).unwrap());
+        assert!(record(vault, &note, "", "New", at(8)).unwrap());
+
+        let revisions = list(vault, &note);
+        assert_eq!(revisions.len(), 3);
+        assert_eq!(revisions[2].preview, "Memo");
+        assert_eq!(read(vault, &note, &revisions[2].id).unwrap(), v1);
+        assert_eq!(read(vault, &note, &revisions[1].id).unwrap(), "Memo\nline one\nline two!!");
+
+        // リネームしても seq が同じなら同じ履歴
+        assert_eq!(list(vault, &vault.join("0003_2026-03-01_Renamed.md")).len(), 3);
+        assert!(read(vault, &note, "../../x").is_err());
+    }
+
+    #[test]
+    fn reused_seq_does_not_share_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::write(vault.join("0002_2026-03-01_Before.md"), "Before").unwrap();
+        let data = logic::build_create_note_data(&vault.to_string_lossy(), "Memo", 3, &storage::new_note_id(), "2026-03-01");
+        let note = PathBuf::from(&data.path_str);
+        fs::write(&note, &data.content).unwrap();
+        assert!(record(vault, &note, &data.content, "Edited", at(0)).unwrap());
+        assert_eq!(list(vault, &note).len(), 1);
+
+        // 捨てたあと、同じ seq で新しいノートを作る（get_next_seq は Vault 直下しか見ない）
+        let trashed = crate::trash::move_to_trash(vault, &note, "2026-03-02T10:00:00+09:00").unwrap();
+        let seq = storage::get_next_seq(&vault.to_string_lossy());
+        assert_eq!(seq, 3);
+        let fresh = logic::build_create_note_data(&vault.to_string_lossy(), "New", seq, &storage::new_note_id(), "2026-03-03");
+        fs::write(&fresh.path_str, &fresh.content).unwrap();
+        assert!(list(vault, Path::new(&fresh.path_str)).is_empty());
+
+        // 戻すと seq は振り直されるが、履歴はついてくる
+        let restored = crate::trash::restore(vault, &trashed).unwrap();
+        assert_ne!(restored, note);
+        assert_eq!(list(vault, &restored).len(), 1);
+        assert!(list(vault, Path::new(&fresh.path_str)).is_empty());
+    }
+
+    #[test]
+    fn notes_without_id_take_their_history_along_when_renumbered() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        let note = vault.join("0005_2026-03-01_Old.md");
+        let content = "---\nseq: 5\n---\n\nOld";
+        fs::write(&note, content).unwrap();
+        record_now(vault, &note, content, at(0)).unwrap();
+
+        let trashed = crate::trash::move_to_trash(vault, &note, "2026-03-02T10:00:00+09:00").unwrap();
+        fs::write(vault.join("0005_2026-03-03_Other.md"), "---\nseq: 5\n---\n\nOther").unwrap();
+        let restored = crate::trash::restore(vault, &trashed).unwrap();
+
+        // 戻したときに id が振られ、seq 5 の履歴はそちらへ移る
+        assert!(NoteFrontmatter::from_content(&fs::read_to_string(&restored).unwrap()).id.is_some());
+        assert_eq!(list(vault, &restored).len(), 1);
+        assert!(list(vault, &vault.join("0005_2026-03-03_Other.md")).is_empty());
+    }
+
+    #[test]
+    fn old_revisions_are_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        let note = vault.join("0001_2026-03-01_A.md");
+        for n in 0..(MAX_REVISIONS as i64 + 5) {
+            record_now(vault, &note, &format!("v{}", n), at(n)).unwrap();
+        }
+        let revisions = list(vault, &note);
+        assert_eq!(revisions.len(), MAX_REVISIONS);
+        assert_eq!(revisions[0].preview, format!("v{}", MAX_REVISIONS + 4));
+    }
+
+    #[test]
+    fn diff_lines_reports_inserts_and_deletes() {
+        let diff = diff_lines("a\nb\nc\nd", "a\nc\nx\nd");
+        let kinds: Vec<(DiffKind, &str)> = diff.iter().map(|d| (d.kind.clone(), d.text.as_str())).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (DiffKind::Equal, "a"),
+                (DiffKind::Delete, "b"),
+                (DiffKind::Equal, "c"),
+                (DiffKind::Insert, "x"),
+                (DiffKind::Equal, "d"),
+            ]
+        );
+        assert_eq!((diff[1].old_line, diff[1].new_line), (Some(2), None));
+        assert_eq!((diff[3].old_line, diff[3].new_line), (None, Some(3)));
+        assert_eq!((diff[4].old_line, diff[4].new_line), (Some(4), Some(4)));
+
+        assert!(diff_lines("same", "same").iter().all(|d| d.kind == DiffKind::Equal));
+        assert_eq!(diff_lines("", "new").len(), 1);
+    }
+}
@@ -113,7 +113,7 @@ fn is_image_file(path: &Path) -> bool {
     matches!(ext.as_str(), "png" | "jpg" | "jpeg" | "gif" | "webp" | "svg")
 }
 
-fn create_symlink(target: &Path, link_path: &Path) -> Result<(), String> {
+pub(crate) fn create_symlink(target: &Path, link_path: &Path) -> Result<(), String> {
     #[cfg(windows)]
     {
         // ファイルかディレクトリかによって使い分ける必要があるが、
@@ -0,0 +1,146 @@
+// [NEW] ノートのメタデータインデックス
+// Vault 直下の隠しフォルダ (.ore-no-fusen/index.json) に、ファイルごとの
+// mtime + サイズ + 解析済み NoteMeta を保存しておく。
+// 一覧取得時は stat だけ行い、mtime かサイズが変わったファイルだけを読み直す。
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+use crate::logic;
+use crate::state::NoteMeta;
+use crate::storage;
+
+/// Vault 内のアプリ用隠しフォルダ
+pub const APP_DIR: &str = ".ore-no-fusen";
+const INDEX_FILE: &str = "index.json";
+// NoteMeta の解析方法を変えたら上げる（古いインデックスは捨てて作り直す）
+const INDEX_VERSION: u32 = 1;
+
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+struct NoteIndex {
+    version: u32,
+    /// ファイル名 -> エントリ（Vault ごと移動してもそのまま使えるようにパスは持たない）
+    entries: BTreeMap<String, IndexEntry>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+struct IndexEntry {
+    mtime_ms: i64,
+    size: u64,
+    meta: NoteMeta,
+}
+
+pub fn app_dir(vault: &Path) -> PathBuf {
+    vault.join(APP_DIR)
+}
+
+fn index_path(vault: &Path) -> PathBuf {
+    app_dir(vault).join(INDEX_FILE)
+}
+
+fn load(vault: &Path) -> NoteIndex {
+    fs::read_to_string(index_path(vault))
+        .ok()
+        .and_then(|s| serde_json::from_str::<NoteIndex>(&s).ok())
+        .filter(|index| index.version == INDEX_VERSION)
+        .unwrap_or_default()
+}
+
+fn save(vault: &Path, index: &NoteIndex) -> Result<(), String> {
+    fs::create_dir_all(app_dir(vault)).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string(index).map_err(|e| e.to_string())?;
+    storage::atomic_write(&index_path(vault), json.as_bytes())
+}
+
+/// フォルダ直下のノート一覧。変更のあったファイルだけを読み、インデックスを更新する。
+pub fn list_notes(folder_path: &str) -> Vec<NoteMeta> {
+    let vault = Path::new(folder_path);
+    let mut index = load(vault);
+    let mut next = NoteIndex { version: INDEX_VERSION, entries: BTreeMap::new() };
+    let mut changed = index.version != INDEX_VERSION;
+
+    for entry in WalkDir::new(folder_path).max_depth(1).into_iter().filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if !entry.file_type().is_file() || path.extension().and_then(|ext| ext.to_str()) != Some("md") {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path_str = path.to_string_lossy().to_string();
+        let Ok(metadata) = entry.metadata() else { continue };
+        let (mtime_ms, size) = (storage::mtime_ms(&metadata), metadata.len());
+
+        let cached = index
+            .entries
+            .remove(&name)
+            .filter(|e| e.mtime_ms == mtime_ms && e.size == size);
+        let item = match cached {
+            Some(mut e) => {
+                e.meta.path = path_str;
+                e
+            }
+            None => {
+                changed = true;
+                let content = fs::read_to_string(path).unwrap_or_default();
+                IndexEntry { mtime_ms, size, meta: logic::build_note_meta(&path_str, &content) }
+            }
+        };
+        next.entries.insert(name, item);
+    }
+    // 消えたファイルが残っていたら更新
+    changed |= !index.entries.is_empty();
+
+    if changed {
+        if let Err(e) = save(vault, &next) {
+            crate::logger::log_warn(&format!("インデックスの保存に失敗しました: {}", e));
+        }
+    }
+
+    let mut notes: Vec<NoteMeta> = next.entries.into_values().map(|e| e.meta).collect();
+    notes.sort_by(|a, b| a.path.cmp(&b.path));
+    notes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_notes_reuses_unchanged_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let folder = dir.path().to_string_lossy().to_string();
+        let path = dir.path().join("0001_2026-01-12_Note.md");
+        fs::write(&path, "---\ntags: [a]\n---\n\nbody").unwrap();
+
+        let first = list_notes(&folder);
+        assert_eq!(first[0].tags, vec!["a".to_string()]);
+        assert!(index_path(dir.path()).exists());
+
+        // インデックス側のメタを書き換えて、再読込されないことを確認
+        let mut index = load(dir.path());
+        index.entries.values_mut().for_each(|e| e.meta.tags = vec!["cached".to_string()]);
+        save(dir.path(), &index).unwrap();
+        assert_eq!(list_notes(&folder)[0].tags, vec!["cached".to_string()]);
+    }
+
+    #[test]
+    fn list_notes_rereads_changed_and_drops_removed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let folder = dir.path().to_string_lossy().to_string();
+        let a = dir.path().join("0001_2026-01-12_A.md");
+        let b = dir.path().join("0002_2026-01-12_B.md");
+        fs::write(&a, "---\ntags: [a]\n---\n").unwrap();
+        fs::write(&b, "b").unwrap();
+        assert_eq!(list_notes(&folder).len(), 2);
+
+        // サイズが変われば読み直す
+        fs::write(&a, "---\ntags: [changed]\n---\n").unwrap();
+        fs::remove_file(&b).unwrap();
+        let notes = list_notes(&folder);
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].tags, vec!["changed".to_string()]);
+        assert_eq!(load(dir.path()).entries.len(), 1);
+    }
+}
@@ -0,0 +1,269 @@
+// [NEW] 操作ジャーナル（元に戻す）
+// ゴミ箱へ移動・アーカイブ・リネーム・タグの一括削除は複数のファイルを動かすので、
+// 操作ごとに「どう戻せばよいか」を Vault の隠しフォルダ (.ore-no-fusen/journal.json) に記録しておく。
+// undo_last で最後の操作を取り消す（取り消した操作は記録から消える。やり直しは無い）。
+// パスは Vault からの相対パスで持つ（ゴミ箱の trashedFrom と同じ）。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::archive;
+use crate::frontmatter::NoteFrontmatter;
+use crate::index;
+use crate::logic;
+use crate::storage;
+use crate::trash;
+
+const JOURNAL_FILE: &str = "journal.json";
+const MAX_ENTRIES: usize = 20;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Operation {
+    /// ゴミ箱へ移動した（戻し先はゴミ箱のノートの trashedFrom）
+    Trash { original: String, trashed: String },
+    /// Archive/ か tags/<タグ>/ へ移した
+    Archive { original: String, archived: String },
+    Rename { old_path: String, new_path: String },
+    /// タグを全ノートから外した
+    DeleteTag { tag: String, notes: Vec<TagRemoval> },
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct TagRemoval {
+    pub path: String,
+    /// 外す前の tags の中の位置（同じ位置に戻す）
+    pub index: usize,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct JournalEntry {
+    pub at: String,
+    pub operation: Operation,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct RenamedNote {
+    pub old_path: String,
+    pub new_path: String,
+}
+
+/// 取り消した結果（パスは絶対パス）
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct UndoReport {
+    pub operation: Operation,
+    /// ゴミ箱・アーカイブから戻したノート
+    pub restored: Vec<String>,
+    /// 元の名前に戻したノート
+    pub renamed: Vec<RenamedNote>,
+    /// タグを戻したノート
+    pub updated: Vec<String>,
+    /// 見つからずに戻せなかったノート
+    pub skipped: Vec<String>,
+}
+
+fn relative(vault: &Path, path: &Path) -> String {
+    path.strip_prefix(vault).unwrap_or(path).to_string_lossy().replace('\\', "/")
+}
+
+fn absolute(vault: &Path, rel: &str) -> PathBuf {
+    vault.join(rel)
+}
+
+// ファイル名の context 部分（トレイの表示用）
+fn title(rel: &str) -> String {
+    let name = rel.rsplit('/').next().unwrap_or(rel);
+    match logic::parse_filename(name).2 {
+        context if !context.is_empty() => context,
+        _ => name.trim_end_matches(".md").to_string(),
+    }
+}
+
+impl Operation {
+    pub fn trash(vault: &Path, original: &Path, trashed: &Path) -> Self {
+        Operation::Trash { original: relative(vault, original), trashed: relative(vault, trashed) }
+    }
+
+    pub fn archive(vault: &Path, original: &Path, archived: &Path) -> Self {
+        Operation::Archive { original: relative(vault, original), archived: relative(vault, archived) }
+    }
+
+    pub fn rename(vault: &Path, old_path: &Path, new_path: &Path) -> Self {
+        Operation::Rename { old_path: relative(vault, old_path), new_path: relative(vault, new_path) }
+    }
+
+    /// notes は (書き換えたノート, 外したタグの位置)
+    pub fn delete_tag(vault: &Path, tag: &str, notes: &[(String, usize)]) -> Self {
+        let notes = notes
+            .iter()
+            .map(|(path, index)| TagRemoval { path: relative(vault, Path::new(path)), index: *index })
+            .collect();
+        Operation::DeleteTag { tag: tag.to_string(), notes }
+    }
+
+    /// トレイやログに出す説明
+    pub fn describe(&self, is_en: bool) -> String {
+        match (self, is_en) {
+            (Operation::Trash { original, .. }, true) => format!("Move to Trash: {}", title(original)),
+            (Operation::Trash { original, .. }, false) => format!("ゴミ箱へ移動: {}", title(original)),
+            (Operation::Archive { original, .. }, true) => format!("Archive: {}", title(original)),
+            (Operation::Archive { original, .. }, false) => format!("アーカイブ: {}", title(original)),
+            (Operation::Rename { old_path, .. }, true) => format!("Rename: {}", title(old_path)),
+            (Operation::Rename { old_path, .. }, false) => format!("名前の変更: {}", title(old_path)),
+            (Operation::DeleteTag { tag, notes }, true) => format!("Delete tag #{} ({} notes)", tag, notes.len()),
+            (Operation::DeleteTag { tag, notes }, false) => format!("タグ #{} を削除 ({} 件)", tag, notes.len()),
+        }
+    }
+}
+
+fn journal_path(vault: &Path) -> PathBuf {
+    index::app_dir(vault).join(JOURNAL_FILE)
+}
+
+fn load(vault: &Path) -> Vec<JournalEntry> {
+    fs::read_to_string(journal_path(vault))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save(vault: &Path, entries: &[JournalEntry]) -> Result<(), String> {
+    fs::create_dir_all(index::app_dir(vault)).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(entries).map_err(|e| e.to_string())?;
+    storage::atomic_write(&journal_path(vault), json.as_bytes())
+}
+
+/// 操作を記録する。MAX_ENTRIES を超えたら古いものから忘れる。
+pub fn record(vault: &Path, operation: Operation, at: &str) -> Result<(), String> {
+    let mut entries = load(vault);
+    entries.push(JournalEntry { at: at.to_string(), operation });
+    if entries.len() > MAX_ENTRIES {
+        entries.drain(..entries.len() - MAX_ENTRIES);
+    }
+    save(vault, &entries)
+}
+
+/// 次に取り消される操作
+pub fn last(vault: &Path) -> Option<JournalEntry> {
+    load(vault).pop()
+}
+
+/// 最後の操作を取り消す。
+/// 失敗しても記録からは消す（同じ操作を何度も戻そうとして、それより前の操作を戻せなくならないように）。
+pub fn undo_last(vault: &Path) -> Result<UndoReport, String> {
+    let mut entries = load(vault);
+    let entry = entries.pop().ok_or("元に戻せる操作がありません")?;
+    save(vault, &entries)?;
+
+    let mut report = UndoReport {
+        operation: entry.operation.clone(),
+        restored: Vec::new(),
+        renamed: Vec::new(),
+        updated: Vec::new(),
+        skipped: Vec::new(),
+    };
+    match entry.operation {
+        Operation::Trash { trashed, .. } => {
+            let trashed = absolute(vault, &trashed);
+            if !trashed.exists() {
+                return Err(format!("ゴミ箱にありません（復元済みか削除済み）: {}", trashed.display()));
+            }
+            let restored = trash::restore(vault, &trashed)?;
+            report.restored.push(restored.to_string_lossy().to_string());
+        }
+        Operation::Archive { original, archived } => {
+            let archived = absolute(vault, &archived);
+            if !archived.exists() {
+                return Err(format!("アーカイブにありません: {}", archived.display()));
+            }
+            let restored = archive::restore_to(vault, &archived, &absolute(vault, &original))?;
+            report.restored.push(restored.to_string_lossy().to_string());
+        }
+        Operation::Rename { old_path, new_path } => {
+            let (old_path, new_path) = (absolute(vault, &old_path), absolute(vault, &new_path));
+            if !new_path.exists() {
+                return Err(format!("ノートが見つかりません: {}", new_path.display()));
+            }
+            if old_path.exists() {
+                return Err(format!("元の名前のノートが既にあります: {}", old_path.display()));
+            }
+            let (old_path, new_path) = (old_path.to_string_lossy().to_string(), new_path.to_string_lossy().to_string());
+            storage::rename_note(&new_path, &old_path)?;
+            report.renamed.push(RenamedNote { old_path: new_path, new_path: old_path });
+        }
+        Operation::DeleteTag { tag, notes } => {
+            for note in notes {
+                let path = absolute(vault, &note.path).to_string_lossy().to_string();
+                let Ok(content) = fs::read_to_string(&path) else {
+                    report.skipped.push(path);
+                    continue;
+                };
+                let mut fm = NoteFrontmatter::from_content(&content);
+                if !fm.tags.contains(&tag) {
+                    let index = note.index.min(fm.tags.len());
+                    fm.tags.insert(index, tag.clone());
+                    if let Err(e) = storage::write_note(&path, &fm.apply_to_content(&content)) {
+                        eprintln!("[Undo] Failed to restore tag '{}' in {}: {}", tag, path, e);
+                        report.skipped.push(path);
+                        continue;
+                    }
+                }
+                report.updated.push(path);
+            }
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_replays_inverse_operations_in_reverse_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        let note = vault.join("0001_2026-01-01_Memo.md");
+        fs::create_dir_all(vault.join("assets/1")).unwrap();
+        fs::write(vault.join("assets/1/a.png"), b"png").unwrap();
+        fs::write(&note, "---\nseq: 1\n---\n\nMemo\n![img](assets/1/a.png)").unwrap();
+        let other = vault.join("0002_2026-01-01_Old.md");
+        fs::write(&other, "Old").unwrap();
+
+        let trashed = trash::move_to_trash(vault, &note, "2026-01-02T00:00:00+09:00").unwrap();
+        record(vault, Operation::trash(vault, &note, &trashed), "t1").unwrap();
+        let renamed = vault.join("0002_2026-01-01_New.md");
+        fs::rename(&other, &renamed).unwrap();
+        record(vault, Operation::rename(vault, &other, &renamed), "t2").unwrap();
+        assert_eq!(last(vault).unwrap().operation.describe(true), "Rename: Old");
+
+        let report = undo_last(vault).unwrap();
+        assert_eq!(report.renamed[0].new_path, other.to_string_lossy());
+        assert!(other.exists() && !renamed.exists());
+
+        let report = undo_last(vault).unwrap();
+        assert_eq!(report.restored, vec![note.to_string_lossy().to_string()]);
+        assert!(note.exists() && vault.join("assets/1/a.png").exists());
+        assert!(!fs::read_to_string(&note).unwrap().contains("trashed"));
+
+        assert!(undo_last(vault).is_err());
+    }
+
+    #[test]
+    fn undo_delete_tag_puts_tag_back_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        let a = vault.join("0001_2026-01-01_A.md");
+        fs::write(&a, "---\ntags: [x, z]\nx: 10\n---\n\nA").unwrap();
+        let gone = vault.join("0002_2026-01-01_B.md").to_string_lossy().to_string();
+        let notes = vec![(a.to_string_lossy().to_string(), 1), (gone.clone(), 0)];
+        record(vault, Operation::delete_tag(vault, "y", &notes), "t").unwrap();
+
+        let report = undo_last(vault).unwrap();
+        assert_eq!(report.updated, vec![a.to_string_lossy().to_string()]);
+        assert_eq!(report.skipped, vec![gone]);
+        let fm = NoteFrontmatter::from_content(&fs::read_to_string(&a).unwrap());
+        assert_eq!(fm.tags, vec!["x", "y", "z"]);
+        assert_eq!(fm.x, Some(10.0));
+    }
+}
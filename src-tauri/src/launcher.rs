@@ -0,0 +1,246 @@
+// [NEW] 「フォルダで表示」「ファイルを開く」の外部コマンド起動
+// 実行部分 (CommandRunner) を差し替えられるので、何も起動せずにテストできる。
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::logger;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LaunchCommand {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl LaunchCommand {
+    fn new(program: &str, args: &[&str]) -> Self {
+        Self {
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+}
+
+pub trait CommandRunner {
+    /// 起動だけして終了は待たない
+    fn spawn(&self, cmd: &LaunchCommand) -> Result<(), String>;
+    /// 終了まで待ち、正常終了したかを返す
+    fn run(&self, cmd: &LaunchCommand) -> Result<bool, String>;
+}
+
+/// 実際にプロセスを起動する Runner
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn spawn(&self, cmd: &LaunchCommand) -> Result<(), String> {
+        Command::new(&cmd.program)
+            .args(&cmd.args)
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| format!("{}: {}", cmd.program, e))
+    }
+
+    fn run(&self, cmd: &LaunchCommand) -> Result<bool, String> {
+        Command::new(&cmd.program)
+            .args(&cmd.args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|s| s.success())
+            .map_err(|e| format!("{}: {}", cmd.program, e))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Platform {
+    Windows,
+    MacOs,
+    /// Linux / BSD (xdg-open + FileManager1)
+    Freedesktop,
+}
+
+impl Platform {
+    pub fn current() -> Self {
+        if cfg!(target_os = "windows") {
+            Platform::Windows
+        } else if cfg!(target_os = "macos") {
+            Platform::MacOs
+        } else {
+            Platform::Freedesktop
+        }
+    }
+}
+
+/// ファイルマネージャでファイルを選択状態で表示する。
+/// ファイルが無い、または選択表示できない場合は親フォルダを開く (Plan B)。
+pub fn reveal_in_file_manager(runner: &impl CommandRunner, platform: Platform, path: &str) -> Result<(), String> {
+    let path = native_path(platform, path);
+    let path_obj = Path::new(&path);
+    logger::log_info(&format!("[DEBUG] reveal_in_file_manager called with: '{}'", path));
+
+    if path_obj.exists() {
+        // Plan A: File exists, select it
+        match platform {
+            Platform::Windows => return runner.spawn(&LaunchCommand::new("explorer", &["/select,", &path])),
+            Platform::MacOs => return runner.spawn(&LaunchCommand::new("open", &["-R", &path])),
+            Platform::Freedesktop => {
+                let cmd = show_items_command(path_obj);
+                if let Ok(true) = runner.run(&cmd) {
+                    return Ok(());
+                }
+                logger::log_warn("[WARN] FileManager1.ShowItems failed. Opening parent folder.");
+            }
+        }
+    } else {
+        logger::log_warn(&format!("[WARN] File not found: '{}'. Opening parent folder.", path));
+    }
+
+    // Plan B: 親フォルダを開く（親が取れなければパスそのもの）
+    let target = path_obj
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or(path.clone());
+    runner.spawn(&open_command(platform, &target))
+}
+
+/// 既定のアプリでファイル（またはフォルダ）を開く
+pub fn open_path(runner: &impl CommandRunner, platform: Platform, path: &str) -> Result<(), String> {
+    let path = native_path(platform, path);
+    runner.spawn(&open_command(platform, &path))
+}
+
+fn open_command(platform: Platform, path: &str) -> LaunchCommand {
+    match platform {
+        // explorer はファイルもフォルダも扱える
+        Platform::Windows => LaunchCommand::new("explorer", &[path]),
+        Platform::MacOs => LaunchCommand::new("open", &[path]),
+        Platform::Freedesktop => LaunchCommand::new("xdg-open", &[path]),
+    }
+}
+
+// org.freedesktop.FileManager1.ShowItems(uris, startup_id)
+// --print-reply で応答を待つので、対応するファイルマネージャが無ければ失敗で返ってくる
+fn show_items_command(path: &Path) -> LaunchCommand {
+    let absolute = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let uri = file_uri(&absolute.to_string_lossy());
+    LaunchCommand::new(
+        "dbus-send",
+        &[
+            "--session",
+            "--print-reply",
+            "--dest=org.freedesktop.FileManager1",
+            "--type=method_call",
+            "/org/freedesktop/FileManager1",
+            "org.freedesktop.FileManager1.ShowItems",
+            &format!("array:string:{}", uri),
+            "string:",
+        ],
+    )
+}
+
+fn native_path(platform: Platform, path: &str) -> String {
+    match platform {
+        // Convert forward slashes to backslashes for Windows
+        Platform::Windows => path.replace('/', "\\"),
+        _ => path.to_string(),
+    }
+}
+
+// file:// URI（スペースや日本語はパーセントエンコード）
+fn file_uri(path: &str) -> String {
+    let mut uri = String::from("file://");
+    for b in path.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => uri.push(b as char),
+            _ => uri.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    uri
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    // 起動したコマンドを記録するだけの Runner
+    struct RecordingRunner {
+        run_succeeds: bool,
+        spawned: RefCell<Vec<LaunchCommand>>,
+        ran: RefCell<Vec<LaunchCommand>>,
+    }
+
+    impl RecordingRunner {
+        fn new(run_succeeds: bool) -> Self {
+            Self { run_succeeds, spawned: RefCell::new(Vec::new()), ran: RefCell::new(Vec::new()) }
+        }
+    }
+
+    impl CommandRunner for RecordingRunner {
+        fn spawn(&self, cmd: &LaunchCommand) -> Result<(), String> {
+            self.spawned.borrow_mut().push(cmd.clone());
+            Ok(())
+        }
+
+        fn run(&self, cmd: &LaunchCommand) -> Result<bool, String> {
+            self.ran.borrow_mut().push(cmd.clone());
+            Ok(self.run_succeeds)
+        }
+    }
+
+    #[test]
+    fn freedesktop_reveal_uses_show_items() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("1_2026-01-01_memo.md");
+        std::fs::write(&file, "x").unwrap();
+        let runner = RecordingRunner::new(true);
+
+        reveal_in_file_manager(&runner, Platform::Freedesktop, &file.to_string_lossy()).unwrap();
+
+        let ran = runner.ran.borrow();
+        assert_eq!(ran.len(), 1);
+        assert_eq!(ran[0].program, "dbus-send");
+        assert!(ran[0].args.iter().any(|a| a.starts_with("array:string:file://") && a.ends_with("1_2026-01-01_memo.md")));
+        assert!(runner.spawned.borrow().is_empty());
+    }
+
+    #[test]
+    fn freedesktop_reveal_falls_back_to_parent_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("memo.md");
+        std::fs::write(&file, "x").unwrap();
+        let parent = dir.path().to_string_lossy().to_string();
+
+        // ShowItems が失敗した場合
+        let runner = RecordingRunner::new(false);
+        reveal_in_file_manager(&runner, Platform::Freedesktop, &file.to_string_lossy()).unwrap();
+        assert_eq!(runner.spawned.borrow()[0], LaunchCommand::new("xdg-open", &[&parent]));
+
+        // ファイルが存在しない場合は ShowItems を試さない
+        let runner = RecordingRunner::new(true);
+        let missing = dir.path().join("missing.md");
+        reveal_in_file_manager(&runner, Platform::Freedesktop, &missing.to_string_lossy()).unwrap();
+        assert!(runner.ran.borrow().is_empty());
+        assert_eq!(runner.spawned.borrow()[0], LaunchCommand::new("xdg-open", &[&parent]));
+    }
+
+    #[test]
+    fn open_path_per_platform() {
+        let runner = RecordingRunner::new(true);
+        open_path(&runner, Platform::Freedesktop, "/tmp/a.md").unwrap();
+        open_path(&runner, Platform::MacOs, "/tmp/a.md").unwrap();
+        open_path(&runner, Platform::Windows, "C:/notes/a.md").unwrap();
+
+        let spawned = runner.spawned.borrow();
+        assert_eq!(spawned[0], LaunchCommand::new("xdg-open", &["/tmp/a.md"]));
+        assert_eq!(spawned[1], LaunchCommand::new("open", &["/tmp/a.md"]));
+        assert_eq!(spawned[2], LaunchCommand::new("explorer", &["C:\\notes\\a.md"]));
+    }
+
+    #[test]
+    fn file_uri_escapes_spaces_and_japanese() {
+        assert_eq!(file_uri("/home/me/my notes/a.md"), "file:///home/me/my%20notes/a.md");
+        assert_eq!(file_uri("/メモ"), "file:///%E3%83%A1%E3%83%A2");
+    }
+}
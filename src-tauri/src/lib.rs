@@ -6,16 +6,34 @@ use raw_window_handle::HasWindowHandle;
 // use tauri::menu::{Menu, MenuItem, CheckMenuItem, Submenu, PredefinedMenuItem, MenuEvent};
 
 mod state;
+mod frontmatter; // フロントマターの型付きモデル
 mod logic;
 mod storage;
 mod tray;
 mod logger;  // ログシステム
+mod paths; // [NEW] OSごとの保存先
+mod launcher; // [NEW] 外部コマンド起動（フォルダで表示/ファイルを開く）
+mod index; // [NEW] メタデータインデックス
+mod search; // [NEW] 全文検索インデックス
+mod query; // [NEW] 検索クエリ言語
 mod settings; 
 mod import; // [NEW] インポート機能
 mod capture; // [NEW] キャプチャ機能
 mod sound; // [NEW] サウンド機能
 mod clipboard; // [NEW] クリップボード機能
-use state::{AppState, Note, NoteMeta};
+mod watcher; // [NEW] Vault監視
+mod replace; // [NEW] 一括置換
+mod trash; // [NEW] ゴミ箱（復元）
+mod archive; // [NEW] アーカイブからの復元
+mod assets; // [NEW] 画像の参照カウント
+mod history; // [NEW] ノートの履歴
+mod journal; // [NEW] 操作の取り消し
+mod transaction; // [NEW] Effect の実行（失敗したら元に戻す）
+mod backup; // [NEW] Vault のバックアップ
+mod gitsync; // [NEW] git による履歴と同期
+use state::{AppState, Note, NoteMeta, NoteVersion, NoteWriteError};
+use search::SearchGroup;
+use query::QueryError;
 
 // --- Commands ---
 
@@ -51,33 +69,16 @@ fn fusen_pick_folder() -> Option<String> {
 
 #[tauri::command]
 fn fusen_get_note(state: State<'_, Mutex<AppState>>, path: String) -> Result<NoteMeta, String> {
-    // 1. Read note content
+    // 1. Read note content (ファイル名 + フロントマターからメタ情報を解析済み)
     let note = storage::read_note(&path)?;
-    
-    // 2. Parse Filename for basic meta
-    let path_obj = Path::new(&path);
-    let filename = path_obj.file_name()
-        .ok_or("Invalid path")?
-        .to_string_lossy()
-        .to_string();
-    let (seq, updated, context) = logic::parse_filename(&filename);
-
-    // 3. Parse Content for extended meta
-    let (x, y, w, h, bg, aot, tags) = logic::extract_meta_from_content(&note.body);
-
-    let meta = NoteMeta {
-        path: path.clone(),
-        seq,
-        context,
-        updated,
-        x, y, width: w, height: h,
-        background_color: bg,
-        always_on_top: aot,
-        tags,
-    };
+    let meta = note.meta;
 
-    // 4. Update AppState
-    logic::apply_update_note(&mut *state.lock().unwrap(), &path, meta.clone());
+    // 2. Update AppState
+    let mut app_state = state.lock().unwrap();
+    logic::apply_update_note(&mut app_state, &path, meta.clone());
+    if let Some(version) = note.version {
+        logic::apply_known_version(&mut app_state, &path, version);
+    }
 
     Ok(meta)
 }
@@ -141,9 +142,14 @@ fn fusen_read_note(state: State<'_, Mutex<AppState>>, path: String) -> Note {
         body: String::new(),
         frontmatter: String::new(),
         meta: NoteMeta { path: path.clone(), ..Default::default() },
+        version: None,
     });
     
-    logic::apply_select_note(&mut *state.lock().unwrap(), path);
+    let mut app_state = state.lock().unwrap();
+    if let Some(version) = &note.version {
+        logic::apply_known_version(&mut app_state, &path, version.clone());
+    }
+    logic::apply_select_note(&mut app_state, path);
     note
 }
 
@@ -152,16 +158,22 @@ fn fusen_create_note(state: State<'_, Mutex<AppState>>, folder_path: String, con
     let today = chrono::Local::now().format("%Y-%m-%d").to_string();
     let next_seq = storage::get_next_seq(&folder_path);
     
-    let data = logic::build_create_note_data(&folder_path, &context, next_seq, &today);
+    let data = logic::build_create_note_data(&folder_path, &context, next_seq, &storage::new_note_id(), &today);
     
     storage::write_note(&data.path_str, &data.content)?;
+    let version = storage::note_version(&data.path_str).ok();
     
-    logic::apply_add_note(&mut *state.lock().unwrap(), data.meta.clone());
+    let mut app_state = state.lock().unwrap();
+    logic::apply_add_note(&mut app_state, data.meta.clone());
+    if let Some(version) = &version {
+        logic::apply_known_version(&mut app_state, &data.path_str, version.clone());
+    }
     
     Ok(Note {
         body: data.body,
         frontmatter: data.frontmatter,
         meta: data.meta,
+        version,
     })
 }
 
@@ -171,8 +183,9 @@ fn fusen_save_note(
     path: String, 
     body: String, 
     frontmatter_raw: String,
-    allow_rename: bool
-) -> Result<String, String> {
+    allow_rename: bool,
+    expected_version: Option<NoteVersion>,
+) -> Result<String, NoteWriteError> {
     // Read old content for change detection
     let old_note = storage::read_note(&path).ok();
     let old_body = old_note.as_ref().map(|n| {
@@ -183,6 +196,9 @@ fn fusen_save_note(
     }).unwrap_or_default();
 
     let mut app_state = state.lock().unwrap();
+
+    // [NEW] 外部で変更されていたら上書きしない
+    ensure_not_modified(&app_state, &path, expected_version, old_note.as_ref().and_then(|n| n.version.as_ref()))?;
     
     // Logicに全て任せる
     let (new_path, effect) = logic::handle_save_note(
@@ -194,24 +210,155 @@ fn fusen_save_note(
         allow_rename
     )?;
     
+    // [NEW] 本文が変わる保存なら、書き換える前の内容を履歴に残す
+    if let Some(old) = old_note.as_ref() {
+        record_history(&app_state, &path, &old.body, &body, &effect);
+    }
+
     // CommandはI/Oを実行するだけ
-    match effect {
-        logic::Effect::WriteNote { path, content } => storage::write_note(&path, &content)?,
-        logic::Effect::RenameNote { old_path, new_path } => storage::rename_note(&old_path, &new_path)?,
-        logic::Effect::Batch(effects) => {
-            for e in effects {
-                match e {
-                    logic::Effect::WriteNote { path, content } => storage::write_note(&path, &content)?,
-                    logic::Effect::RenameNote { old_path, new_path } => storage::rename_note(&old_path, &new_path)?,
-                    logic::Effect::Batch(_) => {} // Nested batch not supported
-                }
-            }
-        },
+    let writes_note = effect.writes_note();
+    run_effect(effect)?;
+    sync_archive_links(&app_state, &path, &new_path);
+    // [NEW] git の自動コミットを予約（有効なときだけ実行される）
+    if writes_note {
+        gitsync::note_saved(&new_path);
+    }
+
+    if new_path != path {
+        app_state.known_versions.remove(&path);
     }
+    remember_version(&mut app_state, &new_path);
     
     Ok(new_path)
 }
 
+// [NEW] 書き換える前の内容を履歴に残す（直前のスナップショットが新しければまとめる）
+fn record_history(app_state: &AppState, path: &str, old_content: &str, new_body: &str, effect: &logic::Effect) {
+    if !effect.writes_note() {
+        return;
+    }
+    let Some(vault) = app_state.base_path.clone().or(app_state.folder_path.clone()) else { return };
+    let now = chrono::Local::now().fixed_offset();
+    if let Err(e) = history::record(Path::new(&vault), Path::new(path), old_content, new_body, now) {
+        eprintln!("[History] Failed to record revision for {}: {}", path, e);
+    }
+}
+
+// [NEW] tags/ にアーカイブされたノートなら、他のタグのフォルダのリンクを今のタグに合わせる
+fn sync_archive_links(app_state: &AppState, old_path: &str, new_path: &str) {
+    let Some(vault) = app_state.base_path.clone().or(app_state.folder_path.clone()) else { return };
+    let vault = Path::new(&vault);
+    if !archive::is_tag_archived(vault, Path::new(new_path)) {
+        return;
+    }
+    let layout = storage::load_settings().map(|s| s.archive_layout).unwrap_or_default();
+    let old_path = (old_path != new_path).then(|| Path::new(old_path));
+    if let Err(e) = archive::sync_links(vault, Path::new(new_path), old_path, layout) {
+        eprintln!("[Archive] Failed to sync links for {}: {}", new_path, e);
+    }
+}
+
+// [NEW] 実行は transaction に任せる（Batch の途中で失敗したら実行済みの分を戻す）
+fn run_effect(effect: logic::Effect) -> Result<(), String> {
+    transaction::run(effect)
+}
+
+// [NEW] 競合検出: expected が渡されなければ、最後に読み書きした時点のバージョンと比較する
+fn ensure_not_modified(
+    app_state: &AppState,
+    path: &str,
+    expected: Option<NoteVersion>,
+    actual: Option<&NoteVersion>,
+) -> Result<(), NoteWriteError> {
+    let Some(actual) = actual else { return Ok(()) };
+    let expected = expected.or_else(|| app_state.known_versions.get(path).cloned());
+    let result = logic::check_version(path, expected.as_ref(), actual);
+    if result.is_err() {
+        logger::log_warn(&format!("外部で変更されたため保存を中止しました: {}", path));
+    }
+    result
+}
+
+// [NEW] 自分で書き込んだ後のバージョンを記録（自分の書き込みを外部変更と誤認しないため）
+fn remember_version(app_state: &mut AppState, path: &str) {
+    if let Ok(version) = storage::note_version(path) {
+        logic::apply_known_version(app_state, path, version);
+    }
+}
+
+// [NEW] 元に戻せるように操作を記録する（記録に失敗しても操作自体は成功扱い）
+fn record_operation(vault: &Path, operation: journal::Operation) {
+    let at = chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false);
+    if let Err(e) = journal::record(vault, operation, &at) {
+        eprintln!("[Journal] Failed to record operation: {}", e);
+    }
+}
+
+// [NEW] 最後の操作（ゴミ箱へ移動・アーカイブ・リネーム・タグの一括削除）を取り消す
+#[tauri::command]
+fn fusen_undo_last_operation(app: tauri::AppHandle) -> Result<journal::UndoReport, String> {
+    undo_last_operation(&app)
+}
+
+/// トレイの「元に戻す」からも呼ぶ
+pub fn undo_last_operation<R: tauri::Runtime>(app: &AppHandle<R>) -> Result<journal::UndoReport, String> {
+    let state = app.state::<Mutex<AppState>>();
+    let mut app_state = state.lock().unwrap();
+    let base_path = app_state.base_path.clone().or(app_state.folder_path.clone()).ok_or("base_path is not set")?;
+
+    let report = match journal::undo_last(Path::new(&base_path)) {
+        Ok(report) => report,
+        Err(e) => {
+            drop(app_state);
+            logger::log_warn(&format!("元に戻せませんでした: {}", e));
+            let _ = crate::tray::refresh_tray_menu(app);
+            return Err(e);
+        }
+    };
+
+    for path in &report.restored {
+        if let Ok(note) = storage::read_note(path) {
+            logic::apply_add_note(&mut app_state, note.meta);
+        }
+        remember_version(&mut app_state, path);
+        sync_archive_links(&app_state, path, path);
+        gitsync::note_saved(path);
+    }
+    for renamed in &report.renamed {
+        if let Some(version) = app_state.known_versions.remove(&renamed.old_path) {
+            logic::apply_known_version(&mut app_state, &renamed.new_path, version);
+        }
+        if let Ok(note) = storage::read_note(&renamed.new_path) {
+            logic::apply_update_note(&mut app_state, &renamed.old_path, note.meta);
+        }
+        sync_archive_links(&app_state, &renamed.old_path, &renamed.new_path);
+        gitsync::note_saved(&renamed.new_path);
+    }
+    for path in &report.updated {
+        if let Ok(note) = storage::read_note(path) {
+            logic::apply_update_note(&mut app_state, path, note.meta);
+        }
+        remember_version(&mut app_state, path);
+        sync_archive_links(&app_state, path, path);
+        gitsync::note_saved(path);
+    }
+    drop(app_state);
+
+    for path in &report.restored {
+        let _ = app.emit("fusen:note_created", path);
+    }
+    for renamed in &report.renamed {
+        let _ = app.emit("fusen:note_renamed", renamed);
+    }
+    for path in &report.updated {
+        let _ = app.emit("fusen:reload_note", path);
+    }
+    let _ = app.emit("fusen:notes_updated", ());
+    let _ = crate::tray::refresh_tray_menu(app);
+    logger::log_info(&format!("元に戻しました: {}", report.operation.describe(false)));
+    Ok(report)
+}
+
 #[tauri::command]
 fn fusen_move_to_trash(
     window: tauri::Window,
@@ -220,20 +367,23 @@ fn fusen_move_to_trash(
 ) -> Result<String, String> {
     let current_path = Path::new(&path);
     let parent = current_path.parent().ok_or("no parent")?;
-    
-    let trash_dir = storage::ensure_trash_dir(parent)?;
-    
-    let filename = current_path.file_name().ok_or("no name")?.to_string_lossy();
-    let new_path = trash_dir.join(filename.as_ref());
-    let new_path_str = new_path.to_string_lossy().to_string();
-    
+    // [NEW] ゴミ箱は Vault 直下にまとめる（復元用に元の場所と日時を記録）
+    let vault_root = {
+        let app_state = state.lock().unwrap();
+        app_state.base_path.clone().or(app_state.folder_path.clone())
+    };
+    let vault = vault_root.as_deref().map(Path::new).unwrap_or(parent);
+    let trashed_at = chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false);
+
+    // [NEW] 他のタグのフォルダに置いたリンクは残さない
+    archive::remove_links(vault, current_path)?;
     // Move associated assets (images) to Trash as well
-    storage::copy_associated_assets(current_path, &trash_dir)?;
-    storage::delete_associated_assets(current_path)?;
-    
-    storage::rename_note(&path, &new_path_str)?;
+    let new_path = trash::move_to_trash(vault, current_path, &trashed_at)?;
+    let new_path_str = new_path.to_string_lossy().to_string();
     
     logic::apply_remove_note(&mut *state.lock().unwrap(), &path);
+    record_operation(vault, journal::Operation::trash(vault, current_path, &new_path));
+    let _ = crate::tray::refresh_tray_menu(window.app_handle());
     
     // Close the window after successful trash move
     let _ = window.close();
@@ -241,6 +391,149 @@ fn fusen_move_to_trash(
     Ok(new_path_str)
 }
 
+// [NEW] ゴミ箱の一覧
+#[tauri::command]
+fn fusen_list_trash(state: State<'_, Mutex<AppState>>) -> Result<Vec<trash::TrashedNote>, String> {
+    let app_state = state.lock().unwrap();
+    let base_path = app_state.base_path.clone().or(app_state.folder_path.clone()).ok_or("base_path is not set")?;
+    Ok(trash::list_trashed(Path::new(&base_path)))
+}
+
+// [NEW] ゴミ箱から元の場所へ戻す。戻したノートのパスを返す（seq が埋まっていれば振り直す）
+#[tauri::command]
+fn fusen_restore_note(
+    state: State<'_, Mutex<AppState>>,
+    app: tauri::AppHandle,
+    path: String
+) -> Result<String, String> {
+    let mut app_state = state.lock().unwrap();
+    let base_path = app_state.base_path.clone().or(app_state.folder_path.clone()).ok_or("base_path is not set")?;
+
+    let restored = trash::restore(Path::new(&base_path), Path::new(&path))?;
+    let restored_str = restored.to_string_lossy().to_string();
+    eprintln!("[Trash] Restored {} -> {}", path, restored_str);
+
+    let note = storage::read_note(&restored_str)?;
+    logic::apply_add_note(&mut app_state, note.meta);
+    remember_version(&mut app_state, &restored_str);
+    sync_archive_links(&app_state, &restored_str, &restored_str);
+    drop(app_state);
+
+    let _ = app.emit("fusen:note_created", &restored_str);
+    let _ = app.emit("fusen:notes_updated", ());
+    let _ = crate::tray::refresh_tray_menu(&app);
+    Ok(restored_str)
+}
+
+// [NEW] アーカイブの一覧（Archive/ と tags/ 以下）
+#[tauri::command]
+fn fusen_list_archived(state: State<'_, Mutex<AppState>>) -> Result<Vec<archive::ArchivedNote>, String> {
+    let app_state = state.lock().unwrap();
+    let base_path = app_state.base_path.clone().or(app_state.folder_path.clone()).ok_or("base_path is not set")?;
+    Ok(archive::list_archived(Path::new(&base_path)))
+}
+
+// [NEW] アーカイブから Vault 直下へ戻し、付箋として開く
+#[tauri::command]
+fn fusen_unarchive_note(
+    state: State<'_, Mutex<AppState>>,
+    app: tauri::AppHandle,
+    path: String
+) -> Result<String, String> {
+    let mut app_state = state.lock().unwrap();
+    let base_path = app_state.base_path.clone().or(app_state.folder_path.clone()).ok_or("base_path is not set")?;
+
+    let restored = archive::unarchive(Path::new(&base_path), Path::new(&path))?;
+    let restored_str = restored.to_string_lossy().to_string();
+    eprintln!("[Archive] Unarchived {} -> {}", path, restored_str);
+
+    let note = storage::read_note(&restored_str)?;
+    logic::apply_add_note(&mut app_state, note.meta);
+    remember_version(&mut app_state, &restored_str);
+    drop(app_state);
+
+    let _ = app.emit("fusen:note_created", &restored_str);
+    let _ = app.emit("fusen:notes_updated", ());
+    let _ = crate::tray::refresh_tray_menu(&app);
+    Ok(restored_str)
+}
+
+// [NEW] ゴミ箱を空にする（他のノートが使っていない画像も消す）
+#[tauri::command]
+fn fusen_empty_trash(state: State<'_, Mutex<AppState>>) -> Result<trash::PurgeReport, String> {
+    let base_path = {
+        let app_state = state.lock().unwrap();
+        app_state.base_path.clone().or(app_state.folder_path.clone()).ok_or("base_path is not set")?
+    };
+    let report = trash::empty_trash(Path::new(&base_path))?;
+    logger::log_info(&format!("[Trash] ゴミ箱を空にしました: ノート {} 件, 画像 {} 件", report.notes.len(), report.assets.len()));
+    Ok(report)
+}
+
+// [NEW] どのノートからも参照されていない画像の一覧
+#[tauri::command]
+fn fusen_list_orphaned_assets(state: State<'_, Mutex<AppState>>) -> Result<Vec<assets::OrphanedAsset>, String> {
+    let base_path = {
+        let app_state = state.lock().unwrap();
+        app_state.base_path.clone().or(app_state.folder_path.clone()).ok_or("base_path is not set")?
+    };
+    Ok(assets::list_orphans(Path::new(&base_path)))
+}
+
+// [NEW] 一覧で選んだ未使用の画像を削除する。削除したパスを返す
+#[tauri::command]
+fn fusen_delete_orphaned_assets(state: State<'_, Mutex<AppState>>, paths: Vec<String>) -> Result<Vec<String>, String> {
+    let base_path = {
+        let app_state = state.lock().unwrap();
+        app_state.base_path.clone().or(app_state.folder_path.clone()).ok_or("base_path is not set")?
+    };
+    let deleted = assets::delete_orphans(Path::new(&base_path), &paths)?;
+    logger::log_info(&format!("[Assets] 未使用の画像を削除しました: {} 件", deleted.len()));
+    Ok(deleted)
+}
+
+// [NEW] 既存の assets/ をハッシュ名にそろえて重複をまとめる（1回だけ実行する移行用）
+#[tauri::command]
+fn fusen_dedupe_assets(state: State<'_, Mutex<AppState>>, app: tauri::AppHandle) -> Result<assets::DedupeReport, String> {
+    let mut app_state = state.lock().unwrap();
+    let base_path = app_state.base_path.clone().or(app_state.folder_path.clone()).ok_or("base_path is not set")?;
+    let report = assets::dedupe(Path::new(&base_path))?;
+    for note in &report.notes {
+        remember_version(&mut app_state, note);
+    }
+    drop(app_state);
+
+    for note in &report.notes {
+        let _ = app.emit("fusen:reload_note", note);
+    }
+    logger::log_info(&format!(
+        "[Assets] 重複をまとめました: 削除 {} 件, 改名 {} 件, ノート {} 件",
+        report.removed.len(), report.renamed, report.notes.len()
+    ));
+    Ok(report)
+}
+
+// [NEW] 既存の画像をノートごとのフォルダ (assets/<seq>/) へ移す（1回だけ実行する移行用）
+#[tauri::command]
+fn fusen_organize_assets(state: State<'_, Mutex<AppState>>, app: tauri::AppHandle) -> Result<assets::OrganizeReport, String> {
+    let mut app_state = state.lock().unwrap();
+    let base_path = app_state.base_path.clone().or(app_state.folder_path.clone()).ok_or("base_path is not set")?;
+    let report = assets::organize(Path::new(&base_path))?;
+    for note in &report.notes {
+        remember_version(&mut app_state, note);
+    }
+    drop(app_state);
+
+    for note in &report.notes {
+        let _ = app.emit("fusen:reload_note", note);
+    }
+    logger::log_info(&format!(
+        "[Assets] 画像をノートごとのフォルダへ移しました: ノート {} 件, 元の画像 {} 件を削除",
+        report.notes.len(), report.cleaned.len()
+    ));
+    Ok(report)
+}
+
 #[tauri::command]
 fn fusen_archive_note(
     window: tauri::Window,
@@ -250,8 +543,7 @@ fn fusen_archive_note(
     let current_path = std::path::Path::new(&path);
     
     // 1. Get current tags
-    let content = storage::read_note(&path)?;
-    let (_, _, _, _, _, _, tags) = logic::extract_meta_from_content(&content.body);
+    let tags = storage::read_note(&path)?.meta.tags;
     
     // 2. Determine vault root
     let vault_root = {
@@ -260,42 +552,42 @@ fn fusen_archive_note(
             .ok_or("Vault root not found")?
     };
     let vault_root_path = std::path::Path::new(&vault_root);
+    // [NEW] 他のノートも使っている画像は元の場所に残す（コピーだけする）
+    let refs = assets::AssetRefs::scan(vault_root_path);
+    let is_shared = |asset: &Path| refs.shared_with_others(asset, current_path);
 
     // 3. Move/Link files and handle assets
-    if tags.is_empty() {
-        // Tagless notes go to general "Archive" folder (Move)
-        let archive_dir = storage::ensure_archive_dir(vault_root_path)?;
-        let new_path = archive_dir.join(current_path.file_name().ok_or("no name")?);
-        let new_path_str = new_path.to_string_lossy().to_string();
-
-        // [New] Copy associated assets BEFORE moving the note
-        storage::copy_associated_assets(current_path, &archive_dir)?;
-
-        // [New] Delete original assets after copy (Move)
-        storage::delete_associated_assets(current_path)?;
-
-        storage::rename_note(&path, &new_path_str)?;
-    } else {
-        // Tagged notes: Move to the first tag folder only
-        let first_tag = &tags[0];
+    // [NEW] フォルダ名にできないタグ（".." や "a/b"）は飛ばす
+    let first_tag = tags.iter().find(|tag| archive::is_folder_tag(tag));
+    let new_path = if let Some(first_tag) = first_tag {
+        // Tagged notes: Move to the first tag folder
         let tag_dir = storage::ensure_tag_dir(vault_root_path, first_tag)?;
         let new_path = tag_dir.join(current_path.file_name().ok_or("no name")?);
-        let new_path_str = new_path.to_string_lossy().to_string();
 
         // Move the file and assets to the first tag folder
-        storage::copy_associated_assets(current_path, &tag_dir)?;
-        storage::delete_associated_assets(current_path)?;
-        storage::rename_note(&path, &new_path_str)?;
-    }
+        storage::move_note_with_assets(current_path, &new_path, is_shared, |_| {})?;
+
+        // [NEW] AllTags なら他のタグのフォルダにリンクを置く
+        // （ノートはもう移したので、リンクに失敗してもアーカイブ自体は続ける）
+        let layout = storage::load_settings().map(|s| s.archive_layout).unwrap_or_default();
+        if let Err(e) = archive::sync_links(vault_root_path, &new_path, None, layout) {
+            eprintln!("[Archive] Failed to sync links for {}: {}", new_path.display(), e);
+        }
+        new_path
+    } else {
+        // Tagless notes go to general "Archive" folder (Move)
+        let archive_dir = storage::ensure_archive_dir(vault_root_path)?;
+        let new_path = archive_dir.join(current_path.file_name().ok_or("no name")?);
+
+        // [NEW] ノートと画像 (assets/<seq>/) を一緒に移す
+        storage::move_note_with_assets(current_path, &new_path, is_shared, |_| {})?;
+        new_path
+    };
     
     // 4. Update state
     logic::apply_remove_note(&mut *state.lock().unwrap(), &path);
-    
-    // 5. Cleanup original assets? (Optional but requested as "移動")
-    // Note: copy_associated_assets used fs::copy. 
-    // If we want "Move", we should delete original after successful move of the note.
-    // However, since multiple notes might share assets (rare in this app but possible),
-    // we'll stick to Copy-and-Success-Move for now.
+    record_operation(vault_root_path, journal::Operation::archive(vault_root_path, current_path, &new_path));
+    let _ = crate::tray::refresh_tray_menu(window.app_handle());
     
     // Close the window
     let _ = window.close();
@@ -303,113 +595,66 @@ fn fusen_archive_note(
     Ok("Archived successfully".to_string())
 }
 
-// [NEW] 全文検索
-#[derive(serde::Serialize, Clone)]
-pub struct SearchHit {
-    pub path: String,
-    pub line: usize,
-    pub preview: String,
-}
-
+// [NEW] 全文検索（search.rs の転置インデックス + query.rs のクエリ言語）
 #[tauri::command]
 fn fusen_search_notes(
     state: State<'_, Mutex<AppState>>,
     query: String
-) -> Vec<SearchHit> {
+) -> Result<Vec<SearchGroup>, QueryError> {
     let app_state = state.lock().unwrap();
     let folder_path = match app_state.base_path.as_ref().or(app_state.folder_path.as_ref()) {
         Some(p) => p.clone(),
         None => {
             eprintln!("[Search] No folder path configured!");
-            return Vec::new();
+            return Ok(Vec::new());
         }
     };
     drop(app_state);
 
     eprintln!("[Search] Searching for '{}' in folder: {}", query, folder_path);
 
-    let hits = search_notes_logic(&folder_path, &query);
+    let groups = search_notes_logic(&folder_path, &query)?;
 
-    eprintln!("[Search] Found {} hits", hits.len());
-    hits
+    eprintln!("[Search] Found {} notes", groups.len());
+    Ok(groups)
 }
 
-fn search_notes_logic(folder_path: &str, query: &str) -> Vec<SearchHit> {
-    use std::io::BufRead;
-    
-    let mut hits = Vec::new();
-    let query_lower = query.to_lowercase();
-    
-    for entry in walkdir::WalkDir::new(folder_path)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| {
-            // [Fix] Exclude Trash folder
-            let path_str = e.path().to_string_lossy();
-            if path_str.contains("\\Trash\\") || path_str.contains("/Trash/") || path_str.ends_with("Trash") {
-                return false;
-            }
-            e.path().extension().map_or(false, |ext| ext == "md")
-        })
-    {
-        if let Ok(file) = std::fs::File::open(entry.path()) {
-            let reader = std::io::BufReader::new(file);
-            
-            // [Fix Line Numbers] State machine to track Body lines only
-            let mut is_frontmatter = false;
-            let mut body_started = false; // [Fix] Track if we hit the first non-empty body line
-            let mut body_line_counter = 0;
-            
-            for (file_line_idx, line_res) in reader.lines().enumerate() {
-                if let Ok(line) = line_res {
-                    // Check Frontmatter Start
-                    if file_line_idx == 0 && line.trim() == "---" {
-                        is_frontmatter = true;
-                        continue;
-                    }
-                    
-                    // Check Frontmatter End
-                    if is_frontmatter {
-                        if line.trim() == "---" {
-                            is_frontmatter = false;
-                        }
-                        continue;
-                    }
-                    
-                    // Body Logic
-                    // Mimic trim_start(): skip leading blank lines
-                    if !body_started {
-                        if line.trim().is_empty() {
-                            continue;
-                        }
-                        body_started = true;
-                    }
+fn search_notes_logic(folder_path: &str, query: &str) -> Result<Vec<SearchGroup>, QueryError> {
+    search::search(folder_path, query)
+}
 
-                    // Now we are in the "visible" body
-                    body_line_counter += 1;
-                    
-                    if line.to_lowercase().contains(&query_lower) {
-                        let preview = if line.chars().count() > 80 {
-                            let start: String = line.chars().take(80).collect();
-                            format!("{}...", start)
-                        } else {
-                            line.to_string()
-                        };
-                        hits.push(SearchHit {
-                            path: entry.path().to_string_lossy().to_string(),
-                            line: body_line_counter,
-                            preview,
-                        });
-                    }
-                }
-            }
+// [NEW] バックグラウンド検索。結果は呼び出し元のウィンドウに fusen:search_results で小分けに送る。
+// 同じウィンドウから新しい request_id で呼ぶと、そのウィンドウの前の検索は打ち切られる。
+#[tauri::command]
+fn fusen_start_search(
+    window: tauri::Window,
+    state: State<'_, Mutex<AppState>>,
+    query: String,
+    request_id: u64
+) -> Result<(), QueryError> {
+    let app_state = state.lock().unwrap();
+    let Some(folder_path) = app_state.base_path.clone().or(app_state.folder_path.clone()) else {
+        eprintln!("[Search] No folder path configured!");
+        return Ok(());
+    };
+    drop(app_state);
+
+    eprintln!("[Search] Job {} started for '{}'", request_id, query);
+    let label = window.label().to_string();
+    search::start_job(label.clone(), folder_path, &query, request_id, move |batch| {
+        if let Err(e) = window.emit_to(label.as_str(), "fusen:search_results", batch) {
+            logger::log_warn(&format!("[Search] Failed to emit results: {}", e));
         }
-    }
-    hits
+    })
 }
 
 #[tauri::command]
-fn fusen_rename_note(state: State<'_, Mutex<AppState>>, path: String, new_context: String) -> Result<String, String> {
+fn fusen_cancel_search(window: tauri::Window, request_id: u64) {
+    search::cancel_job(window.label(), request_id);
+}
+
+#[tauri::command]
+fn fusen_rename_note(state: State<'_, Mutex<AppState>>, app: tauri::AppHandle, path: String, new_context: String) -> Result<String, String> {
     let current_path = Path::new(&path);
     let filename = current_path.file_name().ok_or("no name")?.to_string_lossy().to_string();
         
@@ -425,7 +670,24 @@ fn fusen_rename_note(state: State<'_, Mutex<AppState>>, path: String, new_contex
     storage::rename_note(&path, &new_path_str)?;
 
     if let Ok(saved_note) = storage::read_note(&new_path_str) {
-        logic::apply_update_note(&mut *state.lock().unwrap(), &path, saved_note.meta);
+        let mut app_state = state.lock().unwrap();
+        if let Some(version) = app_state.known_versions.remove(&path) {
+            logic::apply_known_version(&mut app_state, &new_path_str, version);
+        }
+        logic::apply_update_note(&mut app_state, &path, saved_note.meta);
+    }
+
+    // [NEW] 元に戻せるように記録する（名前が変わらなかったときは取り消す操作も無い）
+    if new_path_str == path {
+        return Ok(new_path_str);
+    }
+    let vault_root = {
+        let app_state = state.lock().unwrap();
+        app_state.base_path.clone().or(app_state.folder_path.clone())
+    };
+    if let Some(vault) = vault_root.as_deref().map(Path::new) {
+        record_operation(vault, journal::Operation::rename(vault, current_path, &new_path));
+        let _ = crate::tray::refresh_tray_menu(&app);
     }
 
     Ok(new_path_str)
@@ -440,12 +702,14 @@ fn fusen_get_state(state: State<'_, Mutex<AppState>>) -> AppState {
 fn fusen_update_geometry(
     state: State<'_, Mutex<AppState>>,
     path: String,
-    x: f64, y: f64, width: f64, height: f64
-) -> Result<(), String> {
+    x: f64, y: f64, width: f64, height: f64,
+    expected_version: Option<NoteVersion>,
+) -> Result<(), NoteWriteError> {
     let mut app_state = state.lock().unwrap();
     
     // Command層でI/O: 現在の内容を読み込む
     let note = storage::read_note(&path)?;
+    ensure_not_modified(&app_state, &path, expected_version, note.version.as_ref())?;
     
     // Logic層: 更新ロジックとState同期
     let effect = logic::handle_update_geometry(&mut app_state, &path, &note.body, x, y, width, height)?;
@@ -455,6 +719,7 @@ fn fusen_update_geometry(
         logic::Effect::WriteNote { path, content } => storage::write_note(&path, &content)?,
         _ => {}
     }
+    remember_version(&mut app_state, &path);
     
     Ok(())
 }
@@ -474,12 +739,19 @@ fn fusen_open_file(path: String) -> Result<(), String> {
 }
 
 #[tauri::command]
-fn fusen_add_tag(state: State<'_, Mutex<AppState>>, path: String, tag: String, app: tauri::AppHandle) -> Result<(), String> {
+fn fusen_add_tag(
+    state: State<'_, Mutex<AppState>>,
+    path: String,
+    tag: String,
+    expected_version: Option<NoteVersion>,
+    app: tauri::AppHandle,
+) -> Result<(), NoteWriteError> {
     let mut app_state = state.lock().unwrap();
     
     // Read current content
     let content = storage::read_note(&path)
         .map_err(|e| format!("Failed to read note: {}", e))?;
+    ensure_not_modified(&app_state, &path, expected_version, content.version.as_ref())?;
     
     // Add tag
     let effect = logic::handle_add_tag(&mut *app_state, &path, &content.body, &tag)?;
@@ -489,6 +761,8 @@ fn fusen_add_tag(state: State<'_, Mutex<AppState>>, path: String, tag: String, a
         storage::write_note(&path, &content)
             .map_err(|e| format!("Failed to write note: {}", e))?;
     }
+    remember_version(&mut app_state, &path);
+    sync_archive_links(&app_state, &path, &path);
     
     // Update tray menu
     drop(app_state);
@@ -513,6 +787,8 @@ fn fusen_remove_tag(state: State<'_, Mutex<AppState>>, path: String, tag: String
         storage::write_note(&path, &content)
             .map_err(|e| format!("Failed to write note: {}", e))?;
     }
+    remember_version(&mut app_state, &path);
+    sync_archive_links(&app_state, &path, &path);
     
     // Update tray menu
     drop(app_state); // Release lock before calling refresh_tray_menu
@@ -538,20 +814,25 @@ fn fusen_delete_tag_globally(state: State<'_, Mutex<AppState>>, tag: String, app
     
     let mut modified_count = 0;
     let mut modified_paths: Vec<String> = Vec::new(); // Track modified paths
+    let mut removals: Vec<(String, usize)> = Vec::new(); // [NEW] 元に戻すためにタグの位置を記録
     
     // Create a list of paths to process to avoid borrowing issues
-    let paths: Vec<String> = app_state.notes.iter().map(|n| n.path.clone()).collect();
+    // インデックス上のタグで候補を絞ってから実ファイルを読む
+    let tag_trimmed = tag.trim();
+    let paths: Vec<String> = app_state.notes.iter()
+        .filter(|n| n.tags.iter().any(|t| t.trim() == tag_trimmed))
+        .map(|n| n.path.clone())
+        .collect();
     
     // Iterate through all notes
     for path in paths {
         // Read note content
         if let Ok(note) = storage::read_note(&path) {
-            let (_, _, _, _, _, _, tags) = logic::extract_meta_from_content(&note.body);
+            let tags = &note.meta.tags;
             eprintln!("[Global Delete] Checking note: {} - tags: {:?}", path, tags);
 
             // Check if tag exists (trim both sides for safety)
-            let tag_trimmed = tag.trim();
-            if tags.iter().any(|t| t.trim() == tag_trimmed) {
+            if let Some(index) = tags.iter().position(|t| t.trim() == tag_trimmed) {
                 eprintln!("[Global Delete] Found tag '{}' in {}, attempting to remove...", tag, path);
                 // Remove tag
                 if let Ok(effect) = logic::handle_remove_tag(&mut *app_state, &path, &note.body, tag_trimmed) {
@@ -559,7 +840,9 @@ fn fusen_delete_tag_globally(state: State<'_, Mutex<AppState>>, tag: String, app
                         match storage::write_note(&write_path, &content) {
                             Ok(_) => {
                                 eprintln!("[Global Delete] Successfully wrote modified note: {}", write_path);
+                                remember_version(&mut app_state, &write_path);
                                 modified_count += 1;
+                                removals.push((write_path.clone(), index));
                                 modified_paths.push(write_path);
                             },
                             Err(e) => eprintln!("[Global Delete] Failed to write note: {} error: {}", write_path, e),
@@ -574,6 +857,9 @@ fn fusen_delete_tag_globally(state: State<'_, Mutex<AppState>>, tag: String, app
     
     // Update tray menu
     drop(app_state);
+    if !removals.is_empty() {
+        record_operation(Path::new(&base_path), journal::Operation::delete_tag(Path::new(&base_path), tag_trimmed, &removals));
+    }
     let _ = crate::tray::refresh_tray_menu(&app);
     
     // [NEW] Notify each modified window to reload
@@ -586,6 +872,259 @@ fn fusen_delete_tag_globally(state: State<'_, Mutex<AppState>>, tag: String, app
     Ok(modified_count)
 }
 
+// [NEW] 一括置換のプレビュー（書き込みはしない）
+#[tauri::command]
+fn fusen_replace_preview(
+    state: State<'_, Mutex<AppState>>,
+    find: String,
+    replacement: String,
+    case_sensitive: Option<bool>,
+    use_regex: Option<bool>,
+) -> Result<Vec<replace::ReplacePreview>, String> {
+    let replacer = replace::Replacer::new(&find, &replacement, case_sensitive.unwrap_or(false), use_regex.unwrap_or(false))?;
+    let base_path = {
+        let app_state = state.lock().unwrap();
+        app_state.base_path.clone().or(app_state.folder_path.clone()).ok_or("base_path is not set")?
+    };
+
+    let mut previews = Vec::new();
+    for meta in storage::list_notes(&base_path) {
+        let Ok(note) = storage::read_note(&meta.path) else { continue };
+        let Some(version) = note.version.clone() else { continue };
+        let (_, body) = logic::split_frontmatter(&note.body);
+        if let Some((_, count, snippets)) = replacer.apply(body) {
+            previews.push(replace::ReplacePreview { path: meta.path, context: meta.context, version, count, snippets });
+        }
+    }
+    eprintln!("[Replace] Preview '{}': {} notes", find, previews.len());
+    Ok(previews)
+}
+
+// [NEW] 一括置換の適用。プレビューで返したノートだけを、通常の保存と同じ流れで書き換える。
+// プレビュー後に変更されたノートは上書きせずスキップする。
+#[tauri::command]
+fn fusen_replace_apply(
+    state: State<'_, Mutex<AppState>>,
+    app: tauri::AppHandle,
+    find: String,
+    replacement: String,
+    case_sensitive: Option<bool>,
+    use_regex: Option<bool>,
+    targets: Vec<replace::ReplaceTarget>,
+) -> Result<replace::ReplaceReport, String> {
+    let replacer = replace::Replacer::new(&find, &replacement, case_sensitive.unwrap_or(false), use_regex.unwrap_or(false))?;
+    let mut report = replace::ReplaceReport::default();
+    let mut app_state = state.lock().unwrap();
+    if let Some(base_path) = app_state.base_path.clone().or(app_state.folder_path.clone()) {
+        app_state.notes = storage::list_notes(&base_path);
+    }
+
+    for target in targets {
+        let mut skip = |reason: &str| {
+            eprintln!("[Replace] Skipped {}: {}", target.path, reason);
+            report.skipped.push(replace::SkippedNote { path: target.path.clone(), reason: reason.to_string() });
+        };
+        let Ok(note) = storage::read_note(&target.path) else {
+            skip("ファイルが見つかりません");
+            continue;
+        };
+        if note.version.as_ref().map(|v| &v.hash) != Some(&target.version.hash) {
+            skip("プレビュー後に変更されています");
+            continue;
+        }
+        let (front, body) = logic::split_frontmatter(&note.body);
+        let Some((new_body, count, _)) = replacer.apply(body) else {
+            skip("置換する箇所がありません");
+            continue;
+        };
+
+        let saved = logic::handle_save_note(&mut app_state, &target.path, &new_body, body, front, true)
+            .and_then(|(new_path, effect)| {
+                record_history(&app_state, &target.path, &note.body, &new_body, &effect);
+                run_effect(effect).map(|_| new_path)
+            });
+        match saved {
+            Ok(new_path) => {
+                if new_path != target.path {
+                    app_state.known_versions.remove(&target.path);
+                }
+                remember_version(&mut app_state, &new_path);
+                sync_archive_links(&app_state, &target.path, &new_path);
+                gitsync::note_saved(&new_path);
+                report.replaced.push(replace::ReplacedNote { old_path: target.path.clone(), new_path, count });
+            }
+            Err(e) => skip(&e),
+        }
+    }
+    drop(app_state);
+
+    for note in &report.replaced {
+        if note.new_path != note.old_path {
+            let _ = app.emit("fusen:note_renamed", note);
+        }
+        let _ = app.emit("fusen:reload_note", &note.new_path);
+    }
+    if !report.replaced.is_empty() {
+        let _ = crate::tray::refresh_tray_menu(&app);
+    }
+    eprintln!("[Replace] Applied '{}': {} replaced, {} skipped", find, report.replaced.len(), report.skipped.len());
+    Ok(report)
+}
+
+// [NEW] ノートの履歴（新しい順）
+#[tauri::command]
+fn fusen_list_revisions(state: State<'_, Mutex<AppState>>, path: String) -> Result<Vec<history::Revision>, String> {
+    let vault = {
+        let app_state = state.lock().unwrap();
+        app_state.base_path.clone().or(app_state.folder_path.clone()).ok_or("base_path is not set")?
+    };
+    Ok(history::list(Path::new(&vault), Path::new(&path)))
+}
+
+// [NEW] リビジョンどうし（to を省略したら今の内容）の本文の差分
+#[tauri::command]
+fn fusen_diff_revision(
+    state: State<'_, Mutex<AppState>>,
+    path: String,
+    from: String,
+    to: Option<String>,
+) -> Result<Vec<history::DiffLine>, String> {
+    let vault = {
+        let app_state = state.lock().unwrap();
+        app_state.base_path.clone().or(app_state.folder_path.clone()).ok_or("base_path is not set")?
+    };
+    let (vault, note) = (Path::new(&vault), Path::new(&path));
+    let old = history::read(vault, note, &from)?;
+    let new = match to {
+        Some(id) => history::read(vault, note, &id)?,
+        None => storage::read_note(&path)?.body,
+    };
+    let (_, old_body) = logic::split_frontmatter(&old);
+    let (_, new_body) = logic::split_frontmatter(&new);
+    Ok(history::diff_lines(old_body, new_body))
+}
+
+// [NEW] リビジョンの本文に戻す。位置や色（フロントマター）は今のまま。
+// 戻す前の内容も履歴に残すので、復元自体も取り消せる。
+#[tauri::command]
+fn fusen_restore_revision(
+    state: State<'_, Mutex<AppState>>,
+    app: tauri::AppHandle,
+    path: String,
+    id: String,
+) -> Result<String, String> {
+    let mut app_state = state.lock().unwrap();
+    let vault = app_state.base_path.clone().or(app_state.folder_path.clone()).ok_or("base_path is not set")?;
+    let revision = history::read(Path::new(&vault), Path::new(&path), &id)?;
+    let current = storage::read_note(&path)?;
+    let (front, body) = logic::split_frontmatter(&current.body);
+    let (_, revision_body) = logic::split_frontmatter(&revision);
+
+    history::record_now(Path::new(&vault), Path::new(&path), &current.body, chrono::Local::now().fixed_offset())?;
+    let (new_path, effect) = logic::handle_save_note(&mut app_state, &path, revision_body, body, front, true)?;
+    let writes_note = effect.writes_note();
+    run_effect(effect)?;
+    sync_archive_links(&app_state, &path, &new_path);
+    if writes_note {
+        gitsync::note_saved(&new_path);
+    }
+    if new_path != path {
+        app_state.known_versions.remove(&path);
+    }
+    remember_version(&mut app_state, &new_path);
+    drop(app_state);
+
+    if new_path != path {
+        let renamed = replace::ReplacedNote { old_path: path.clone(), new_path: new_path.clone(), count: 0 };
+        let _ = app.emit("fusen:note_renamed", &renamed);
+    }
+    let _ = app.emit("fusen:reload_note", &new_path);
+    logger::log_info(&format!("履歴から復元しました: {} ({})", new_path, id));
+    Ok(new_path)
+}
+
+// [NEW] 今すぐバックアップを作る（自動バックアップが無効でも保存先があれば作れる）
+#[tauri::command]
+fn fusen_create_backup(state: State<'_, Mutex<AppState>>) -> Result<backup::BackupInfo, String> {
+    let vault = {
+        let app_state = state.lock().unwrap();
+        app_state.base_path.clone().or(app_state.folder_path.clone()).ok_or("base_path is not set")?
+    };
+    let options = backup::BackupOptions::from_settings(&storage::load_settings()?)?;
+    let info = backup::create(Path::new(&vault), &options, chrono::Local::now().fixed_offset())?;
+    let removed = backup::prune(&options.dir, options.keep);
+    logger::log_info(&format!("[Backup] バックアップを作成しました: {} (古いもの {} 件を削除)", info.name, removed.len()));
+    Ok(info)
+}
+
+// [NEW] バックアップの一覧（新しい順）
+#[tauri::command]
+fn fusen_list_backups() -> Result<Vec<backup::BackupInfo>, String> {
+    let options = backup::BackupOptions::from_settings(&storage::load_settings()?)?;
+    Ok(backup::list(&options.dir))
+}
+
+// [NEW] バックアップから戻す。target を指定したらその空のフォルダへ展開する。
+// 省略したら Vault に上書きする（confirm が true のときだけ。先に今の Vault をバックアップする）。
+#[tauri::command]
+fn fusen_restore_backup(
+    state: State<'_, Mutex<AppState>>,
+    app: tauri::AppHandle,
+    path: String,
+    target: Option<String>,
+    confirm: Option<bool>,
+) -> Result<backup::RestoreReport, String> {
+    let zip_path = Path::new(&path);
+    if !zip_path.is_file() {
+        return Err(format!("バックアップが見つかりません: {}", path));
+    }
+    if let Some(target) = target {
+        let report = backup::restore_to_folder(zip_path, Path::new(&target))?;
+        logger::log_info(&format!("[Backup] {} を {} に展開しました ({} 件)", path, target, report.files.len()));
+        return Ok(report);
+    }
+    if confirm != Some(true) {
+        return Err("Vault への上書きには確認が必要です".to_string());
+    }
+
+    // zip の作成・展開は時間がかかるので、その間は AppState をロックしない
+    let vault = {
+        let app_state = state.lock().unwrap();
+        app_state.base_path.clone().or(app_state.folder_path.clone()).ok_or("base_path is not set")?
+    };
+    let options = backup::BackupOptions::from_settings(&storage::load_settings()?)?;
+    let report = backup::restore_over_vault(Path::new(&vault), zip_path, &options, chrono::Local::now().fixed_offset())?;
+
+    // 開いているノートの次の保存が「外部で変更された」にならないように、展開後のバージョンを覚える
+    let mut app_state = state.lock().unwrap();
+    app_state.notes = storage::list_notes(&vault);
+    for file in report.files.iter().filter(|f| f.ends_with(".md")) {
+        remember_version(&mut app_state, file);
+    }
+    drop(app_state);
+
+    for file in report.files.iter().filter(|f| f.ends_with(".md")) {
+        let _ = app.emit("fusen:reload_note", file);
+    }
+    let _ = app.emit("fusen:notes_updated", ());
+    let _ = crate::tray::refresh_tray_menu(&app);
+    logger::log_info(&format!("[Backup] {} を Vault に上書きしました ({} 件)", path, report.files.len()));
+    Ok(report)
+}
+
+// [NEW] 今すぐ git にコミットしてリモートと同期する（自動コミットが無効でも使える）
+#[tauri::command]
+async fn fusen_git_sync(app: tauri::AppHandle) -> Result<gitsync::SyncReport, String> {
+    let report = tauri::async_runtime::spawn_blocking(move || gitsync::sync_now(&app, true))
+        .await
+        .map_err(|e| e.to_string())??;
+    logger::log_info(&format!(
+        "[Git] 同期しました (commit: {}, merge: {}, push: {}, 衝突: {})",
+        report.committed, report.merged, report.pushed, report.conflicts.len()
+    ));
+    Ok(report)
+}
+
 #[tauri::command]
 fn fusen_get_all_tags(state: State<'_, Mutex<AppState>>) -> Vec<String> {
     let app_state = state.lock().unwrap();
@@ -608,14 +1147,8 @@ fn get_filtered_note_paths(state: State<'_, Mutex<AppState>>, active_tags: &[Str
         .ok_or("base_path is not set")?;
     drop(app_state);
     
-    // 全ノート取得 & タグ解析
-    let mut all_notes = storage::list_notes(&base_path);
-    for n in all_notes.iter_mut() {
-        if let Ok(note) = storage::read_note(&n.path) {
-            let (_, _, _, _, _, _, tags) = logic::extract_meta_from_content(&note.body);
-            n.tags = tags;
-        }
-    }
+    // 全ノート取得（タグはインデックスから）
+    let all_notes = storage::list_notes(&base_path);
     
     // フィルタリング（OR条件）
     let selected: Vec<String> = active_tags.iter().map(|t| t.trim().to_string()).collect();
@@ -685,12 +1218,11 @@ fn setup_first_launch(
     // 1. ベースパスを決定
     let base_path = if use_default {
         // 推奨パス: Documents/OreNoFusen
-        let docs = std::env::var("USERPROFILE")
-            .map_err(|_| {
-                logger::log_error("USERPROFILE environment variable not found");
-                "USERPROFILE not found".to_string()
-            })?;
-        PathBuf::from(docs).join("Documents").join("OreNoFusen")
+        paths::default_vault_dir()
+            .map_err(|e| {
+                logger::log_error(&format!("Failed to resolve Documents folder: {}", e));
+                e
+            })?
             .to_string_lossy().to_string()
     } else {
         custom_path.ok_or_else(|| {
@@ -742,6 +1274,9 @@ fn setup_first_launch(
         app_state.base_path = Some(base_path.clone());
         app_state.folder_path = Some(base_path.clone());
     }
+    if let Err(e) = watcher::watch_vault(&app_handle, &base_path) {
+        logger::log_warn(&format!("Vaultの監視を開始できませんでした: {}", e));
+    }
 
     // [FIX] イベント発行: フロントエンドに設定変更を通知
     use tauri::Emitter; // Emitterトレイトが必要
@@ -828,22 +1363,20 @@ fn fusen_refresh_notes_with_tags(state: State<'_, Mutex<AppState>>) -> Result<Ve
         .or(app_state.folder_path.clone())
         .ok_or("base_path is not set")?;
 
-    // まず一覧（パス）を取る
-    let mut notes = storage::list_notes(&base_path);
-
-    // 各ノートを読んで tags を確実に詰める
-    for n in notes.iter_mut() {
-        if let Ok(note) = storage::read_note(&n.path) {
-            let (_x, _y, _w, _h, _bg, _aot, tags) = logic::extract_meta_from_content(&note.body);
-            n.tags = tags;
-        }
-    }
+    // 一覧（tags 込み）はインデックスから。変更のあったファイルだけ読み直される
+    let notes = storage::list_notes(&base_path);
 
     // stateにも反映
     app_state.notes = notes.clone();
     Ok(notes)
 }
 
+// [NEW] 起動時リカバリの結果を取得（フロントで通知表示用）
+#[tauri::command]
+fn fusen_get_recovered_files(state: State<'_, Mutex<AppState>>) -> Vec<state::RecoveredFile> {
+    state.lock().unwrap().recovered_files.clone()
+}
+
 // [NEW] ウィンドウをAlt+Tab/タスクビューから除外する（WS_EX_TOOLWINDOW適用）
 #[tauri::command]
 async fn fusen_make_tool_window(window: tauri::Window) -> Result<(), String> {
@@ -901,6 +1434,15 @@ pub fn run() {
             fusen_create_note,
             fusen_save_note,
             fusen_move_to_trash,
+            fusen_list_trash, // [NEW] ゴミ箱
+            fusen_restore_note,
+            fusen_empty_trash,
+            fusen_list_orphaned_assets,
+            fusen_delete_orphaned_assets,
+            fusen_dedupe_assets,
+            fusen_organize_assets,
+            fusen_list_archived, // [NEW] アーカイブから復元
+            fusen_unarchive_note,
             fusen_rename_note,
             fusen_get_state,
             fusen_update_geometry,
@@ -924,15 +1466,34 @@ pub fn run() {
             capture::fusen_capture_screen, // [NEW] 画面キャプチャ
             sound::fusen_play_sound, // [NEW] サウンド再生
             fusen_search_notes, // [NEW] 全文検索
+            fusen_start_search, // [NEW] バックグラウンド検索
+            fusen_cancel_search,
+            fusen_replace_preview, // [NEW] 一括置換
+            fusen_replace_apply,
+            fusen_list_revisions,
+            fusen_diff_revision,
+            fusen_restore_revision,
+            fusen_undo_last_operation,
+            fusen_create_backup,
+            fusen_list_backups,
+            fusen_restore_backup,
+            fusen_git_sync,
             clipboard::fusen_get_image_from_clipboard, // [NEW] クリップボード画像取得
             fusen_make_tool_window, // [NEW] Alt+Tab/タスクビューから除外
+            fusen_get_recovered_files, // [NEW] 起動時リカバリ結果
         ])
         /* .on_menu_event(|app, event| {
              // handle_menu_event(app, &event);
         }) */
         .setup(|app| {
+            // [NEW] 旧保存先からの移行（ログファイルも移すので最初に行う）
+            let migrated = paths::migrate_legacy_files();
+
             // アプリケーション起動ログ
             logger::log_app_start();
+            for m in &migrated {
+                logger::log_info(&format!("旧保存先から移行しました: {}", m));
+            }
             
             // [DEBUG] Startup Environment Diagnosis
             if let Ok(cwd) = std::env::current_dir() {
@@ -961,6 +1522,28 @@ pub fn run() {
                     let mut app_state = state.lock().unwrap();
                     app_state.base_path = settings.base_path.clone();
                     app_state.folder_path = settings.base_path.clone();
+
+                    // [NEW] 前回クラッシュ時に残った一時ファイルを回収
+                    let mut recovered = Vec::new();
+                    if let Ok(settings_path) = storage::get_settings_path() {
+                        if let Some(dir) = settings_path.parent() {
+                            recovered.extend(storage::recover_temp_files(dir));
+                        }
+                    }
+                    if let Some(base) = &settings.base_path {
+                        recovered.extend(storage::recover_temp_files(std::path::Path::new(base)));
+                    }
+                    if !recovered.is_empty() {
+                        logger::log_warn(&format!("一時ファイルを {} 件回収しました", recovered.len()));
+                    }
+                    app_state.recovered_files = recovered;
+
+                    // [NEW] 外部での変更を監視
+                    if let Some(base) = &settings.base_path {
+                        if let Err(e) = watcher::watch_vault(app.handle(), base) {
+                            logger::log_warn(&format!("Vaultの監視を開始できませんでした: {}", e));
+                        }
+                    }
                     
                     if settings.base_path.is_some() {
                         logger::log_info("保存先フォルダは設定済みです");
@@ -973,6 +1556,13 @@ pub fn run() {
                     logger::log_info("初回起動またはクリーンインストールを検出しました");
                 }
             }
+
+            // [NEW] ゴミ箱の期限切れノートを起動時と定期的に削除
+            trash::start_retention_sweeper(app.handle().clone());
+            // [NEW] 自動バックアップ
+            backup::start_scheduler(app.handle().clone());
+            // [NEW] git の自動コミット
+            gitsync::start_worker(app.handle().clone());
             
             if cfg!(debug_assertions) {
                 app.handle().plugin(tauri_plugin_log::Builder::default().build())?;
@@ -1031,8 +1621,15 @@ pub fn run() {
             logger::log_info("アプリの初期化が完了しました");
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            // [NEW] 終了時にもバックアップ（前回から変更があれば）と、待っている git のコミット
+            if let tauri::RunEvent::Exit = event {
+                gitsync::flush_on_exit(app);
+                backup::run_if_due(app, true);
+            }
+        });
 }
 
 
@@ -1052,7 +1649,7 @@ mod search_tests {
         fs::write(&file1, "Hello World\nThis is a test.").unwrap();
         fs::write(&file2, "Another note\nHello there.").unwrap();
         
-        let hits = search_notes_logic(dir.path().to_str().unwrap(), "Hello");
+        let hits = search_notes_logic(dir.path().to_str().unwrap(), "Hello").unwrap();
         assert_eq!(hits.len(), 2);
     }
 }
@@ -7,11 +7,9 @@ const MAX_LOG_SIZE: u64 = 5 * 1024 * 1024; // 5MB
 const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// ログファイルのパスを取得
-/// インストールフォルダ（%LOCALAPPDATA%\ore-no-fusen\）に配置
+/// OSごとのデータフォルダ配下（paths::log_dir）に配置
 pub fn get_log_path() -> Result<PathBuf, String> {
-    let app_data = std::env::var("LOCALAPPDATA")
-        .map_err(|_| "LOCALAPPDATA not found".to_string())?;
-    let log_dir = PathBuf::from(app_data).join("ore-no-fusen");
+    let log_dir = crate::paths::log_dir()?;
     create_dir_all(&log_dir).map_err(|e| format!("Failed to create log directory: {}", e))?;
     Ok(log_dir.join("app.log"))
 }
@@ -1,14 +1,34 @@
-use crate::state::{AppState, NoteMeta};
+use crate::state::{AppState, NoteMeta, NoteVersion, NoteWriteError};
+use crate::frontmatter::{self, NoteFrontmatter};
 
 // ロジック層: 副作用なし、純粋関数のみ
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum Effect {
 
     WriteNote { path: String, content: String },
     RenameNote { old_path: String, new_path: String },
+    /// [NEW] ノートや画像の移動（移し先が既にあれば失敗する）
+    MoveFile { from: String, to: String },
+    /// [NEW] ノートや画像のコピー（コピー先が既にあれば失敗する）
+    CopyFile { from: String, to: String },
+    /// [NEW] ノートや画像の削除
+    DeleteFile { path: String },
+    /// 全部成功するか、途中で失敗したら実行済みの分を戻す (transaction::run)
     Batch(Vec<Effect>), 
 }
 
+impl Effect {
+    /// [NEW] ノートの内容を書き換えるか（Batch の中も見る）
+    pub fn writes_note(&self) -> bool {
+        match self {
+            Effect::WriteNote { .. } => true,
+            Effect::RenameNote { .. } | Effect::MoveFile { .. } | Effect::CopyFile { .. } | Effect::DeleteFile { .. } => false,
+            Effect::Batch(effects) => effects.iter().any(Effect::writes_note),
+        }
+    }
+}
+
 pub fn parse_filename(filename: &str) -> (i32, String, String) {
     let parts: Vec<&str> = filename.split('_').collect();
     if parts.len() >= 3 {
@@ -33,77 +53,53 @@ pub fn generate_filename(seq: i32, date: &str, context: &str) -> String {
 }
 
 pub fn split_frontmatter(src: &str) -> (&str, &str) {
-    if !src.starts_with("---") {
-        return ("", src);
-    }
-    // Find second ---
-    if let Some(end) = src[3..].find("---") {
-        let fence_end = 3 + end + 3;
-        let front = &src[..fence_end];
-        let body = &src[fence_end..].trim_start();
-        return (front, body);
+    match frontmatter::split(src) {
+        Some((_, rest)) => {
+            // 閉じフェンスまでを front に含める
+            let front = &src[..src.len() - rest.len()];
+            (front, rest.trim_start())
+        },
+        None => ("", src),
     }
-    ("", src)
 }
 
-pub fn generate_frontmatter(seq: i32, context: &str, created: &str, updated: &str, background_color: Option<&str>, tags: &[String]) -> String {
-    let color_line = if let Some(c) = background_color {
-        format!("\nbackgroundColor: {}", c)
-    } else {
-        "\nbackgroundColor: #f7e9b0".to_string()
-    };
-    
-    let tags_line = if !tags.is_empty() {
-        format!("\ntags: [{}]", tags.join(", "))
-    } else {
-        "".to_string()
-    };
-    
+pub fn generate_frontmatter(seq: i32, id: &str, context: &str, created: &str, updated: &str, background_color: Option<&str>, tags: &[String]) -> String {
     // Δ0.7: Complete frontmatter with all fields including geometry defaults
-    format!(
-        "---\ntype: sticky\nseq: {}\ncontext: {}\ncreated: {}\nupdated: {}{}{}\nx: 100\ny: 100\nwidth: 400\nheight: 300\nfontFamily: BIZ UDGothic\nfontSize: 8\nlineHeight: 1.0\n---\n",
-        seq, context, created, updated, color_line, tags_line
-    )
+    let mut fm = NoteFrontmatter::default();
+    fm.note_type = Some("sticky".to_string());
+    fm.seq = Some(seq);
+    fm.id = Some(id.to_string());
+    fm.context = Some(context.to_string());
+    fm.created = Some(created.to_string());
+    fm.updated = Some(updated.to_string());
+    fm.background_color = Some(background_color.unwrap_or("#f7e9b0").to_string());
+    fm.tags = tags.to_vec();
+    fm.x = Some(100.0);
+    fm.y = Some(100.0);
+    fm.width = Some(400.0);
+    fm.height = Some(300.0);
+    fm.font_family = Some("BIZ UDGothic".to_string());
+    fm.font_size = Some(8.0);
+    fm.line_height = Some(1.0);
+    format!("{}\n", fm.render())
 }
 
-pub fn extract_meta_from_content(content: &str) -> (Option<f64>, Option<f64>, Option<f64>, Option<f64>, Option<String>, Option<bool>, Vec<String>) {
-    // \b (単語境界) を使い、他フィールドの末尾文字にマッチしないよう安全に抽出
-    let re_x = regex::Regex::new(r"\bx:\s*([\d\.]+)").unwrap();
-    let re_y = regex::Regex::new(r"\by:\s*([\d\.]+)").unwrap();
-    let re_w = regex::Regex::new(r"\b(?:width|w):\s*([\d\.]+)").unwrap();
-    let re_h = regex::Regex::new(r"\b(?:height|h):\s*([\d\.]+)").unwrap();
-    let re_color = regex::Regex::new(r#"backgroundColor:\s*["']?([^"'\s]+)["']?"#).unwrap();
-    let re_aot = regex::Regex::new(r"alwaysOnTop:\s*(true|false)").unwrap();
-    let re_tags = regex::Regex::new(r"(?m)^tags:\s*(.*)$").unwrap();
-
-    let x = re_x.captures(content).and_then(|c| c[1].parse().ok());
-    let y = re_y.captures(content).and_then(|c| c[1].parse().ok());
-    let width = re_w.captures(content).and_then(|c| c[1].parse().ok());
-    let height = re_h.captures(content).and_then(|c| c[1].parse().ok());
-    let color = re_color.captures(content).map(|c| c[1].to_string());
-    let always_on_top = re_aot.captures(content).and_then(|c| c[1].parse().ok());
-    
-    let tags_val = re_tags.captures(content).map(|c| c[1].trim().to_string()).unwrap_or_default();
-    let tags = if tags_val.starts_with('[') && tags_val.ends_with(']') {
-        tags_val[1..tags_val.len()-1].split(',')
-            .map(|s| s.trim().trim_matches('"').trim_matches('\'').to_string())
-            .filter(|s| !s.is_empty())
-            .collect()
-    } else if !tags_val.is_empty() {
-        tags_val.split(',')
-            .map(|s| s.trim().trim_matches('"').trim_matches('\'').to_string())
-            .filter(|s| !s.is_empty())
-            .collect()
-    } else {
-        Vec::new()
-    };
-
-    (x, y, width, height, color, always_on_top, tags)
-}
+/// ファイル名 (seq/updated/context) とフロントマターから NoteMeta を組み立てる
+pub fn build_note_meta(path: &str, content: &str) -> NoteMeta {
+    let filename = std::path::Path::new(path).file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let (seq, updated, context) = parse_filename(&filename);
 
-pub fn update_updated_field(frontmatter: &str, new_date: &str) -> String {
-    let re = regex::Regex::new(r"updated: \d{4}-\d{2}-\d{2}").unwrap();
-    re.replace(frontmatter, format!("updated: {}", new_date)).to_string()
+    let mut meta = NoteMeta {
+        path: path.to_string(),
+        seq,
+        context,
+        updated,
+        ..Default::default()
+    };
+    NoteFrontmatter::from_content(content).fill_meta(&mut meta);
+    meta
 }
 
 // --- High-Level Logic Handlers (Returns Effect) ---
@@ -132,42 +128,39 @@ pub fn handle_save_note(
     let old_updated = old_meta.as_ref().map(|m| m.updated.clone()).unwrap_or_else(|| today.clone());
 
     // Extract content fields from NEW frontmatter_raw
-    let (_, _, _, _, new_color, new_aot, new_tags) = extract_meta_from_content(frontmatter_raw);
+    let mut new_fm = NoteFrontmatter::from_content(frontmatter_raw);
 
     // Check if "Content-related" fields changed
     let content_changed = body != old_body 
-        || old_meta.as_ref().map_or(true, |m| m.background_color != new_color 
-            || m.always_on_top != new_aot 
-            || m.tags != new_tags);
+        || old_meta.as_ref().map_or(true, |m| m.background_color != new_fm.background_color 
+            || m.always_on_top != new_fm.always_on_top 
+            || m.tags != new_fm.tags);
 
     // Determine final updated date
     let final_updated = if content_changed { today } else { old_updated };
+    new_fm.updated = Some(final_updated.clone());
 
     // Rule A: If allow_rename is false, skip ALL rename logic
     if !allow_rename {
         println!("[DEBUG logic] allowRename=false. Skipping rename check. Path={}", current_path);
         
-        let final_frontmatter = update_updated_field(frontmatter_raw, &final_updated);
-        let content = format!("{}\n\n{}", final_frontmatter, body);
+        let content = format!("{}\n\n{}", new_fm.render(), body);
 
         // Update State (Metadata)
-        let (x, y, w, h, bg, aot, tags) = extract_meta_from_content(&content);
-        let new_meta = NoteMeta {
+        let mut new_meta = NoteMeta {
             path: current_path.to_string(),
             seq,
             context: old_context, // Use old context
             updated: final_updated,
-            x, y, width: w, height: h,
-            background_color: bg,
-            always_on_top: aot,
-            tags,
+            ..Default::default()
         };
+        new_fm.fill_meta(&mut new_meta);
 
         // WRITE GUARD: If content is IDENTICAL to what logic expects (meaning no changes at all, inclusive of geometry)
         // Note: content as assembled above vs what was theoretically there. 
         // More robust: Compare new_meta with old_meta AND body with old_body.
         let nothing_changed = !content_changed 
-            && old_meta.as_ref().map_or(false, |m| m.x == x && m.y == y && m.width == w && m.height == h);
+            && old_meta.as_ref().map_or(false, |m| same_geometry(m, &new_meta));
 
         let effect = if nothing_changed {
             Effect::Batch(vec![]) // No write
@@ -206,8 +199,7 @@ pub fn handle_save_note(
         current_path.to_string()
     };
     
-    let final_frontmatter = update_updated_field(frontmatter_raw, &final_updated);
-    let content = format!("{}\n\n{}", final_frontmatter, body);
+    let content = format!("{}\n\n{}", new_fm.render(), body);
     
     // Prepare Effect
     let mut effects = Vec::new();
@@ -219,10 +211,17 @@ pub fn handle_save_note(
     }
 
     // Update State
-    let (x, y, w, h, bg, aot, tags) = extract_meta_from_content(&content);
+    let mut new_meta = NoteMeta {
+        path: final_path_str.clone(),
+        seq,
+        context: new_context,
+        updated: final_updated,
+        ..Default::default()
+    };
+    new_fm.fill_meta(&mut new_meta);
 
     let nothing_changed = !should_rename && !content_changed 
-        && old_meta.as_ref().map_or(false, |m| m.x == x && m.y == y && m.width == w && m.height == h);
+        && old_meta.as_ref().map_or(false, |m| same_geometry(m, &new_meta));
 
     if !nothing_changed {
         effects.push(Effect::WriteNote {
@@ -230,22 +229,16 @@ pub fn handle_save_note(
             content: content.clone(),
         });
     }
-    let new_meta = NoteMeta {
-        path: final_path_str.clone(),
-        seq,
-        context: new_context,
-        updated: final_updated,
-        x, y, width: w, height: h,
-        background_color: bg,
-        always_on_top: aot,
-        tags,
-    };
     
     apply_update_note(state, current_path, new_meta);
     
     Ok((final_path_str, Effect::Batch(effects)))
 }
 
+fn same_geometry(a: &NoteMeta, b: &NoteMeta) -> bool {
+    a.x == b.x && a.y == b.y && a.width == b.width && a.height == b.height
+}
+
 // --- Builders (Deprecated/Legacy for other commands if needed, but updated for fields) ---
 
 pub struct CreateNoteData {
@@ -258,12 +251,12 @@ pub struct CreateNoteData {
     pub meta: NoteMeta,
 }
 
-pub fn build_create_note_data(folder_path: &str, context: &str, next_seq: i32, today: &str) -> CreateNoteData {
+pub fn build_create_note_data(folder_path: &str, context: &str, next_seq: i32, id: &str, today: &str) -> CreateNoteData {
     let filename = generate_filename(next_seq, today, context);
     let path = std::path::Path::new(folder_path).join(&filename);
     let path_str = path.to_string_lossy().to_string();
     
-    let frontmatter = generate_frontmatter(next_seq, context, today, today, Some("#f7e9b0"), &[]);
+    let frontmatter = generate_frontmatter(next_seq, id, context, today, today, Some("#f7e9b0"), &[]);
     let body = "ここにコンテキストを書く！".to_string();
     let content = format!("{}\n\n{}", frontmatter, body);
     
@@ -309,44 +302,57 @@ pub fn apply_update_note(state: &mut AppState, old_path: &str, note: NoteMeta) {
 
 pub fn apply_remove_note(state: &mut AppState, path: &str) {
     state.notes.retain(|n| n.path != path);
+    state.known_versions.remove(path);
 }
 
-// Geometry Update Logic
+// --- Vault監視 (Watcher) ---
 
-pub fn update_frontmatter_value(content: &str, key: &str, value: String) -> String {
-    // Determine Frontmatter area
-    if !content.trim_start().starts_with("---") {
-        return format!("---\n{}: {}\n---\n\n{}", key, value, content);
-    }
-    
-    // Find closing ---
-    // skip first ---
-    let start_idx = content.find("---").unwrap() + 3;
-    let end_idx = match content[start_idx..].find("---") {
-        Some(i) => start_idx + i,
-        None => return format!("---\n{}: {}\n---\n\n{}", key, value, content),
-    };
-    
-    let frontmatter = &content[..end_idx];
-    let body = &content[end_idx..]; // Keep the closing fence in 'body' for easy replacement
-    
-    let re = regex::Regex::new(&format!(r"(?m)^{}:\s*.*$", regex::escape(key))).unwrap();
-    if re.is_match(frontmatter) {
-        let new_fm = re.replace(frontmatter, format!("{}: {}", key, value)).to_string();
-        format!("{}{}", new_fm, body)
-    } else {
-        // Insert before closing ---
-        // Ensure the last line of frontmatter has a newline
-        let mut new_fm = frontmatter.to_string();
-        if !new_fm.ends_with('\n') {
-            new_fm.push('\n');
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum VaultChange {
+    Created,
+    Modified,
+    Removed,
+}
+
+/// ファイル監視で拾ったパスを AppState と突き合わせて分類する。
+/// `on_disk` は現在のディスク上のバージョン（ファイルが無ければ None）。
+/// 自分の書き込み（記録済みバージョンと内容が同じ）は None を返して無視させる。
+pub fn classify_vault_change(state: &AppState, path: &str, on_disk: Option<&NoteVersion>) -> Option<VaultChange> {
+    let listed = state.notes.iter().any(|n| n.path == path);
+    match (on_disk, listed) {
+        (Some(_), false) => Some(VaultChange::Created),
+        (Some(version), true) => {
+            let is_own_write = state
+                .known_versions
+                .get(path)
+                .is_some_and(|known| known.hash == version.hash);
+            if is_own_write { None } else { Some(VaultChange::Modified) }
         }
-        new_fm.push_str(&format!("{}: {}\n", key, value));
-        format!("{}{}", new_fm, body)
+        (None, true) => Some(VaultChange::Removed),
+        (None, false) => None,
     }
 }
 
+// --- 競合検出 (Version Token) ---
 
+/// 読み込み・書き込み後のバージョンを記録する
+pub fn apply_known_version(state: &mut AppState, path: &str, version: NoteVersion) {
+    state.known_versions.insert(path.to_string(), version);
+}
+
+/// 書き込み前のチェック。
+/// 期待するバージョンが分からない場合（一度も読んでいないノート）は通す。
+/// mtime だけが変わって内容が同じ場合（同期ツールの touch 等）は競合とみなさない。
+pub fn check_version(path: &str, expected: Option<&NoteVersion>, actual: &NoteVersion) -> Result<(), NoteWriteError> {
+    match expected {
+        Some(expected) if expected.hash != actual.hash => Err(NoteWriteError::Conflict {
+            path: path.to_string(),
+            expected: expected.clone(),
+            actual: actual.clone(),
+        }),
+        _ => Ok(()),
+    }
+}
 
 pub fn handle_add_tag(
     state: &mut AppState,
@@ -354,17 +360,17 @@ pub fn handle_add_tag(
     current_content: &str,
     tag: &str
 ) -> Result<Effect, String> {
-    let (_, _, _, _, _, _, mut tags) = extract_meta_from_content(current_content);
-    if !tags.contains(&tag.to_string()) {
-        tags.push(tag.to_string());
-        tags.sort();
+    let mut fm = NoteFrontmatter::from_content(current_content);
+    if !fm.tags.contains(&tag.to_string()) {
+        fm.tags.push(tag.to_string());
+        fm.tags.sort();
     }
     
-    let new_content = update_frontmatter_value(current_content, "tags", format!("[{}]", tags.join(", ")));
+    let new_content = fm.apply_to_content(current_content);
     
     // State update
     if let Some(index) = state.notes.iter().position(|n| n.path == path) {
-        state.notes[index].tags = tags;
+        state.notes[index].tags = fm.tags;
     }
     
     Ok(Effect::WriteNote { path: path.to_string(), content: new_content })
@@ -376,14 +382,14 @@ pub fn handle_remove_tag(
     current_content: &str,
     tag: &str
 ) -> Result<Effect, String> {
-    let (_, _, _, _, _, _, mut tags) = extract_meta_from_content(current_content);
-    tags.retain(|t| t != tag);
+    let mut fm = NoteFrontmatter::from_content(current_content);
+    fm.tags.retain(|t| t != tag);
     
-    let new_content = update_frontmatter_value(current_content, "tags", format!("[{}]", tags.join(", ")));
+    let new_content = fm.apply_to_content(current_content);
     
     // State update
     if let Some(index) = state.notes.iter().position(|n| n.path == path) {
-        state.notes[index].tags = tags;
+        state.notes[index].tags = fm.tags;
     }
     
     Ok(Effect::WriteNote { path: path.to_string(), content: new_content })
@@ -407,11 +413,12 @@ pub fn handle_update_geometry(
     current_content: &str,
     x: f64, y: f64, w: f64, h: f64
 ) -> Result<Effect, String> {
-    let mut new_content = current_content.to_string();
-    new_content = update_frontmatter_value(&new_content, "x", x.round().to_string());
-    new_content = update_frontmatter_value(&new_content, "y", y.round().to_string());
-    new_content = update_frontmatter_value(&new_content, "width", w.round().to_string());
-    new_content = update_frontmatter_value(&new_content, "height", h.round().to_string());
+    let mut fm = NoteFrontmatter::from_content(current_content);
+    fm.x = Some(x.round());
+    fm.y = Some(y.round());
+    fm.width = Some(w.round());
+    fm.height = Some(h.round());
+    let new_content = fm.apply_to_content(current_content);
     
     // State update (Single Source of Truth)
     if let Some(index) = state.notes.iter().position(|n| n.path == path) {
@@ -430,6 +437,54 @@ pub fn handle_update_geometry(
 mod tests {
     use super::*;
 
+    fn version(mtime_ms: i64, hash: &str) -> NoteVersion {
+        NoteVersion { mtime_ms, hash: hash.to_string() }
+    }
+
+    #[test]
+    fn classify_vault_change_detects_external_changes() {
+        let mut state = AppState::default();
+        state.notes.push(NoteMeta { path: "a.md".to_string(), ..Default::default() });
+        apply_known_version(&mut state, "a.md", version(1000, "abc"));
+
+        assert_eq!(classify_vault_change(&state, "b.md", Some(&version(1, "x"))), Some(VaultChange::Created));
+        assert_eq!(classify_vault_change(&state, "a.md", Some(&version(2000, "def"))), Some(VaultChange::Modified));
+        assert_eq!(classify_vault_change(&state, "a.md", None), Some(VaultChange::Removed));
+        assert_eq!(classify_vault_change(&state, "b.md", None), None);
+    }
+
+    #[test]
+    fn classify_vault_change_ignores_own_writes() {
+        let mut state = AppState::default();
+        state.notes.push(NoteMeta { path: "a.md".to_string(), ..Default::default() });
+        apply_known_version(&mut state, "a.md", version(1000, "abc"));
+
+        assert_eq!(classify_vault_change(&state, "a.md", Some(&version(1500, "abc"))), None);
+    }
+
+    #[test]
+    fn check_version_passes_when_unchanged_or_unknown() {
+        let v = version(1000, "abc");
+        assert!(check_version("a.md", Some(&v), &v).is_ok());
+        // touch されただけ (内容は同じ)
+        assert!(check_version("a.md", Some(&v), &version(2000, "abc")).is_ok());
+        // 一度も読んでいない
+        assert!(check_version("a.md", None, &v).is_ok());
+    }
+
+    #[test]
+    fn check_version_reports_both_versions_on_conflict() {
+        let err = check_version("a.md", Some(&version(1000, "abc")), &version(2000, "def")).unwrap_err();
+        match err {
+            NoteWriteError::Conflict { path, expected, actual } => {
+                assert_eq!(path, "a.md");
+                assert_eq!(expected.hash, "abc");
+                assert_eq!(actual.hash, "def");
+            }
+            other => panic!("expected conflict, got {:?}", other),
+        }
+    }
+
     #[test]
     fn sanitize_basic() {
         let out = sanitize_context("foo/bar");
@@ -515,13 +570,13 @@ mod tests {
     #[test]
     fn extract_meta_with_tags() {
         let content = "---\ntags: [work, personal,  hoge]\n---";
-        let (_, _, _, _, _, _, tags) = extract_meta_from_content(content);
-        assert_eq!(tags, vec!["work", "personal", "hoge"]);
+        let fm = NoteFrontmatter::from_content(content);
+        assert_eq!(fm.tags, vec!["work", "personal", "hoge"]);
     }
 
     #[test]
     fn generate_frontmatter_with_tags() {
-        let fm = generate_frontmatter(1, "ctx", "2024-01-01", "2024-01-01", None, &vec!["tag1".to_string(), "tag2".to_string()]);
+        let fm = generate_frontmatter(1, "n1", "ctx", "2024-01-01", "2024-01-01", None, &vec!["tag1".to_string(), "tag2".to_string()]);
         assert!(fm.contains("tags: [tag1, tag2]"));
     }
 
@@ -633,7 +688,7 @@ mod tests {
     #[test]
     fn generate_frontmatter_with_default_color() {
         // デフォルトカラー（指定なし）の場合
-        let frontmatter = generate_frontmatter(1, "テストメモ", "2026-01-12", "2026-01-12", None, &[]);
+        let frontmatter = generate_frontmatter(1, "n1", "テストメモ", "2026-01-12", "2026-01-12", None, &[]);
         
         // 必須フィールドが含まれていることを確認
         assert!(frontmatter.contains("type: sticky"));
@@ -655,7 +710,7 @@ mod tests {
     #[test]
     fn generate_frontmatter_with_custom_color() {
         // カスタムカラーを指定
-        let frontmatter = generate_frontmatter(42, "青いメモ", "2026-01-12", "2026-01-12", Some("#80d8ff"), &[]);
+        let frontmatter = generate_frontmatter(42, "n42", "青いメモ", "2026-01-12", "2026-01-12", Some("#80d8ff"), &[]);
         
         assert!(frontmatter.contains("backgroundColor: #80d8ff"));
         assert!(frontmatter.contains("seq: 42"));
@@ -664,14 +719,14 @@ mod tests {
     #[test]
     fn generate_frontmatter_format() {
         // フロントマターが正しいYAML形式であることを確認
-        let frontmatter = generate_frontmatter(1, "test", "2026-01-12", "2026-01-12", None, &[]);
+        let frontmatter = generate_frontmatter(1, "n1", "test", "2026-01-12", "2026-01-12", None, &[]);
         
         // ---で開始・終了することを確認
         assert!(frontmatter.starts_with("---\n"));
         assert!(frontmatter.contains("---\n") && frontmatter.matches("---").count() == 2);
     }
 
-    // === extract_meta_from_content のテスト ===
+    // === NoteFrontmatter::from_content のテスト ===
     // コンテンツからメタデータを抽出する関数
     
     #[test]
@@ -691,17 +746,17 @@ alwaysOnTop: true
 メモの本文
 "#;
         
-        let (x, y, width, height, color, aot, tags) = extract_meta_from_content(content);
+        let fm = NoteFrontmatter::from_content(content);
         
-        assert_eq!(x, Some(150.0));
-        assert_eq!(y, Some(200.0));
-        assert_eq!(width, Some(500.0));
+        assert_eq!(fm.x, Some(150.0));
+        assert_eq!(fm.y, Some(200.0));
+        assert_eq!(fm.width, Some(500.0));
         
         // 修正済み: height は正しく 400.0 を取得する
-        assert_eq!(height, Some(400.0));
+        assert_eq!(fm.height, Some(400.0));
         
-        assert_eq!(color, Some("#ffcdd2".to_string()));
-        assert_eq!(aot, Some(true));
+        assert_eq!(fm.background_color, Some("#ffcdd2".to_string()));
+        assert_eq!(fm.always_on_top, Some(true));
     }
 
     #[test]
@@ -712,14 +767,14 @@ x: 100
 backgroundColor: #f7e9b0
 ---"#;
         
-        let (x, y, width, height, color, aot, tags) = extract_meta_from_content(content);
+        let fm = NoteFrontmatter::from_content(content);
         
-        assert_eq!(x, Some(100.0));
-        assert_eq!(y, None);  // 存在しない
-        assert_eq!(width, None);
-        assert_eq!(height, None);
-        assert_eq!(color, Some("#f7e9b0".to_string()));
-        assert_eq!(aot, None);
+        assert_eq!(fm.x, Some(100.0));
+        assert_eq!(fm.y, None);  // 存在しない
+        assert_eq!(fm.width, None);
+        assert_eq!(fm.height, None);
+        assert_eq!(fm.background_color, Some("#f7e9b0".to_string()));
+        assert_eq!(fm.always_on_top, None);
     }
 
     #[test]
@@ -727,46 +782,53 @@ backgroundColor: #f7e9b0
         // フロントマターが存在しない場合
         let content = "ただのテキスト";
         
-        let (x, y, width, height, color, aot, tags) = extract_meta_from_content(content);
+        let fm = NoteFrontmatter::from_content(content);
         
         // 全てNone
-        assert_eq!(x, None);
-        assert_eq!(y, None);
-        assert_eq!(width, None);
-        assert_eq!(height, None);
-        assert_eq!(color, None);
-        assert_eq!(aot, None);
+        assert_eq!(fm.x, None);
+        assert_eq!(fm.y, None);
+        assert_eq!(fm.width, None);
+        assert_eq!(fm.height, None);
+        assert_eq!(fm.background_color, None);
+        assert_eq!(fm.always_on_top, None);
     }
 
     #[test]
     fn extract_meta_float_values() {
         // 小数点を含む座標
-        let content = "x: 123.45\ny: 678.9";
+        let content = "---\nx: 123.45\ny: 678.9\n---";
         
-        let (x, y, _, _, _, _, _) = extract_meta_from_content(content);
+        let fm = NoteFrontmatter::from_content(content);
         
-        assert_eq!(x, Some(123.45));
-        assert_eq!(y, Some(678.9));
+        assert_eq!(fm.x, Some(123.45));
+        assert_eq!(fm.y, Some(678.9));
     }
 
-    // === update_updated_field のテスト ===
+    // === NoteFrontmatter での updated の更新 ===
     // フロントマター内の "updated" フィールドを更新する
-    
+
+    // 型付きのフィールドを変えて書き戻す。未知のキーや本文はそのまま残る
+    fn with(content: &str, update: impl FnOnce(&mut NoteFrontmatter)) -> String {
+        let mut fm = NoteFrontmatter::from_content(content);
+        update(&mut fm);
+        fm.apply_to_content(content)
+    }
+
     #[test]
-    fn update_updated_field_replaces_date() {
+    fn frontmatter_updated_replaces_date() {
         // 既存の日付を新しい日付に置き換える
         let frontmatter = "---\ncreated: 2026-01-10\nupdated: 2026-01-10\n---\n";
-        let result = update_updated_field(frontmatter, "2026-01-12");
+        let result = with(frontmatter, |fm| fm.updated = Some("2026-01-12".to_string()));
         
         assert!(result.contains("updated: 2026-01-12"));
         assert!(!result.contains("updated: 2026-01-10"));
     }
 
     #[test]
-    fn update_updated_field_preserves_other_fields() {
+    fn frontmatter_updated_preserves_other_fields() {
         // 他のフィールドは変更されないことを確認
         let frontmatter = "---\nseq: 1\ncreated: 2026-01-10\nupdated: 2026-01-10\ncontext: test\n---\n";
-        let result = update_updated_field(frontmatter, "2026-01-12");
+        let result = with(frontmatter, |fm| fm.updated = Some("2026-01-12".to_string()));
         
         assert!(result.contains("seq: 1"));
         assert!(result.contains("created: 2026-01-10")); // createdは変わらない
@@ -774,14 +836,13 @@ backgroundColor: #f7e9b0
         assert!(result.contains("updated: 2026-01-12")); // updatedだけ変わる
     }
 
-    // === update_frontmatter_value のテスト ===
-    // フロントマターの任意のフィールドを更新する汎用関数
-    
+    // === NoteFrontmatter での任意フィールドの更新 ===
+
     #[test]
-    fn update_frontmatter_value_updates_existing_field() {
+    fn frontmatter_updates_existing_field() {
         // 既存フィールドの値を更新
         let content = "---\nx: 100\ny: 200\n---\n\n本文";
-        let result = update_frontmatter_value(content, "x", "150".to_string());
+        let result = with(content, |fm| fm.x = Some(150.0));
         
         assert!(result.contains("x: 150"));
         assert!(!result.contains("x: 100"));
@@ -789,21 +850,22 @@ backgroundColor: #f7e9b0
     }
 
     #[test]
-    fn update_frontmatter_value_adds_new_field() {
+    fn frontmatter_adds_new_field() {
         // 存在しないフィールドを追加
-        let content = "---\nx: 100\n---\n\n本文";
-        let result = update_frontmatter_value(content, "y", "200".to_string());
+        let content = "---\nx: 100\ncustomKey: keep me\n---\n\n本文";
+        let result = with(content, |fm| fm.y = Some(200.0));
         
         assert!(result.contains("x: 100")); // 既存フィールドは保持
+        assert!(result.contains("customKey: keep me")); // 未知のキーも保持
         assert!(result.contains("y: 200")); // 新しいフィールドが追加
         assert!(result.contains("本文"));
     }
 
     #[test]
-    fn update_frontmatter_value_creates_frontmatter_if_missing() {
+    fn frontmatter_created_if_missing() {
         // フロントマターが存在しない場合、新規作成
         let content = "ただの本文";
-        let result = update_frontmatter_value(content, "x", "100".to_string());
+        let result = with(content, |fm| fm.x = Some(100.0));
         
         assert!(result.contains("---"));
         assert!(result.contains("x: 100"));
@@ -811,29 +873,30 @@ backgroundColor: #f7e9b0
     }
 
     #[test]
-    fn update_frontmatter_value_handles_multiline_body() {
+    fn frontmatter_update_keeps_multiline_body() {
         // 複数行の本文が正しく保持されるか
         let content = "---\nseq: 1\n---\n\n行1\n行2\n行3";
-        let result = update_frontmatter_value(content, "x", "100".to_string());
+        let result = with(content, |fm| fm.x = Some(100.0));
         
         assert!(result.contains("x: 100"));
         assert!(result.contains("行1\n行2\n行3"));
     }
 
     #[test]
-    fn update_frontmatter_value_with_special_chars_in_key() {
-        // キーに特殊文字が含まれる場合（regex::escape でエスケープされる）
+    fn frontmatter_writes_camel_case_keys() {
+        // キャメルケースのキーで書き出される
         let content = "---\ntest: 1\n---\n\n本文";
-        let result = update_frontmatter_value(content, "backgroundColor", "#f7e9b0".to_string());
+        let result = with(content, |fm| fm.background_color = Some("#f7e9b0".to_string()));
         
         assert!(result.contains("backgroundColor: #f7e9b0"));
+        assert!(result.contains("test: 1"));
     }
 
     // === build_create_note_data のテスト ===
     
     #[test]
     fn test_build_create_note_data() {
-        let data = build_create_note_data("/test/folder", "テストメモ", 42, "2026-01-12");
+        let data = build_create_note_data("/test/folder", "テストメモ", 42, "n42", "2026-01-12");
         
         // ファイル名が正しく生成される
         assert_eq!(data.filename, "0042_2026-01-12_テストメモ.md");
@@ -849,6 +912,7 @@ backgroundColor: #f7e9b0
         // コンテンツにフロントマターと本文が含まれる
         assert!(data.content.contains("---"));
         assert!(data.content.contains("seq: 42"));
+        assert!(data.content.contains("id: n42"));
         assert!(data.content.contains("ここにコンテキストを書く！"));
     }
 
@@ -1028,17 +1092,17 @@ tags: [OreNoFusen, 開発プロセス]
 
 ロードマップ"#;
         
-        let (x, y, width, height, color, _, tags) = extract_meta_from_content(content);
+        let fm = NoteFrontmatter::from_content(content);
         
         // ⚠️ 最重要: height が width の値で上書きされていないこと
-        assert_eq!(width, Some(413.0), "width は 413.0 であるべき");
-        assert_eq!(height, Some(241.0), "height は 241.0 であるべき (width の値ではない!)");
+        assert_eq!(fm.width, Some(413.0), "width は 413.0 であるべき");
+        assert_eq!(fm.height, Some(241.0), "height は 241.0 であるべき (width の値ではない!)");
         
         // 他のフィールドも正しく読めている
-        assert_eq!(x, Some(1425.0));
-        assert_eq!(y, Some(551.0));
-        assert_eq!(color, Some("#ffcdd2".to_string()));
-        assert!(tags.contains(&"OreNoFusen".to_string()));
+        assert_eq!(fm.x, Some(1425.0));
+        assert_eq!(fm.y, Some(551.0));
+        assert_eq!(fm.background_color, Some("#ffcdd2".to_string()));
+        assert!(fm.tags.contains(&"OreNoFusen".to_string()));
     }
 
     /// No.1バグ回帰テスト (追加): width/height の順序が逆でも正しく動作
@@ -1047,10 +1111,10 @@ tags: [OreNoFusen, 開発プロセス]
         // height が width より前に来るケース
         let content = "---\nheight: 300\nwidth: 400\n---";
         
-        let (_, _, width, height, _, _, _) = extract_meta_from_content(content);
+        let fm = NoteFrontmatter::from_content(content);
         
-        assert_eq!(width, Some(400.0));
-        assert_eq!(height, Some(300.0));
+        assert_eq!(fm.width, Some(400.0));
+        assert_eq!(fm.height, Some(300.0));
     }
 
     /// No.1バグ回帰テスト (追加): 短縮形 w: と h: も正しく動作
@@ -1058,10 +1122,30 @@ tags: [OreNoFusen, 開発プロセス]
     fn regression_no1_short_form_w_and_h() {
         let content = "---\nw: 500\nh: 250\n---";
         
-        let (_, _, width, height, _, _, _) = extract_meta_from_content(content);
+        let fm = NoteFrontmatter::from_content(content);
         
-        assert_eq!(width, Some(500.0));
-        assert_eq!(height, Some(250.0));
+        assert_eq!(fm.width, Some(500.0));
+        assert_eq!(fm.height, Some(250.0));
+    }
+
+    #[test]
+    fn handle_save_note_keeps_unknown_frontmatter_keys() {
+        let mut state = AppState::default();
+        let path = "/vault/0001_2026-01-10_メモ.md";
+        apply_add_note(&mut state, NoteMeta { path: path.to_string(), seq: 1, ..Default::default() });
+
+        let frontmatter_raw = "---\nseq: 1\nupdated: 2026-01-10\nx: -200\nfontSize: 12\nplugin:\n  pinned: true\n---";
+        let (_, effect) = handle_save_note(&mut state, path, "メモ\n新しい本文", "メモ", frontmatter_raw, false).unwrap();
+
+        match effect {
+            Effect::WriteNote { content, .. } => {
+                assert!(content.contains("plugin:\n  pinned: true"));
+                assert!(!content.contains("updated: 2026-01-10"));
+            },
+            _ => panic!("Expected WriteNote effect"),
+        }
+        assert_eq!(state.notes[0].x, Some(-200.0));
+        assert_eq!(state.notes[0].font_size, Some(12.0));
     }
 
     #[test]
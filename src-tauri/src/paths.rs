@@ -0,0 +1,114 @@
+// [NEW] プラットフォームごとの保存先
+// Windows: %APPDATA% / %LOCALAPPDATA% / Documents
+// macOS:   ~/Library/Application Support / ~/Documents
+// Linux:   XDG (~/.config, ~/.local/state, XDG_DOCUMENTS_DIR)
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use directories::{ProjectDirs, UserDirs};
+
+const APP_NAME: &str = "OreNoFusen";
+
+fn project_dirs() -> Result<ProjectDirs, String> {
+    ProjectDirs::from("", "", APP_NAME).ok_or_else(|| "home directory not found".to_string())
+}
+
+/// 設定ファイル (settings.json) の置き場所
+pub fn config_dir() -> Result<PathBuf, String> {
+    Ok(project_dirs()?.config_dir().to_path_buf())
+}
+
+/// ログファイルの置き場所（Linux は XDG_STATE_HOME）
+pub fn log_dir() -> Result<PathBuf, String> {
+    let dirs = project_dirs()?;
+    let dir = dirs.state_dir().unwrap_or(dirs.data_local_dir());
+    Ok(dir.join("logs"))
+}
+
+/// 初回セットアップの推奨 Vault: Documents/OreNoFusen
+pub fn default_vault_dir() -> Result<PathBuf, String> {
+    let user_dirs = UserDirs::new().ok_or_else(|| "home directory not found".to_string())?;
+    let docs = user_dirs
+        .document_dir()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| user_dirs.home_dir().join("Documents"));
+    Ok(docs.join(APP_NAME))
+}
+
+// --- 旧バージョンの保存先からの移行 ---
+// 以前は環境変数 (APPDATA / LOCALAPPDATA) を直接参照していた。
+
+fn legacy_config_file() -> Option<PathBuf> {
+    std::env::var_os("APPDATA").map(|d| PathBuf::from(d).join(APP_NAME).join("settings.json"))
+}
+
+fn legacy_log_file() -> Option<PathBuf> {
+    std::env::var_os("LOCALAPPDATA").map(|d| PathBuf::from(d).join("ore-no-fusen").join("app.log"))
+}
+
+/// 旧保存先のファイルを新しい場所へ移す。起動直後（ログ出力前）に一度だけ呼ぶ。
+/// 戻り値は移行したファイルの説明（ログ出力用）。
+pub fn migrate_legacy_files() -> Vec<String> {
+    let mut moved = Vec::new();
+    let targets = [
+        (legacy_config_file(), config_dir().map(|d| d.join("settings.json"))),
+        (legacy_log_file(), log_dir().map(|d| d.join("app.log"))),
+    ];
+    for (old, new) in targets {
+        let (Some(old), Ok(new)) = (old, new) else { continue };
+        match migrate_file(&old, &new) {
+            Ok(true) => moved.push(format!("{:?} -> {:?}", old, new)),
+            Ok(false) => {}
+            Err(e) => eprintln!("[PATHS] Failed to migrate {:?}: {}", old, e),
+        }
+    }
+    moved
+}
+
+/// 新しい場所にまだファイルが無い場合だけ移動する。移動したら true。
+fn migrate_file(old: &Path, new: &Path) -> Result<bool, String> {
+    if old == new || !old.is_file() || new.exists() {
+        return Ok(false);
+    }
+    if let Some(parent) = new.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    // ドライブをまたぐ場合は rename できないのでコピーしてから消す
+    if fs::rename(old, new).is_err() {
+        fs::copy(old, new).map_err(|e| e.to_string())?;
+        let _ = fs::remove_file(old);
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrate_file_moves_when_target_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("old").join("settings.json");
+        let new = dir.path().join("new").join("settings.json");
+        fs::create_dir_all(old.parent().unwrap()).unwrap();
+        fs::write(&old, "{}").unwrap();
+
+        assert!(migrate_file(&old, &new).unwrap());
+        assert!(!old.exists());
+        assert_eq!(fs::read_to_string(&new).unwrap(), "{}");
+    }
+
+    #[test]
+    fn migrate_file_keeps_existing_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("old.json");
+        let new = dir.path().join("new.json");
+        fs::write(&old, "old").unwrap();
+        fs::write(&new, "new").unwrap();
+
+        assert!(!migrate_file(&old, &new).unwrap());
+        assert!(old.exists());
+        assert_eq!(fs::read_to_string(&new).unwrap(), "new");
+    }
+}
@@ -0,0 +1,420 @@
+// [NEW] 検索クエリ言語のパーサ
+//
+//   query   := or
+//   or      := and ( "OR" and )*
+//   and     := unary ( ["AND"] unary )*      … 並べるだけでも AND
+//   unary   := ("NOT" | "-") unary | primary
+//   primary := "(" query ")" | "\"phrase\"" | /regex/ | key:value | word
+//
+// key: tag / color / seq / updated / in
+// seq と updated は比較演算子を付けられる (seq:>=10, updated:>2026-01-01)
+
+use regex::Regex;
+
+#[derive(serde::Serialize, Debug, Clone, PartialEq)]
+pub struct QueryError {
+    /// エラー位置（クエリ文字列の先頭からの文字数）
+    pub position: usize,
+    pub message: String,
+}
+
+impl QueryError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        Self { position, message: message.into() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cmp {
+    Eq,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl Cmp {
+    pub fn test<T: PartialOrd>(self, actual: T, expected: T) -> bool {
+        match self {
+            Cmp::Eq => actual == expected,
+            Cmp::Gt => actual > expected,
+            Cmp::Ge => actual >= expected,
+            Cmp::Lt => actual < expected,
+            Cmp::Le => actual <= expected,
+        }
+    }
+}
+
+/// 検索範囲 (in:)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scope {
+    /// Vault 直下
+    Notes,
+    /// Archive/ と tags/ 以下
+    Archive,
+    Trash,
+    All,
+}
+
+#[derive(Debug, Clone)]
+pub enum Query {
+    /// 単語または "フレーズ"（行単位の部分一致、大文字小文字は区別しない）
+    Text(String),
+    Regex(Regex),
+    Tag(String),
+    Color(String),
+    Seq(Cmp, i32),
+    /// YYYY-MM-DD
+    Updated(Cmp, String),
+    In(Scope),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+    Not(Box<Query>),
+}
+
+pub fn parse(input: &str) -> Result<Query, QueryError> {
+    let tokens = lex(input)?;
+    if tokens.is_empty() {
+        return Err(QueryError::new(0, "empty query"));
+    }
+    let mut parser = Parser { tokens, pos: 0, end: input.chars().count() };
+    let query = parser.parse_or()?;
+    if let Some(tok) = parser.peek() {
+        return Err(QueryError::new(tok.start, "unexpected ')'"));
+    }
+    Ok(query)
+}
+
+// --- 字句解析 ---
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Phrase(String),
+    Regex(String),
+    /// key:value（value がクオートされていれば展開済み）
+    Field(String, String),
+    Word(String),
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    tok: Tok,
+    /// 文字位置
+    start: usize,
+}
+
+fn lex(input: &str) -> Result<Vec<Token>, QueryError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        match c {
+            '(' => {
+                tokens.push(Token { tok: Tok::LParen, start });
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token { tok: Tok::RParen, start });
+                i += 1;
+            }
+            '"' => {
+                let (text, next) = read_quoted(&chars, i)?;
+                tokens.push(Token { tok: Tok::Phrase(text), start });
+                i = next;
+            }
+            '/' => {
+                let (pattern, next) = read_regex(&chars, i)?;
+                tokens.push(Token { tok: Tok::Regex(pattern), start });
+                i = next;
+            }
+            '-' if chars.get(i + 1).is_some_and(|n| !n.is_whitespace()) => {
+                tokens.push(Token { tok: Tok::Not, start });
+                i += 1;
+            }
+            _ => {
+                let mut word = String::new();
+                while i < chars.len() && !chars[i].is_whitespace() && chars[i] != '(' && chars[i] != ')' {
+                    // key:"quoted value"
+                    if chars[i] == '"' && word.ends_with(':') {
+                        let (text, next) = read_quoted(&chars, i)?;
+                        let key = word.trim_end_matches(':').to_lowercase();
+                        tokens.push(Token { tok: Tok::Field(key, text), start });
+                        word.clear();
+                        i = next;
+                        break;
+                    }
+                    word.push(chars[i]);
+                    i += 1;
+                }
+                if word.is_empty() {
+                    continue;
+                }
+                let tok = match word.as_str() {
+                    "AND" => Tok::And,
+                    "OR" => Tok::Or,
+                    "NOT" => Tok::Not,
+                    _ => match split_field(&word) {
+                        Some((key, value)) => Tok::Field(key, value),
+                        None => Tok::Word(word),
+                    },
+                };
+                tokens.push(Token { tok, start });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+// key:value の形か（key は英字のみ。"10:30" などはただの単語）
+fn split_field(word: &str) -> Option<(String, String)> {
+    let (key, value) = word.split_once(':')?;
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    Some((key.to_lowercase(), value.to_string()))
+}
+
+fn read_quoted(chars: &[char], open: usize) -> Result<(String, usize), QueryError> {
+    let mut text = String::new();
+    let mut i = open + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if i + 1 < chars.len() => {
+                text.push(chars[i + 1]);
+                i += 2;
+            }
+            '"' => return Ok((text, i + 1)),
+            c => {
+                text.push(c);
+                i += 1;
+            }
+        }
+    }
+    Err(QueryError::new(open, "unterminated quoted phrase"))
+}
+
+fn read_regex(chars: &[char], open: usize) -> Result<(String, usize), QueryError> {
+    let mut pattern = String::new();
+    let mut i = open + 1;
+    while i < chars.len() {
+        match chars[i] {
+            // \/ はスラッシュそのもの。それ以外のエスケープは正規表現に渡す
+            '\\' if chars.get(i + 1) == Some(&'/') => {
+                pattern.push('/');
+                i += 2;
+            }
+            '\\' if i + 1 < chars.len() => {
+                pattern.push('\\');
+                pattern.push(chars[i + 1]);
+                i += 2;
+            }
+            '/' => return Ok((pattern, i + 1)),
+            c => {
+                pattern.push(c);
+                i += 1;
+            }
+        }
+    }
+    Err(QueryError::new(open, "unterminated regex"))
+}
+
+// --- 構文解析 ---
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// 入力の文字数（末尾で式が足りない場合のエラー位置）
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let tok = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        tok
+    }
+
+    fn parse_or(&mut self) -> Result<Query, QueryError> {
+        let mut items = vec![self.parse_and()?];
+        while matches!(self.peek(), Some(Token { tok: Tok::Or, .. })) {
+            self.next();
+            items.push(self.parse_and()?);
+        }
+        Ok(if items.len() == 1 { items.pop().unwrap() } else { Query::Or(items) })
+    }
+
+    fn parse_and(&mut self) -> Result<Query, QueryError> {
+        let mut items = vec![self.parse_unary()?];
+        loop {
+            match self.peek().map(|t| &t.tok) {
+                Some(Tok::And) => {
+                    self.next();
+                    items.push(self.parse_unary()?);
+                }
+                Some(Tok::Or) | Some(Tok::RParen) | None => break,
+                Some(_) => items.push(self.parse_unary()?),
+            }
+        }
+        Ok(if items.len() == 1 { items.pop().unwrap() } else { Query::And(items) })
+    }
+
+    fn parse_unary(&mut self) -> Result<Query, QueryError> {
+        if matches!(self.peek(), Some(Token { tok: Tok::Not, .. })) {
+            self.next();
+            return Ok(Query::Not(Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Query, QueryError> {
+        let Some(token) = self.next() else {
+            return Err(QueryError::new(self.end, "expected a search term"));
+        };
+        match token.tok {
+            Tok::LParen => {
+                let inner = self.parse_or()?;
+                match self.next() {
+                    Some(Token { tok: Tok::RParen, .. }) => Ok(inner),
+                    _ => Err(QueryError::new(token.start, "unclosed '('")),
+                }
+            }
+            Tok::RParen => Err(QueryError::new(token.start, "unexpected ')'")),
+            Tok::And | Tok::Or => Err(QueryError::new(token.start, "expected a search term before operator")),
+            Tok::Not => unreachable!("handled in parse_unary"),
+            Tok::Phrase(text) => {
+                if text.trim().is_empty() {
+                    return Err(QueryError::new(token.start, "empty phrase"));
+                }
+                Ok(Query::Text(text))
+            }
+            Tok::Regex(pattern) => Regex::new(&pattern)
+                .map(Query::Regex)
+                .map_err(|e| QueryError::new(token.start, format!("invalid regex: {}", e))),
+            Tok::Word(word) => Ok(Query::Text(word)),
+            Tok::Field(key, value) => parse_field(&key, &value, token.start),
+        }
+    }
+}
+
+fn parse_field(key: &str, value: &str, start: usize) -> Result<Query, QueryError> {
+    // 値の位置 = key + ':' の後ろ
+    let value_pos = start + key.chars().count() + 1;
+    // "TODO:" や "http://..." のような、フィールドではない key: はそのまま文字列として探す
+    let literal = || Ok(Query::Text(format!("{}:{}", key, value)));
+    if value.is_empty() {
+        return literal();
+    }
+    match key {
+        "tag" => Ok(Query::Tag(value.to_string())),
+        "color" => Ok(Query::Color(value.to_string())),
+        "seq" => {
+            let (cmp, rest) = split_cmp(value);
+            let n = rest.parse::<i32>().map_err(|_| {
+                QueryError::new(value_pos + (value.len() - rest.len()), format!("'{}' is not a number", rest))
+            })?;
+            Ok(Query::Seq(cmp, n))
+        }
+        "updated" => {
+            let (cmp, rest) = split_cmp(value);
+            if chrono::NaiveDate::parse_from_str(rest, "%Y-%m-%d").is_err() {
+                return Err(QueryError::new(
+                    value_pos + (value.len() - rest.len()),
+                    format!("'{}' is not a date (YYYY-MM-DD)", rest),
+                ));
+            }
+            Ok(Query::Updated(cmp, rest.to_string()))
+        }
+        "in" => match value.to_lowercase().as_str() {
+            "notes" => Ok(Query::In(Scope::Notes)),
+            "archive" => Ok(Query::In(Scope::Archive)),
+            "trash" => Ok(Query::In(Scope::Trash)),
+            "all" => Ok(Query::In(Scope::All)),
+            other => Err(QueryError::new(
+                value_pos,
+                format!("unknown scope '{}' (notes, archive, trash, all)", other),
+            )),
+        },
+        _ => literal(),
+    }
+}
+
+// 比較演算子は ASCII なのでバイト長 = 文字数
+fn split_cmp(value: &str) -> (Cmp, &str) {
+    for (prefix, cmp) in [(">=", Cmp::Ge), ("<=", Cmp::Le), (">", Cmp::Gt), ("<", Cmp::Lt), ("=", Cmp::Eq)] {
+        if let Some(rest) = value.strip_prefix(prefix) {
+            return (cmp, rest);
+        }
+    }
+    (Cmp::Eq, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_implicit_and_with_or_and_not() {
+        let q = parse("memo tag:work OR NOT \"東京 タワー\"").unwrap();
+        let Query::Or(items) = q else { panic!("expected OR") };
+        assert!(matches!(&items[0], Query::And(a) if a.len() == 2));
+        assert!(matches!(&items[1], Query::Not(inner) if matches!(**inner, Query::Text(ref t) if t == "東京 タワー")));
+    }
+
+    #[test]
+    fn parse_fields_and_comparisons() {
+        assert!(matches!(parse("seq:>=12").unwrap(), Query::Seq(Cmp::Ge, 12)));
+        assert!(matches!(parse("updated:>2026-01-01").unwrap(), Query::Updated(Cmp::Gt, ref d) if d == "2026-01-01"));
+        assert!(matches!(parse("color:#f7e9b0").unwrap(), Query::Color(ref c) if c == "#f7e9b0"));
+        assert!(matches!(parse("tag:\"my tag\"").unwrap(), Query::Tag(ref t) if t == "my tag"));
+        assert!(matches!(parse("in:trash").unwrap(), Query::In(Scope::Trash)));
+        assert!(matches!(parse("foo NOT in:archive").unwrap(), Query::And(ref items) if matches!(items[1], Query::Not(_))));
+        // 数字だけのキーはただの単語
+        assert!(matches!(parse("10:30").unwrap(), Query::Text(ref t) if t == "10:30"));
+    }
+
+    #[test]
+    fn unknown_fields_and_empty_values_are_searched_literally() {
+        assert!(matches!(parse("TODO:").unwrap(), Query::Text(ref t) if t == "todo:"));
+        assert!(matches!(parse("http://example.com").unwrap(), Query::Text(ref t) if t == "http://example.com"));
+        assert!(matches!(parse("note:").unwrap(), Query::Text(ref t) if t == "note:"));
+        assert!(matches!(parse("tag:").unwrap(), Query::Text(ref t) if t == "tag:"));
+        // 知っているフィールドの値がおかしいときはエラーのまま
+        assert!(parse("seq:abc").is_err());
+    }
+
+    #[test]
+    fn parse_regex_and_grouping() {
+        let q = parse("(a OR b) /fo+\\/bar/").unwrap();
+        let Query::And(items) = q else { panic!("expected AND") };
+        assert!(matches!(items[0], Query::Or(_)));
+        assert!(matches!(&items[1], Query::Regex(re) if re.as_str() == "fo+/bar"));
+    }
+
+    #[test]
+    fn parse_errors_report_positions() {
+        assert_eq!(parse("foo \"bar").unwrap_err().position, 4);
+        assert_eq!(parse("(foo").unwrap_err().position, 0);
+        assert_eq!(parse("foo)").unwrap_err().position, 3);
+        assert_eq!(parse("foo AND").unwrap_err().position, 7);
+        assert_eq!(parse("seq:abc").unwrap_err().position, 4);
+        assert_eq!(parse("updated:>2026-13-01").unwrap_err().position, 9);
+        assert_eq!(parse("/[/").unwrap_err().position, 0);
+        assert_eq!(parse("in:nowhere").unwrap_err().position, 3);
+    }
+}
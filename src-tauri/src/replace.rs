@@ -0,0 +1,144 @@
+// [NEW] Vault 全体の検索・置換
+// 1. プレビュー: 置換後の本文を作って、ノートごとに変わる行を返す（書き込みはしない）
+// 2. 適用: プレビューしたノート（パス + バージョン）だけを書き換える
+// 置換は本文の行ごとに行い、フロントマターには触れない。
+
+use regex::{NoExpand, Regex, RegexBuilder};
+
+use crate::state::NoteVersion;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct ReplaceSnippet {
+    /// 本文の行番号（1始まり。検索結果の行番号と同じ数え方）
+    pub line: usize,
+    pub before: String,
+    pub after: String,
+}
+
+/// プレビューの1ノート分
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct ReplacePreview {
+    pub path: String,
+    pub context: String,
+    /// 適用時にこのバージョンから変わっていたらスキップする
+    pub version: NoteVersion,
+    /// 置換される箇所の数
+    pub count: usize,
+    pub snippets: Vec<ReplaceSnippet>,
+}
+
+/// 適用対象（プレビューの path + version をそのまま返してもらう）
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct ReplaceTarget {
+    pub path: String,
+    pub version: NoteVersion,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct ReplacedNote {
+    pub old_path: String,
+    /// 1行目が変わってリネームされた場合は新しいパス
+    pub new_path: String,
+    pub count: usize,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct SkippedNote {
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(serde::Serialize, Clone, Debug, Default)]
+pub struct ReplaceReport {
+    pub replaced: Vec<ReplacedNote>,
+    pub skipped: Vec<SkippedNote>,
+}
+
+/// 検索語と置換文字列。use_regex が false なら両方ともそのままの文字列として扱う。
+pub struct Replacer {
+    pattern: Regex,
+    replacement: String,
+    use_regex: bool,
+}
+
+impl Replacer {
+    pub fn new(find: &str, replacement: &str, case_sensitive: bool, use_regex: bool) -> Result<Self, String> {
+        if find.is_empty() {
+            return Err("検索する文字列が空です".to_string());
+        }
+        let source = if use_regex { find.to_string() } else { regex::escape(find) };
+        let pattern = RegexBuilder::new(&source)
+            .case_insensitive(!case_sensitive)
+            .build()
+            .map_err(|e| format!("正規表現が正しくありません: {}", e))?;
+        Ok(Self { pattern, replacement: replacement.to_string(), use_regex })
+    }
+
+    /// 本文を置換する。変更が無ければ None、あれば (新しい本文, 置換数, 変わった行)。
+    pub fn apply(&self, body: &str) -> Option<(String, usize, Vec<ReplaceSnippet>)> {
+        let mut count = 0;
+        let mut snippets = Vec::new();
+        // '\n' で分けて '\n' で戻すので、改行コードはそのまま残る
+        let lines: Vec<String> = body
+            .split('\n')
+            .enumerate()
+            .map(|(i, line)| {
+                let matches = self.pattern.find_iter(line).filter(|m| !m.is_empty()).count();
+                if matches == 0 {
+                    return line.to_string();
+                }
+                let replaced = if self.use_regex {
+                    self.pattern.replace_all(line, self.replacement.as_str()).into_owned()
+                } else {
+                    self.pattern.replace_all(line, NoExpand(&self.replacement)).into_owned()
+                };
+                if replaced != line {
+                    count += matches;
+                    snippets.push(ReplaceSnippet { line: i + 1, before: line.to_string(), after: replaced.clone() });
+                }
+                replaced
+            })
+            .collect();
+
+        if snippets.is_empty() {
+            None
+        } else {
+            Some((lines.join("\n"), count, snippets))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_replace_ignores_case_and_regex_syntax() {
+        let replacer = Replacer::new("project.x", "$Apollo", false, false).unwrap();
+        let (body, count, snippets) = replacer.apply("Project.X kickoff\nmemo\nprojectyx, project.x").unwrap();
+        assert_eq!(body, "$Apollo kickoff\nmemo\nprojectyx, $Apollo");
+        assert_eq!(count, 2);
+        assert_eq!(snippets.iter().map(|s| s.line).collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(snippets[1].before, "projectyx, project.x");
+
+        assert!(replacer.apply("nothing here").is_none());
+    }
+
+    #[test]
+    fn regex_replace_expands_groups_and_keeps_line_endings() {
+        let replacer = Replacer::new(r"(\d+)月", "${1}/", true, true).unwrap();
+        let (body, count, _) = replacer.apply("1月と2月\r\n予定").unwrap();
+        assert_eq!(body, "1/と2/\r\n予定");
+        assert_eq!(count, 2);
+
+        assert!(Replacer::new("(", "", true, true).is_err());
+        assert!(Replacer::new("", "x", true, false).is_err());
+    }
+
+    #[test]
+    fn case_sensitive_replace_skips_other_cases() {
+        let replacer = Replacer::new("Tokyo", "東京", true, false).unwrap();
+        let (body, count, _) = replacer.apply("Tokyo tokyo TOKYO").unwrap();
+        assert_eq!((body.as_str(), count), ("東京 tokyo TOKYO", 1));
+    }
+}
@@ -0,0 +1,897 @@
+// [NEW] 全文検索インデックス
+// 日本語は単語の区切りが無いので、CJK 文字は 1文字 + 2文字 (bigram)、
+// ラテン文字は単語単位でトークン化した転置インデックスを持つ。
+// インデックスは Vault の隠しフォルダ (.ore-no-fusen/search.json) に保存し、
+// 検索のたびに mtime + サイズで変更を検出して差分だけ更新する。
+// クエリの文法は query.rs を参照。
+// 検索オーバーレイからはバックグラウンドのジョブとして実行し、結果を小分けにして返す。
+// 同じウィンドウで新しいジョブが始まると、そのウィンドウの前のジョブは途中で打ち切る。
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use walkdir::WalkDir;
+
+use crate::query::{self, Query, QueryError, Scope};
+use crate::state::NoteMeta;
+use crate::{index, logic, storage};
+
+const SEARCH_FILE: &str = "search.json";
+// トークン化の方法を変えたら上げる（古いインデックスは作り直す）
+const SEARCH_VERSION: u32 = 2;
+const PREVIEW_CHARS: usize = 80;
+// 長い行を切り出すとき、一致箇所の前に残す文字数
+const PREVIEW_LEAD: usize = 20;
+// ヒット行の前後に付ける行数
+const CONTEXT_LINES: usize = 1;
+// ストリーミング検索で1回のイベントに載せるノート数
+const BATCH_GROUPS: usize = 20;
+
+/// preview 内の一致範囲（文字単位のオフセット。end は含まない）
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct MatchRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct SearchHit {
+    pub path: String,
+    pub line: usize,
+    /// 一致箇所が見えるように切り出した行
+    pub preview: String,
+    /// preview 内のハイライト範囲（メタデータのみの一致なら空）
+    pub ranges: Vec<MatchRange>,
+    /// 前後の行（最大 CONTEXT_LINES 行ずつ）
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+    /// ノート単位のスコア（大きいほど上位）
+    pub score: f64,
+}
+
+/// ノート単位にまとめた検索結果。オーバーレイが追加で fusen_get_note しなくて済むよう、
+/// 表示に使うメタデータも載せる。
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct SearchGroup {
+    pub path: String,
+    pub seq: i32,
+    pub context: String,
+    pub tags: Vec<String>,
+    pub background_color: Option<String>,
+    pub score: f64,
+    pub hits: Vec<SearchHit>,
+}
+
+// (doc id, 本文の行番号 1始まり)
+type Posting = (u32, u32);
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+struct Doc {
+    /// Vault からの相対パス
+    rel_path: String,
+    mtime_ms: i64,
+    size: u64,
+    /// 本文の行（フロントマターと先頭の空行を除く。行番号はフロントの表示と一致）
+    lines: Vec<String>,
+    /// tag: / color: / seq: / updated: 用
+    meta: NoteMeta,
+}
+
+impl Doc {
+    fn scope(&self) -> Scope {
+        let path = Path::new(&self.rel_path);
+        if path.components().count() <= 1 {
+            return Scope::Notes;
+        }
+        match path.components().next().and_then(|c| c.as_os_str().to_str()) {
+            Some("Trash") => Scope::Trash,
+            Some("Archive") | Some("tags") => Scope::Archive,
+            _ => Scope::Notes,
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Default, Clone)]
+struct SearchIndex {
+    version: u32,
+    next_id: u32,
+    docs: HashMap<u32, Doc>,
+    postings: BTreeMap<String, BTreeSet<Posting>>,
+}
+
+/// ストリーミング検索の結果の一部 (fusen:search_results イベント)
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct SearchBatch {
+    pub request_id: u64,
+    /// スコア順。前のバッチの続き
+    pub groups: Vec<SearchGroup>,
+    /// 最後のバッチなら true（打ち切られたジョブは done を送らない）
+    pub done: bool,
+}
+
+// 検索のたびにファイルから読み直さないよう、メモリ上にも保持する。
+// ロックするのは更新の間だけで、結果を作る間は Arc で共有したスナップショットを読む
+// （他のウィンドウの検索や fusen_search_notes を待たせないため）
+static CACHE: Mutex<Option<(PathBuf, Arc<SearchIndex>)>> = Mutex::new(None);
+// ウィンドウ (label) ごとに実行中であるべきジョブの request id。これと違うジョブは打ち切る。
+// request id はウィンドウごとに数えるので、別のウィンドウの検索とは混ざらない
+static CURRENT_JOBS: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
+
+fn is_current(owner: &str, request_id: u64) -> bool {
+    CURRENT_JOBS.lock().unwrap().get(owner) == Some(&request_id)
+}
+
+/// 全文検索。インデックスが無い・古い場合は自動で作り直す。
+pub fn search(folder_path: &str, query: &str) -> Result<Vec<SearchGroup>, QueryError> {
+    let parsed = query::parse(query)?;
+    let mut groups = Vec::new();
+    stream(Path::new(folder_path), &parsed, &|| false, &mut |batch, _| groups.extend(batch));
+    Ok(groups)
+}
+
+/// 検索ジョブを別スレッドで開始する。構文エラーはその場で返し、
+/// 結果は emit に BATCH_GROUPS 件ずつ渡す。同じ owner (ウィンドウ) の前のジョブは打ち切られる。
+pub fn start_job(
+    owner: String,
+    folder_path: String,
+    query: &str,
+    request_id: u64,
+    mut emit: impl FnMut(SearchBatch) + Send + 'static,
+) -> Result<(), QueryError> {
+    let parsed = query::parse(query)?;
+    CURRENT_JOBS.lock().unwrap().insert(owner.clone(), request_id);
+    std::thread::spawn(move || {
+        let cancelled = || !is_current(&owner, request_id);
+        let completed = stream(Path::new(&folder_path), &parsed, &cancelled, &mut |groups, done| {
+            emit(SearchBatch { request_id, groups, done })
+        });
+        if completed {
+            cancel_job(&owner, request_id);
+        } else {
+            eprintln!("[Search] Job {} of {} cancelled", request_id, owner);
+        }
+    });
+    Ok(())
+}
+
+/// owner の実行中のジョブを打ち切る（既に別のジョブに替わっていれば何もしない）
+pub fn cancel_job(owner: &str, request_id: u64) {
+    let mut jobs = CURRENT_JOBS.lock().unwrap();
+    if jobs.get(owner) == Some(&request_id) {
+        jobs.remove(owner);
+    }
+}
+
+/// インデックスを更新してから、結果をスコア順に小分けで emit(groups, done) に渡す。
+/// 途中で cancelled() が true になったら false を返す。
+fn stream(
+    vault: &Path,
+    query: &Query,
+    cancelled: &dyn Fn() -> bool,
+    emit: &mut dyn FnMut(Vec<SearchGroup>, bool),
+) -> bool {
+    let snapshot = {
+        let mut cache = CACHE.lock().unwrap();
+        if !matches!(cache.as_ref(), Some((p, _)) if p == vault) {
+            *cache = Some((vault.to_path_buf(), Arc::new(load(vault))));
+        }
+        let (_, index) = cache.as_mut().unwrap();
+
+        // 打ち切られた場合も読んだ分はメモリ上に残るので、次のジョブはその続きから。
+        // 結果を作っている最中のジョブが古いスナップショットを持っていれば、ここで複製してから更新する
+        let Some(changed) = Arc::make_mut(index).refresh_until(vault, cancelled) else { return false };
+        if changed {
+            if let Err(e) = save(vault, index) {
+                crate::logger::log_warn(&format!("検索インデックスの保存に失敗しました: {}", e));
+            }
+        }
+        Arc::clone(index)
+    };
+    snapshot.stream_results(vault, query, cancelled, emit)
+}
+
+fn load(vault: &Path) -> SearchIndex {
+    fs::read_to_string(index::app_dir(vault).join(SEARCH_FILE))
+        .ok()
+        .and_then(|s| serde_json::from_str::<SearchIndex>(&s).ok())
+        .filter(|idx| idx.version == SEARCH_VERSION)
+        .unwrap_or_else(|| SearchIndex { version: SEARCH_VERSION, ..Default::default() })
+}
+
+fn save(vault: &Path, index: &SearchIndex) -> Result<(), String> {
+    let dir = index::app_dir(vault);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string(index).map_err(|e| e.to_string())?;
+    storage::atomic_write(&dir.join(SEARCH_FILE), json.as_bytes())
+}
+
+// 検索対象: Vault 以下の .md（隠しフォルダは除く。Trash は in:trash / in:all で一致したときだけ結果に出す）
+fn searchable_files(vault: &Path) -> impl Iterator<Item = (String, PathBuf)> + '_ {
+    WalkDir::new(vault)
+        .into_iter()
+        .filter_entry(|e| {
+            let name = e.file_name().to_string_lossy();
+            e.depth() == 0 || !name.starts_with('.')
+        })
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && e.path().extension().and_then(|ext| ext.to_str()) == Some("md"))
+        .filter_map(move |e| {
+            let rel = e.path().strip_prefix(vault).ok()?.to_string_lossy().to_string();
+            Some((rel, e.path().to_path_buf()))
+        })
+}
+
+fn body_lines(content: &str) -> Vec<String> {
+    let (_, body) = logic::split_frontmatter(content);
+    body.lines()
+        .skip_while(|l| l.trim().is_empty())
+        .map(|l| l.to_string())
+        .collect()
+}
+
+impl SearchIndex {
+    /// ディスクと突き合わせて差分を反映する。変更があれば true。
+    #[cfg(test)]
+    fn refresh(&mut self, vault: &Path) -> bool {
+        self.refresh_until(vault, &|| false).unwrap_or(false)
+    }
+
+    /// 変更のあったファイルだけ読み直す。変更があれば Some(true)、打ち切られたら None。
+    fn refresh_until(&mut self, vault: &Path, cancelled: &dyn Fn() -> bool) -> Option<bool> {
+        let mut changed = false;
+        let mut by_path: HashMap<String, u32> = self.docs.iter().map(|(id, d)| (d.rel_path.clone(), *id)).collect();
+
+        for (rel_path, path) in searchable_files(vault) {
+            if cancelled() {
+                return None;
+            }
+            let Ok(metadata) = fs::metadata(&path) else { continue };
+            let (mtime_ms, size) = (storage::mtime_ms(&metadata), metadata.len());
+
+            let existing = by_path.remove(&rel_path);
+            if let Some(id) = existing {
+                let doc = &self.docs[&id];
+                if doc.mtime_ms == mtime_ms && doc.size == size {
+                    continue;
+                }
+                self.remove_doc(id);
+            }
+            let content = fs::read_to_string(&path).unwrap_or_default();
+            let meta = logic::build_note_meta(&path.to_string_lossy(), &content);
+            self.add_doc(Doc { rel_path, mtime_ms, size, lines: body_lines(&content), meta });
+            changed = true;
+        }
+
+        // 消えたファイル（最後まで走査できたときだけ判定できる）
+        for (_, id) in by_path {
+            self.remove_doc(id);
+            changed = true;
+        }
+        Some(changed)
+    }
+
+    fn add_doc(&mut self, doc: Doc) {
+        let id = self.next_id;
+        self.next_id += 1;
+        for (i, line) in doc.lines.iter().enumerate() {
+            for token in tokenize(&normalize(line), false) {
+                self.postings.entry(token).or_default().insert((id, i as u32 + 1));
+            }
+        }
+        self.docs.insert(id, doc);
+    }
+
+    fn remove_doc(&mut self, id: u32) {
+        let Some(doc) = self.docs.remove(&id) else { return };
+        for (i, line) in doc.lines.iter().enumerate() {
+            for token in tokenize(&normalize(line), false) {
+                if let Some(set) = self.postings.get_mut(&token) {
+                    set.remove(&(id, i as u32 + 1));
+                    if set.is_empty() {
+                        self.postings.remove(&token);
+                    }
+                }
+            }
+        }
+    }
+
+    // ラテン文字の単語は前方一致で引く（"hel" で "hello" に当たるように）
+    fn lookup(&self, token: &str) -> BTreeSet<Posting> {
+        if token.chars().any(is_cjk) {
+            return self.postings.get(token).cloned().unwrap_or_default();
+        }
+        self.postings
+            .range(token.to_string()..)
+            .take_while(|(key, _)| key.starts_with(token))
+            .flat_map(|(_, set)| set.iter().copied())
+            .collect()
+    }
+
+    /// 単語・フレーズに一致する行 (doc id -> 行番号)
+    fn text_lines(&self, text: &str) -> HashMap<u32, BTreeSet<u32>> {
+        let needle = normalize(text);
+        // 1. 候補行をトークンの積集合で絞る（トークンが無い記号だけの語は全行）
+        let tokens = tokenize(&needle, true);
+        let candidates: Vec<Posting> = if tokens.is_empty() {
+            self.docs
+                .iter()
+                .flat_map(|(id, d)| (1..=d.lines.len() as u32).map(move |line| (*id, line)))
+                .collect()
+        } else {
+            let mut sets = tokens.iter().map(|t| self.lookup(t));
+            let first = sets.next().unwrap_or_default();
+            sets.fold(first, |acc, set| acc.intersection(&set).copied().collect())
+                .into_iter()
+                .collect()
+        };
+
+        // 2. 実際の行で部分一致を確認（bigram の偽陽性を除く）
+        let mut found: HashMap<u32, BTreeSet<u32>> = HashMap::new();
+        for (id, line) in candidates {
+            if normalize(&self.docs[&id].lines[line as usize - 1]).contains(&needle) {
+                found.entry(id).or_default().insert(line);
+            }
+        }
+        found
+    }
+
+    #[cfg(test)]
+    fn run(&self, vault: &Path, query: &Query) -> Vec<SearchGroup> {
+        let mut groups = Vec::new();
+        self.stream_results(vault, query, &|| false, &mut |batch, _| groups.extend(batch));
+        groups
+    }
+
+    fn stream_results(
+        &self,
+        vault: &Path,
+        query: &Query,
+        cancelled: &dyn Fn() -> bool,
+        emit: &mut dyn FnMut(Vec<SearchGroup>, bool),
+    ) -> bool {
+        let ranked = self.rank(query);
+        let mut highlights = Vec::new();
+        collect_highlights(query, &mut highlights);
+
+        // プレビュー等の組み立てはバッチごとに行い、その間に打ち切りを確認する
+        let mut chunks = ranked.chunks(BATCH_GROUPS).peekable();
+        if chunks.peek().is_none() {
+            emit(Vec::new(), true);
+            return true;
+        }
+        while let Some(chunk) = chunks.next() {
+            if cancelled() {
+                return false;
+            }
+            let groups = chunk.iter().map(|(score, id, lines)| self.group(vault, *id, lines, *score, &highlights)).collect();
+            emit(groups, chunks.peek().is_none());
+        }
+        true
+    }
+
+    /// 一致したノートをスコア順に並べる (score, doc id, ハイライトする行)
+    fn rank(&self, query: &Query) -> Vec<(f64, u32, Vec<u32>)> {
+        // 単語・フレーズは転置インデックスでまとめて引いておく
+        let mut texts = Vec::new();
+        collect_texts(query, &mut texts);
+        let text_hits: HashMap<String, HashMap<u32, BTreeSet<u32>>> =
+            texts.iter().map(|t| (t.clone(), self.text_lines(t))).collect();
+
+        // ノート単位でスコア付け: ヒット行数 + タイトル（1行目）一致ボーナス
+        let mut ranked: Vec<(f64, i64, u32, Vec<u32>)> = Vec::new();
+        for (id, doc) in &self.docs {
+            let Some(lines) = eval(query, *id, doc, &text_hits) else { continue };
+            if doc.scope() == Scope::Trash && !admits_trash(query, *id, doc, &text_hits) {
+                continue;
+            }
+            let lines: Vec<u32> = lines.into_iter().collect();
+            let title_bonus = if lines.first() == Some(&1) { 2.0 } else { 0.0 };
+            ranked.push((lines.len() as f64 + title_bonus, doc.mtime_ms, *id, lines));
+        }
+        // スコア降順、同点なら新しいノートを上に
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0).then(b.1.cmp(&a.1)).then(a.2.cmp(&b.2)));
+        ranked.into_iter().map(|(score, _, id, lines)| (score, id, lines)).collect()
+    }
+
+    fn group(&self, vault: &Path, id: u32, lines: &[u32], score: f64, highlights: &[Highlight]) -> SearchGroup {
+        let doc = &self.docs[&id];
+        let path = vault.join(&doc.rel_path).to_string_lossy().to_string();
+        // メタデータだけで一致したノートは1行目を返す
+        let lines = if lines.is_empty() { &[1][..] } else { lines };
+        let hits = lines
+            .iter()
+            .map(|line| build_hit(&path, &doc.lines, *line as usize, highlights, score))
+            .collect();
+        SearchGroup {
+            path,
+            seq: doc.meta.seq,
+            context: doc.meta.context.clone(),
+            tags: doc.meta.tags.clone(),
+            background_color: doc.meta.background_color.clone(),
+            score,
+            hits,
+        }
+    }
+}
+
+fn build_hit(path: &str, lines: &[String], line: usize, highlights: &[Highlight], score: f64) -> SearchHit {
+    let idx = (line - 1).min(lines.len());
+    let text = lines.get(idx).map_or("", String::as_str);
+    let (preview, ranges) = preview(text, &match_ranges(text, highlights));
+    let before = lines[idx.saturating_sub(CONTEXT_LINES)..idx].iter().map(|l| clip(l)).collect();
+    let after = lines
+        .iter()
+        .skip(idx + 1)
+        .take(CONTEXT_LINES)
+        .map(|l| clip(l))
+        .collect();
+    SearchHit { path: path.to_string(), line, preview, ranges, before, after, score }
+}
+
+fn collect_texts(query: &Query, out: &mut Vec<String>) {
+    match query {
+        Query::Text(t) => out.push(t.clone()),
+        Query::And(items) | Query::Or(items) => items.iter().for_each(|q| collect_texts(q, out)),
+        Query::Not(inner) => collect_texts(inner, out),
+        _ => {}
+    }
+}
+
+// ハイライト対象（NOT の中の語は光らせない）
+enum Highlight<'a> {
+    /// 正規化済みの語
+    Text(Vec<char>),
+    Regex(&'a regex::Regex),
+}
+
+fn collect_highlights<'a>(query: &'a Query, out: &mut Vec<Highlight<'a>>) {
+    match query {
+        Query::Text(t) => out.push(Highlight::Text(normalize(t).chars().collect())),
+        Query::Regex(re) => out.push(Highlight::Regex(re)),
+        Query::And(items) | Query::Or(items) => items.iter().for_each(|q| collect_highlights(q, out)),
+        _ => {}
+    }
+}
+
+/// 行内の一致範囲（元の行での文字オフセット）。重なり・隣接はまとめる。
+fn match_ranges(line: &str, highlights: &[Highlight]) -> Vec<(usize, usize)> {
+    // 正規化後の文字 -> 元の文字位置（小文字化で文字数が変わることがある）
+    let mut norm = Vec::new();
+    let mut origin = Vec::new();
+    for (i, c) in line.chars().enumerate() {
+        for n in normalize_char(c) {
+            norm.push(n);
+            origin.push(i);
+        }
+    }
+
+    let mut ranges = Vec::new();
+    for highlight in highlights {
+        match highlight {
+            Highlight::Text(needle) if !needle.is_empty() && needle.len() <= norm.len() => {
+                for start in 0..=norm.len() - needle.len() {
+                    if norm[start..start + needle.len()] == needle[..] {
+                        ranges.push((origin[start], origin[start + needle.len() - 1] + 1));
+                    }
+                }
+            }
+            Highlight::Regex(re) => {
+                for m in re.find_iter(line).filter(|m| !m.is_empty()) {
+                    let start = line[..m.start()].chars().count();
+                    ranges.push((start, start + m.as_str().chars().count()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    ranges.sort();
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// ノートがクエリに一致すれば、ハイライトすべき行の集合を返す（メタデータのみの一致なら空集合）
+fn eval(query: &Query, id: u32, doc: &Doc, text_hits: &HashMap<String, HashMap<u32, BTreeSet<u32>>>) -> Option<BTreeSet<u32>> {
+    let matched = |ok: bool| if ok { Some(BTreeSet::new()) } else { None };
+    match query {
+        Query::Text(t) => text_hits.get(t).and_then(|m| m.get(&id)).cloned(),
+        Query::Regex(re) => {
+            let lines: BTreeSet<u32> = doc
+                .lines
+                .iter()
+                .enumerate()
+                .filter(|(_, l)| re.is_match(l))
+                .map(|(i, _)| i as u32 + 1)
+                .collect();
+            if lines.is_empty() { None } else { Some(lines) }
+        }
+        Query::Tag(tag) => matched(doc.meta.tags.iter().any(|t| t.trim().eq_ignore_ascii_case(tag.trim()))),
+        Query::Color(color) => {
+            let norm = |c: &str| c.trim().trim_start_matches('#').to_lowercase();
+            matched(doc.meta.background_color.as_deref().is_some_and(|c| norm(c) == norm(color)))
+        }
+        Query::Seq(cmp, n) => matched(cmp.test(doc.meta.seq, *n)),
+        Query::Updated(cmp, date) => {
+            let updated: String = doc.meta.updated.chars().take(10).collect();
+            matched(!updated.is_empty() && cmp.test(updated.as_str(), date.as_str()))
+        }
+        Query::In(scope) => matched(*scope == Scope::All || *scope == doc.scope()),
+        Query::And(items) => {
+            let mut lines = BTreeSet::new();
+            for q in items {
+                lines.extend(eval(q, id, doc, text_hits)?);
+            }
+            Some(lines)
+        }
+        Query::Or(items) => {
+            let mut result: Option<BTreeSet<u32>> = None;
+            for q in items {
+                if let Some(lines) = eval(q, id, doc, text_hits) {
+                    result.get_or_insert_with(BTreeSet::new).extend(lines);
+                }
+            }
+            result
+        }
+        Query::Not(inner) => matched(eval(inner, id, doc, text_hits).is_none()),
+    }
+}
+
+/// ゴミ箱のノートは、肯定の in:trash / in:all がそのノートに一致したときだけ結果に出す
+/// （NOT in:trash や、OR の別の枝で一致しただけなら出さない）
+fn admits_trash(query: &Query, id: u32, doc: &Doc, text_hits: &HashMap<String, HashMap<u32, BTreeSet<u32>>>) -> bool {
+    match query {
+        Query::In(scope) => matches!(scope, Scope::Trash | Scope::All),
+        Query::And(items) => items.iter().any(|q| admits_trash(q, id, doc, text_hits)),
+        Query::Or(items) => items
+            .iter()
+            .any(|q| eval(q, id, doc, text_hits).is_some() && admits_trash(q, id, doc, text_hits)),
+        _ => false,
+    }
+}
+
+/// 最初の一致箇所が見えるように行を切り出し、範囲を preview 内のオフセットに直す
+fn preview(line: &str, ranges: &[(usize, usize)]) -> (String, Vec<MatchRange>) {
+    let chars: Vec<char> = line.chars().collect();
+    let (start, end) = if chars.len() > PREVIEW_CHARS {
+        let first = ranges.first().map_or(0, |r| r.0);
+        let start = first.saturating_sub(PREVIEW_LEAD).min(chars.len() - PREVIEW_CHARS);
+        (start, start + PREVIEW_CHARS)
+    } else {
+        (0, chars.len())
+    };
+    let prefix = if start > 0 { "..." } else { "" };
+    let suffix = if end < chars.len() { "..." } else { "" };
+    let text: String = chars[start..end].iter().collect();
+
+    let shift = prefix.len();
+    let ranges = ranges
+        .iter()
+        .filter(|r| r.0 < end && r.1 > start)
+        .map(|r| MatchRange { start: r.0.max(start) - start + shift, end: r.1.min(end) - start + shift })
+        .collect();
+    (format!("{}{}{}", prefix, text, suffix), ranges)
+}
+
+// 前後の行は先頭から切り詰めるだけ
+fn clip(line: &str) -> String {
+    if line.chars().count() > PREVIEW_CHARS {
+        let start: String = line.chars().take(PREVIEW_CHARS).collect();
+        format!("{}...", start)
+    } else {
+        line.to_string()
+    }
+}
+
+// --- トークン化 ---
+
+// 全角英数を半角に寄せてから小文字化
+fn normalize(s: &str) -> String {
+    s.chars().flat_map(normalize_char).collect()
+}
+
+fn normalize_char(c: char) -> std::char::ToLowercase {
+    let c = match c {
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        '\u{3000}' => ' ',
+        _ => c,
+    };
+    c.to_lowercase()
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'   // ひらがな・カタカナ
+        | '\u{3400}'..='\u{4DBF}' // CJK拡張A
+        | '\u{4E00}'..='\u{9FFF}' // CJK統合漢字
+        | '\u{F900}'..='\u{FAFF}' // CJK互換漢字
+        | '\u{FF66}'..='\u{FF9F}' // 半角カタカナ
+        | '\u{AC00}'..='\u{D7AF}' // ハングル
+    )
+}
+
+/// 正規化済みテキストをトークンに分ける。
+/// CJK の連続は bigram（インデックス側は 1文字も入れる。クエリ側は2文字以上なら bigram のみ）、
+/// それ以外の英数字は単語単位。
+fn tokenize(text: &str, for_query: bool) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut cjk: Vec<char> = Vec::new();
+
+    let flush_cjk = |cjk: &mut Vec<char>, tokens: &mut Vec<String>| {
+        if !for_query || cjk.len() == 1 {
+            tokens.extend(cjk.iter().map(|c| c.to_string()));
+        }
+        tokens.extend(cjk.windows(2).map(|w| w.iter().collect::<String>()));
+        cjk.clear();
+    };
+
+    for c in text.chars() {
+        if is_cjk(c) {
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+            cjk.push(c);
+        } else {
+            if !cjk.is_empty() {
+                flush_cjk(&mut cjk, &mut tokens);
+            }
+            if c.is_alphanumeric() {
+                word.push(c);
+            } else if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+    if !cjk.is_empty() {
+        flush_cjk(&mut cjk, &mut tokens);
+    }
+    tokens.sort();
+    tokens.dedup();
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index_of(dir: &Path) -> SearchIndex {
+        let mut index = SearchIndex { version: SEARCH_VERSION, ..Default::default() };
+        index.refresh(dir);
+        index
+    }
+
+    fn run_query(index: &SearchIndex, dir: &Path, q: &str) -> Vec<SearchHit> {
+        index.run(dir, &query::parse(q).unwrap()).into_iter().flat_map(|g| g.hits).collect()
+    }
+
+    #[test]
+    fn tokenize_uses_bigrams_for_japanese_and_words_for_latin() {
+        assert_eq!(tokenize("東京タワー", true), vec!["タワ", "ワー", "京タ", "東京"]);
+        assert_eq!(tokenize("hello, world", true), vec!["hello", "world"]);
+        // インデックス側は1文字も持つ
+        assert!(tokenize("猫です", false).contains(&"猫".to_string()));
+        // 全角英数は半角・小文字に寄せる
+        assert_eq!(tokenize(&normalize("ＡＢＣ"), true), vec!["abc"]);
+    }
+
+    #[test]
+    fn query_finds_japanese_substrings() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.md"), "---\nseq: 1\n---\n\n買い物リスト\n東京タワーに行く").unwrap();
+        fs::write(dir.path().join("b.md"), "京都タワー").unwrap();
+        let index = index_of(dir.path());
+
+        let hits = run_query(&index, dir.path(), "東京タワー");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].line, 2);
+        assert!(hits[0].path.ends_with("a.md"));
+
+        // 1文字検索
+        assert_eq!(run_query(&index, dir.path(), "京").len(), 2);
+    }
+
+    #[test]
+    fn query_ranks_title_matches_first_and_matches_latin_prefixes() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.md"), "memo\nsee Rust docs").unwrap();
+        fs::write(dir.path().join("b.md"), "Rustacean notes\nbody").unwrap();
+        let index = index_of(dir.path());
+
+        let hits = run_query(&index, dir.path(), "rust");
+        assert_eq!(hits.len(), 2);
+        assert!(hits[0].path.ends_with("b.md"));
+        assert!(hits[0].score > hits[1].score);
+    }
+
+    #[test]
+    fn query_filters_by_metadata_and_scope() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("Trash")).unwrap();
+        fs::create_dir_all(dir.path().join("Archive")).unwrap();
+        fs::write(
+            dir.path().join("0003_2026-02-01_A.md"),
+            "---\nseq: 3\nupdated: 2026-02-01\ntags: [work]\nbackgroundColor: #f7e9b0\n---\n\nplan\nmeeting notes",
+        ).unwrap();
+        fs::write(dir.path().join("0001_2025-12-01_B.md"), "---\nseq: 1\nupdated: 2025-12-01\n---\n\nmeeting").unwrap();
+        fs::write(dir.path().join("Archive").join("0002_2026-01-05_C.md"), "meeting archived").unwrap();
+        fs::write(dir.path().join("Trash").join("0004_2026-01-06_D.md"), "meeting trashed").unwrap();
+        let index = index_of(dir.path());
+
+        assert_eq!(run_query(&index, dir.path(), "meeting").len(), 3);
+        let hits = run_query(&index, dir.path(), "meeting tag:work");
+        assert_eq!((hits.len(), hits[0].line), (1, 2));
+        assert_eq!(run_query(&index, dir.path(), "color:F7E9B0")[0].line, 1);
+        assert_eq!(run_query(&index, dir.path(), "seq:>=3").len(), 1);
+        assert_eq!(run_query(&index, dir.path(), "meeting updated:<2026-01-01").len(), 1);
+        assert_eq!(run_query(&index, dir.path(), "meeting NOT tag:work").len(), 2);
+        assert_eq!(run_query(&index, dir.path(), "meeting OR plan").len(), 4);
+        assert!(run_query(&index, dir.path(), "in:archive")[0].path.ends_with("C.md"));
+        assert!(run_query(&index, dir.path(), "meeting in:trash")[0].path.ends_with("D.md"));
+        // ゴミ箱は肯定の in:trash / in:all で一致したときだけ
+        assert_eq!(run_query(&index, dir.path(), "meeting in:all").len(), 4);
+        assert_eq!(run_query(&index, dir.path(), "meeting in:notes").len(), 2);
+        assert_eq!(run_query(&index, dir.path(), "meeting NOT in:trash").len(), 3);
+        assert_eq!(run_query(&index, dir.path(), "meeting NOT in:archive").len(), 2);
+        assert_eq!(run_query(&index, dir.path(), "meeting OR in:archive").len(), 3);
+        let hits = run_query(&index, dir.path(), "plan OR in:trash");
+        assert_eq!(hits.len(), 2);
+        assert!(hits.iter().any(|h| h.path.ends_with("D.md")));
+        assert_eq!(run_query(&index, dir.path(), "/meet(ing)? (notes|archived)/").len(), 2);
+    }
+
+    #[test]
+    fn hits_are_grouped_with_ranges_and_context() {
+        let dir = tempfile::tempdir().unwrap();
+        let long = format!("{}東京タワーへ行く", "あ".repeat(100));
+        fs::write(
+            dir.path().join("0005_2026-03-01_旅行.md"),
+            format!("---\nseq: 5\ntags: [trip]\nbackgroundColor: #d0f0c0\n---\n\n予定\n{}\nメモ\nRust と rust", long),
+        ).unwrap();
+        let index = index_of(dir.path());
+
+        let groups = index.run(dir.path(), &query::parse("東京").unwrap());
+        assert_eq!(groups.len(), 1);
+        let group = &groups[0];
+        assert_eq!((group.seq, group.context.as_str()), (5, "旅行"));
+        assert_eq!(group.tags, vec!["trip".to_string()]);
+        assert_eq!(group.background_color.as_deref(), Some("#d0f0c0"));
+
+        // 行の後ろのほうの一致も preview に入り、範囲は preview 内のオフセット
+        let hit = &group.hits[0];
+        assert_eq!(hit.line, 2);
+        assert!(hit.preview.starts_with("..."));
+        let r = &hit.ranges[0];
+        let matched: String = hit.preview.chars().skip(r.start).take(r.end - r.start).collect();
+        assert_eq!(matched, "東京");
+        assert_eq!(hit.before, vec!["予定".to_string()]);
+        assert_eq!(hit.after, vec!["メモ".to_string()]);
+
+        // 大文字小文字を無視した複数一致と正規表現、NOT の語は光らせない
+        let hits = run_query(&index, dir.path(), "rust -メモ2");
+        assert_eq!(hits[0].ranges, vec![MatchRange { start: 0, end: 4 }, MatchRange { start: 7, end: 11 }]);
+        let hits = run_query(&index, dir.path(), "/R.st/");
+        assert_eq!(hits[0].ranges, vec![MatchRange { start: 0, end: 4 }]);
+    }
+
+    #[test]
+    fn stream_results_in_batches_and_stops_when_cancelled() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..(BATCH_GROUPS + 5) {
+            fs::write(dir.path().join(format!("{:04}.md", i)), "apple").unwrap();
+        }
+        let index = index_of(dir.path());
+        let q = query::parse("apple").unwrap();
+
+        let mut batches = Vec::new();
+        assert!(index.stream_results(dir.path(), &q, &|| false, &mut |g, done| batches.push((g.len(), done))));
+        assert_eq!(batches, vec![(BATCH_GROUPS, false), (5, true)]);
+
+        // 一致なしでも done を1回送る
+        let mut batches = Vec::new();
+        index.stream_results(dir.path(), &query::parse("banana").unwrap(), &|| false, &mut |g, done| batches.push((g.len(), done)));
+        assert_eq!(batches, vec![(0, true)]);
+
+        // 打ち切られたら何も送らない
+        let mut sent = 0;
+        assert!(!index.stream_results(dir.path(), &q, &|| true, &mut |_, _| sent += 1));
+        assert_eq!(sent, 0);
+    }
+
+    #[test]
+    fn cancelled_refresh_keeps_unvisited_docs() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.md"), "apple").unwrap();
+        fs::write(dir.path().join("b.md"), "banana").unwrap();
+        let mut index = index_of(dir.path());
+        fs::remove_file(dir.path().join("a.md")).unwrap();
+
+        // 走査しきれていないので、消えたとは判断しない
+        assert_eq!(index.refresh_until(dir.path(), &|| true), None);
+        assert_eq!(index.docs.len(), 2);
+        assert_eq!(index.refresh_until(dir.path(), &|| false), Some(true));
+        assert_eq!(index.docs.len(), 1);
+    }
+
+    #[test]
+    fn cancel_job_only_clears_matching_request() {
+        CURRENT_JOBS.lock().unwrap().insert("cancel-test".to_string(), 7);
+        cancel_job("cancel-test", 6);
+        assert!(is_current("cancel-test", 7));
+        cancel_job("cancel-test", 7);
+        assert!(!is_current("cancel-test", 7));
+    }
+
+    #[test]
+    fn slow_job_does_not_block_other_searches() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.md"), "meeting notes").unwrap();
+        let vault = dir.path().to_string_lossy().to_string();
+
+        // 結果を受け取る側が止まっているジョブ
+        let (entered_tx, entered_rx) = std::sync::mpsc::channel();
+        let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+        let release_rx = Mutex::new(release_rx);
+        start_job("slow-window".to_string(), vault.clone(), "meeting", 1, move |_| {
+            let _ = entered_tx.send(());
+            let _ = release_rx.lock().unwrap().recv();
+        })
+        .unwrap();
+        entered_rx.recv_timeout(std::time::Duration::from_secs(10)).unwrap();
+
+        // その間も別の検索は終わる
+        let (done_tx, done_rx) = std::sync::mpsc::channel();
+        let other = vault.clone();
+        std::thread::spawn(move || done_tx.send(search(&other, "meeting").unwrap().len()).unwrap());
+        assert_eq!(done_rx.recv_timeout(std::time::Duration::from_secs(10)).unwrap(), 1);
+        release_tx.send(()).unwrap();
+    }
+
+    #[test]
+    fn jobs_in_different_windows_do_not_cancel_each_other() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.md"), "meeting notes").unwrap();
+        let vault = dir.path().to_string_lossy().to_string();
+
+        // 別々のウィンドウが同じ request id で検索しても、互いに打ち切らない
+        let (tx, rx) = std::sync::mpsc::channel();
+        for owner in ["window-a", "window-b"] {
+            let tx = tx.clone();
+            start_job(owner.to_string(), vault.clone(), "meeting", 1, move |batch| {
+                if batch.done {
+                    tx.send((owner, batch.groups.len())).unwrap();
+                }
+            })
+            .unwrap();
+        }
+        let mut finished: Vec<_> = (0..2).map(|_| rx.recv_timeout(std::time::Duration::from_secs(10)).unwrap()).collect();
+        finished.sort();
+        assert_eq!(finished, vec![("window-a", 1), ("window-b", 1)]);
+    }
+
+    #[test]
+    fn refresh_updates_changed_files_and_skips_trash() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("Trash")).unwrap();
+        fs::write(dir.path().join("Trash").join("old.md"), "apple").unwrap();
+        fs::write(dir.path().join("a.md"), "apple").unwrap();
+        let mut index = index_of(dir.path());
+        assert_eq!(run_query(&index, dir.path(), "apple").len(), 1);
+
+        fs::write(dir.path().join("a.md"), "banana pie").unwrap();
+        assert!(index.refresh(dir.path()));
+        assert!(run_query(&index, dir.path(), "apple").is_empty());
+        assert_eq!(run_query(&index, dir.path(), "banana").len(), 1);
+        assert!(!index.refresh(dir.path()));
+    }
+}
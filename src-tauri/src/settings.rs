@@ -34,6 +34,13 @@ pub fn save_settings<R: Runtime>(
         }
     }
 
+    // [NEW] 監視対象も切り替える
+    if let Some(path) = &settings.base_path {
+        if let Err(e) = crate::watcher::watch_vault(&app, path) {
+            eprintln!("[SETTINGS] Failed to watch vault: {}", e);
+        }
+    }
+
     // 3. 全ウィンドウに通知を飛ばす（全体更新イベント）
     let _ = app.emit("settings_updated", &settings);
 
@@ -15,6 +15,14 @@ pub struct NoteMeta {
     pub always_on_top: Option<bool>,
     #[serde(default)]
     pub tags: Vec<String>,
+    #[serde(default)]
+    pub created: Option<String>,
+    #[serde(default)]
+    pub font_family: Option<String>,
+    #[serde(default)]
+    pub font_size: Option<f64>,
+    #[serde(default)]
+    pub line_height: Option<f64>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Clone)]
@@ -22,6 +30,41 @@ pub struct Note {
     pub body: String,
     pub frontmatter: String,
     pub meta: NoteMeta,
+    /// 読み込んだ時点のディスク上のバージョン（競合検出用）
+    #[serde(default)]
+    pub version: Option<NoteVersion>,
+}
+
+/// ファイルのバージョントークン。mtime と内容ハッシュの組。
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct NoteVersion {
+    pub mtime_ms: i64,
+    pub hash: String,
+}
+
+/// 保存系コマンドのエラー。フロントエンドは `kind` で分岐する。
+#[derive(serde::Serialize, Debug)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum NoteWriteError {
+    /// 読み込み後にディスク上のファイルが外部で変更された
+    Conflict {
+        path: String,
+        expected: NoteVersion,
+        actual: NoteVersion,
+    },
+    Failed { message: String },
+}
+
+impl From<String> for NoteWriteError {
+    fn from(message: String) -> Self {
+        NoteWriteError::Failed { message }
+    }
+}
+
+impl From<&str> for NoteWriteError {
+    fn from(message: &str) -> Self {
+        NoteWriteError::Failed { message: message.to_string() }
+    }
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Default, Clone)]
@@ -33,6 +76,31 @@ pub struct AppState {
     pub active_context_menu_path: Option<String>,
     pub active_world: Option<String>,
     pub active_tags: Vec<String>,
+    /// 起動時リカバリで見つかった一時ファイル (storage::recover_temp_files)
+    #[serde(default)]
+    pub recovered_files: Vec<RecoveredFile>,
+    /// 最後に読み書きした時点の各ノートのバージョン (path -> version)
+    #[serde(skip)]
+    pub known_versions: std::collections::HashMap<String, NoteVersion>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub enum RecoveryAction {
+    /// 本体が存在しなかったので一時ファイル（または削除途中で退避したファイル）を本体として復元した
+    Restored,
+    /// 本体は無事だったので一時ファイルを `.recovered-*` として退避した
+    SetAside,
+    /// 空の一時ファイル、または本体が残っている削除途中の退避ファイルだったので削除した
+    Removed,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct RecoveredFile {
+    pub temp_path: String,
+    pub target_path: String,
+    pub action: RecoveryAction,
+    /// SetAside の場合の退避先
+    pub kept_as: Option<String>,
 }
 
 // NEW: UC-01 - 設定ファイル用の構造体
@@ -51,11 +119,71 @@ pub struct Settings {
     #[serde(alias = "soundEnabled")]
     #[serde(default = "default_sound_enabled")]
     pub sound_enabled: bool,
+    /// [NEW] ゴミ箱の保持日数。これより古いノートは完全に削除する（0 = 削除しない）
+    #[serde(alias = "trashRetentionDays")]
+    #[serde(default = "default_trash_retention_days")]
+    pub trash_retention_days: u32,
+    /// [NEW] タグ付きノートのアーカイブ先
+    #[serde(alias = "archiveLayout")]
+    #[serde(default)]
+    pub archive_layout: ArchiveLayout,
+    /// [NEW] Vault の自動バックアップ (backup.rs)
+    #[serde(alias = "backupEnabled")]
+    #[serde(default)]
+    pub backup_enabled: bool,
+    /// バックアップ (zip) の保存先フォルダ
+    #[serde(alias = "backupDir")]
+    #[serde(default)]
+    pub backup_dir: Option<String>,
+    /// 何時間ごとに作るか（0 = 終了時だけ）
+    #[serde(alias = "backupIntervalHours")]
+    #[serde(default = "default_backup_interval_hours")]
+    pub backup_interval_hours: u32,
+    /// 残す数。超えたら古いものから消す（0 = 全部残す）
+    #[serde(alias = "backupKeep")]
+    #[serde(default = "default_backup_keep")]
+    pub backup_keep: u32,
+    /// ゴミ箱 (Trash/) も含める
+    #[serde(alias = "backupIncludeTrash")]
+    #[serde(default)]
+    pub backup_include_trash: bool,
+    /// アーカイブ (Archive/, tags/) も含める
+    #[serde(alias = "backupIncludeArchive")]
+    #[serde(default = "default_backup_include_archive")]
+    pub backup_include_archive: bool,
+    /// [NEW] 保存後に Vault を git へ自動コミットする (gitsync.rs)
+    #[serde(alias = "gitSyncEnabled")]
+    #[serde(default)]
+    pub git_sync_enabled: bool,
+    /// 同期先のリモート (URL かパス)。無ければコミットだけ
+    #[serde(alias = "gitRemote")]
+    #[serde(default)]
+    pub git_remote: Option<String>,
+    /// 最後の保存から何秒待ってコミットするか
+    #[serde(alias = "gitCommitDelaySecs")]
+    #[serde(default = "default_git_commit_delay_secs")]
+    pub git_commit_delay_secs: u32,
+}
+
+/// [NEW] タグ付きノートをどこへアーカイブするか
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ArchiveLayout {
+    /// 最初のタグのフォルダだけ（従来の動作）
+    #[default]
+    FirstTag,
+    /// 本体は最初のタグのフォルダ、他のタグのフォルダにはリンクを置く
+    AllTags,
 }
 
 fn default_language() -> String { "ja".to_string() }
 fn default_font_size() -> f64 { 16.0 }
 fn default_sound_enabled() -> bool { true }
+fn default_trash_retention_days() -> u32 { 30 }
+fn default_backup_interval_hours() -> u32 { 24 }
+fn default_backup_keep() -> u32 { 10 }
+fn default_backup_include_archive() -> bool { true }
+fn default_git_commit_delay_secs() -> u32 { 60 }
 
 impl Default for Settings {
     fn default() -> Self {
@@ -65,6 +193,17 @@ impl Default for Settings {
             auto_start: false,
             font_size: default_font_size(),
             sound_enabled: default_sound_enabled(),
+            trash_retention_days: default_trash_retention_days(),
+            archive_layout: ArchiveLayout::default(),
+            backup_enabled: false,
+            backup_dir: None,
+            backup_interval_hours: default_backup_interval_hours(),
+            backup_keep: default_backup_keep(),
+            backup_include_trash: false,
+            backup_include_archive: default_backup_include_archive(),
+            git_sync_enabled: false,
+            git_remote: None,
+            git_commit_delay_secs: default_git_commit_delay_secs(),
         }
     }
 }
@@ -1,16 +1,17 @@
 
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicI64, Ordering};
 use walkdir::WalkDir;
-use crate::state::{Note, NoteMeta};
-use crate::logic;
+use crate::state::{Note, NoteMeta, NoteVersion, RecoveredFile, RecoveryAction};
+use crate::logic::{self, Effect};
+use crate::frontmatter::NoteFrontmatter;
 
 // UC-01: 設定ファイル管理
 pub use crate::state::Settings;
 
 pub fn get_settings_path() -> Result<PathBuf, String> {
-    let app_data = std::env::var("APPDATA").map_err(|_| "APPDATA not found".to_string())?;
-    let config_dir = PathBuf::from(app_data).join("OreNoFusen");
+    let config_dir = crate::paths::config_dir()?;
     fs::create_dir_all(&config_dir).map_err(|e| e.to_string())?;
     Ok(config_dir.join("settings.json"))
 }
@@ -27,7 +28,7 @@ pub fn load_settings() -> Result<Settings, String> {
 pub fn save_settings(settings: &Settings) -> Result<(), String> {
     let path = get_settings_path()?;
     let content = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
-    fs::write(path, content).map_err(|e| e.to_string())
+    atomic_write(&path, content.as_bytes())
 }
 
 pub fn ensure_directory(path: &str) -> Result<(), String> {
@@ -59,12 +60,14 @@ pub fn import_files(source_dir: &str, dest_dir: &str) -> Result<usize, String> {
                 
                 // 4. 新しいseqとファイル名を生成
                 let seq = get_next_seq(dest_dir);
+                let id = new_note_id();
                 let today = chrono::Local::now().format("%Y-%m-%d").to_string();
                 let new_filename = logic::generate_filename(seq, &today, &context);
                 
                 // 5. 新しいフロントマターを生成（Δ0.7完全形式）
                 let frontmatter = logic::generate_frontmatter(
                     seq, 
+                    &id,
                     &context, 
                     &today,  // created
                     &today,  // updated
@@ -75,7 +78,7 @@ pub fn import_files(source_dir: &str, dest_dir: &str) -> Result<usize, String> {
                 // 6. 新しい内容を作成して保存
                 let new_content = format!("{}\n\n{}", frontmatter, body);
                 let dest_path = PathBuf::from(dest_dir).join(new_filename);
-                fs::write(dest_path, new_content).map_err(|e| e.to_string())?;
+                atomic_write(&dest_path, new_content.as_bytes())?;
                 count += 1;
             }
         }
@@ -86,94 +89,197 @@ pub fn import_files(source_dir: &str, dest_dir: &str) -> Result<usize, String> {
 
 // フロントマターを削除して本文だけを抽出するヘルパー関数
 fn extract_body_without_frontmatter(content: &str) -> String {
-    let trimmed = content.trim_start();
-    if trimmed.starts_with("---") {
-        // フロントマターの終わりを探す（最初の---の後）
-        if let Some(first_end) = trimmed[3..].find("---") {
-            let body_start = 3 + first_end + 3;
-            return trimmed[body_start..].trim_start().to_string();
-        }
-    }
-    // フロントマターがない場合はそのまま返す
-    content.to_string()
+    let (_, body) = logic::split_frontmatter(content);
+    body.to_string()
 }
 
+// メタデータインデックス経由（変更のあったファイルだけ読み直す）
 pub fn list_notes(folder_path: &str) -> Vec<NoteMeta> {
-    let mut notes = Vec::new();
-    let walker = WalkDir::new(folder_path).max_depth(1).into_iter();
-
-    for entry in walker.filter_map(|e| e.ok()) {
-        if entry.file_type().is_file() {
-            let path = entry.path();
-            if path.extension().map_or(false, |ext| ext == "md") {
-                let filename = path.file_name().unwrap().to_string_lossy().to_string();
-                let (seq, updated, context) = logic::parse_filename(&filename);
-                
-                let mut x = None;
-                let mut y = None;
-                let mut width = None;
-                let mut height = None;
-                let mut background_color = None;
-                let mut always_on_top = None;
-                let mut tags = Vec::new();
-
-                if let Ok(content) = fs::read_to_string(path) {
-                     let (lx, ly, lw, lh, lc, laot, ltags) = logic::extract_meta_from_content(&content);
-                     x = lx; y = ly; width = lw; height = lh; background_color = lc; always_on_top = laot;
-                     tags = ltags;
-                }
-
-                notes.push(NoteMeta {
-                    path: path.to_string_lossy().to_string(),
-                    seq,
-                    context,
-                    updated,
-                    x, y, width, height, background_color, always_on_top,
-                    tags
-                });
-            }
-        }
-    }
-    notes.sort_by(|a, b| a.path.cmp(&b.path));
-    notes
+    crate::index::list_notes(folder_path)
 }
 
 pub fn read_note(path: &str) -> Result<Note, String> {
     let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let version = fs::metadata(path).map(|m| build_version(content.as_bytes(), &m)).ok();
     
-    // 1. ファイル名から基本情報を解析
-    let path_obj = Path::new(path);
-    let filename = path_obj.file_name()
-        .ok_or("Invalid filename")?
-        .to_string_lossy()
-        .to_string();
-    let (seq, updated, context) = logic::parse_filename(&filename);
-
-    // 2. コンテンツから拡張メタデータを解析（list_notesと同様のロジック）
-    let (x, y, width, height, background_color, always_on_top, tags) = logic::extract_meta_from_content(&content);
-
-    // 3. 正しい値をセットして返す
+    // ファイル名 (seq/updated/context) とフロントマターからメタデータを解析
+    let meta = logic::build_note_meta(path, &content);
+
     Ok(Note {
         body: content,
         frontmatter: String::new(), 
-        meta: NoteMeta { 
-            path: path.to_string(),
-            seq,
-            context,
-            updated,
-            x, 
-            y, 
-            width, 
-            height, 
-            background_color, 
-            always_on_top,
-            tags,
-        },
+        meta,
+        version,
     })
 }
 
+// 現在のディスク上のバージョンを取得する（書き込み前の競合チェック用）
+pub fn note_version(path: &str) -> Result<NoteVersion, String> {
+    let content = fs::read(path).map_err(|e| e.to_string())?;
+    let metadata = fs::metadata(path).map_err(|e| e.to_string())?;
+    Ok(build_version(&content, &metadata))
+}
+
+fn build_version(content: &[u8], metadata: &fs::Metadata) -> NoteVersion {
+    use sha2::{Digest, Sha256};
+
+    NoteVersion {
+        mtime_ms: mtime_ms(metadata),
+        hash: format!("{:x}", Sha256::digest(content)),
+    }
+}
+
+pub fn mtime_ms(metadata: &fs::Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
 pub fn write_note(path: &str, content: &str) -> Result<(), String> {
-    fs::write(path, content).map_err(|e| e.to_string())
+    atomic_write(Path::new(path), content.as_bytes())
+}
+
+// --- クラッシュセーフな書き込み ---
+// 一時ファイルに書いて fsync してから rename で差し替える。
+// 途中でクラッシュしても、元のファイルは「旧版のまま」か「新版に置き換え済み」のどちらかになる。
+
+const TEMP_SUFFIX: &str = ".fusen-tmp";
+/// transaction.rs が削除を確定するまで退避しておくファイルの拡張子
+pub(crate) const STAGED_SUFFIX: &str = ".fusen-del";
+static TEMP_COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+pub fn atomic_write(path: &Path, content: &[u8]) -> Result<(), String> {
+    let dir = path.parent().ok_or("No parent")?;
+    let filename = path.file_name().ok_or("Invalid filename")?.to_string_lossy().to_string();
+
+    // 同時書き込みでも衝突しないよう pid + 連番を付ける: .{filename}.{pid}-{n}.fusen-tmp
+    let n = TEMP_COUNTER.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    let temp_path = dir.join(format!(".{}.{}-{}{}", filename, std::process::id(), n, TEMP_SUFFIX));
+
+    if let Err(e) = write_temp_and_rename(&temp_path, path, content) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e.to_string());
+    }
+    sync_dir(dir);
+    Ok(())
+}
+
+fn write_temp_and_rename(temp_path: &Path, path: &Path, content: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut file = fs::OpenOptions::new().write(true).create_new(true).open(temp_path)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(temp_path, path)
+}
+
+/// rename 自体を永続化するためにディレクトリを fsync する (Windows では不要・不可)
+fn sync_dir(dir: &Path) {
+    #[cfg(unix)]
+    {
+        if let Ok(d) = fs::File::open(dir) {
+            let _ = d.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = dir;
+    }
+}
+
+/// 一時ファイル名 (.{name}.{pid}-{n}{suffix}) から本来のファイル名を取り出す
+fn temp_target_name(temp_name: &str, suffix: &str) -> Option<String> {
+    let inner = temp_name.strip_prefix('.')?.strip_suffix(suffix)?;
+    // 末尾の .{pid}-{n} を外す
+    let (target, _) = inner.rsplit_once('.')?;
+    if target.is_empty() { None } else { Some(target.to_string()) }
+}
+
+/// 起動時リカバリ: 前回のクラッシュで残った一時ファイルを探して処理する
+pub fn recover_temp_files(root: &Path) -> Vec<RecoveredFile> {
+    let mut report = Vec::new();
+
+    for entry in WalkDir::new(root).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        // 削除の途中でクラッシュして退避したままのファイル: 元の場所が空いていれば戻し、埋まっていれば消す
+        if let Some(target_name) = temp_target_name(&name, STAGED_SUFFIX) {
+            if let Some(recovered) = recover_staged(entry.path(), &target_name) {
+                report.push(recovered);
+            }
+            continue;
+        }
+        let target_name = match temp_target_name(&name, TEMP_SUFFIX) {
+            Some(t) => t,
+            None => continue,
+        };
+        let temp_path = entry.path().to_path_buf();
+        let target_path = temp_path.with_file_name(&target_name);
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+
+        let (action, kept_as) = if size == 0 {
+            let _ = fs::remove_file(&temp_path);
+            (RecoveryAction::Removed, None)
+        } else if !target_path.exists() {
+            match fs::rename(&temp_path, &target_path) {
+                Ok(_) => (RecoveryAction::Restored, None),
+                Err(e) => {
+                    crate::logger::log_error(&format!("[Recovery] Failed to restore {}: {}", crate::logger::sanitize_path(&name), e));
+                    continue;
+                }
+            }
+        } else {
+            // 本体が残っている = rename 前にクラッシュした。新しい内容かもしれないので消さずに退避
+            let stamp = chrono::Local::now().format("%Y%m%d%H%M%S");
+            let aside = temp_path.with_file_name(format!("{}.recovered-{}", target_name, stamp));
+            match fs::rename(&temp_path, &aside) {
+                Ok(_) => (RecoveryAction::SetAside, Some(aside.to_string_lossy().to_string())),
+                Err(e) => {
+                    crate::logger::log_error(&format!("[Recovery] Failed to set aside {}: {}", crate::logger::sanitize_path(&name), e));
+                    continue;
+                }
+            }
+        };
+
+        crate::logger::log_warn(&format!("[Recovery] Leftover temp file for '{}': {:?}", target_name, action));
+        report.push(RecoveredFile {
+            temp_path: temp_path.to_string_lossy().to_string(),
+            target_path: target_path.to_string_lossy().to_string(),
+            action,
+            kept_as,
+        });
+    }
+
+    report
+}
+
+fn recover_staged(staged_path: &Path, target_name: &str) -> Option<RecoveredFile> {
+    let target_path = staged_path.with_file_name(target_name);
+    let result = if target_path.exists() {
+        fs::remove_file(staged_path).map(|_| RecoveryAction::Removed)
+    } else {
+        fs::rename(staged_path, &target_path).map(|_| RecoveryAction::Restored)
+    };
+    let action = match result {
+        Ok(action) => action,
+        Err(e) => {
+            crate::logger::log_error(&format!("[Recovery] Failed to recover staged {}: {}", crate::logger::sanitize_path(target_name), e));
+            return None;
+        }
+    };
+    crate::logger::log_warn(&format!("[Recovery] Leftover staged deletion for '{}': {:?}", target_name, action));
+    Some(RecoveredFile {
+        temp_path: staged_path.to_string_lossy().to_string(),
+        target_path: target_path.to_string_lossy().to_string(),
+        action,
+        kept_as: None,
+    })
 }
 
 pub fn rename_note(old_path: &str, new_path: &str) -> Result<(), String> {
@@ -193,6 +299,15 @@ pub fn get_next_seq(folder_path: &str) -> i32 {
     max_seq + 1
 }
 
+/// [NEW] 新しいノートの id（フロントマターの id:）。seq と違って使い回さない。
+/// 作成時刻（マイクロ秒）の16進。同じ時刻に続けて作っても重ならないよう前の値より大きくする
+pub fn new_note_id() -> String {
+    static LAST: AtomicI64 = AtomicI64::new(0);
+    let now = chrono::Utc::now().timestamp_micros();
+    let prev = LAST.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| Some(now.max(last + 1))).unwrap_or(0);
+    format!("{:x}", now.max(prev + 1))
+}
+
 pub fn ensure_trash_dir(parent_path: &Path) -> Result<PathBuf, String> {
     let trash_dir = parent_path.join("Trash");
     if !trash_dir.exists() {
@@ -222,115 +337,221 @@ pub fn ensure_archive_dir(parent_path: &Path) -> Result<PathBuf, String> {
     }
     Ok(archive_dir)
 }
-pub fn copy_associated_assets(note_path: &Path, target_note_dir: &Path) -> Result<(), String> {
-    let content = fs::read_to_string(note_path).map_err(|e| e.to_string())?;
-    let re = regex::Regex::new(r"!\[[^\]]*\]\((assets/[^)]+)\)").unwrap();
+/// [NEW] ノートごとの画像フォルダ（ノートからの相対パス）。seq が無いノートは assets/ 直下。
+pub fn note_assets_rel(seq: i32) -> String {
+    if seq > 0 {
+        format!("assets/{}", seq)
+    } else {
+        "assets".to_string()
+    }
+}
 
-    let note_dir = note_path.parent().ok_or("No parent")?;
-    let target_assets_dir = target_note_dir.join("assets");
+// --- ノートの移動（ゴミ箱・アーカイブ・復元） ---
+
+/// ノートを dest へ移し、画像も dest のフォルダの assets/<seq>/ へ移す。
+/// edit でフロントマターを書き換えられる。is_shared が true の画像は移さずにコピーする。
+/// [NEW] 画像の移動・ノートの書き込み・元のノートの削除は1つの Batch として実行し、
+/// 途中で失敗したら全部元に戻す（画像だけ移ってリンクが切れたノートを残さない）。
+pub fn move_note_with_assets(
+    src: &Path,
+    dest: &Path,
+    is_shared: impl Fn(&Path) -> bool,
+    edit: impl FnOnce(&mut NoteFrontmatter),
+) -> Result<(), String> {
+    let content = fs::read_to_string(src).map_err(|e| e.to_string())?;
+    let src_dir = src.parent().ok_or("No parent")?;
+    let dest_dir = dest.parent().ok_or("No parent")?;
+    let seq = logic::parse_filename(&dest.file_name().ok_or("Invalid filename")?.to_string_lossy()).0;
+
+    let (content, mut effects) = plan_asset_moves(&content, src_dir, dest_dir, seq, is_shared)?;
+    let mut fm = NoteFrontmatter::from_content(&content);
+    edit(&mut fm);
+
+    effects.push(Effect::WriteNote { path: path_string(dest), content: fm.apply_to_content(&content) });
+    if normalize_path(src) != normalize_path(dest) {
+        effects.push(Effect::DeleteFile { path: path_string(src) });
+    }
+    crate::transaction::run(Effect::Batch(effects))
+}
 
-    for cap in re.captures_iter(&content) {
-        let asset_rel_path = &cap[1];
-        let src_asset_path = note_dir.join(asset_rel_path);
-        
-        if src_asset_path.exists() {
-            if !target_assets_dir.exists() {
-                fs::create_dir_all(&target_assets_dir).map_err(|e| e.to_string())?;
-            }
-            let asset_filename = src_asset_path.file_name().ok_or("No asset filename")?;
-            let dest_asset_path = target_assets_dir.join(asset_filename);
-            
-            // すでに存在する場合はスキップまたは上書き
-            if !dest_asset_path.exists() {
-                fs::copy(&src_asset_path, &dest_asset_path).map_err(|e| e.to_string())?;
-            }
+fn path_string(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+/// ゴミ箱・アーカイブから戻すときの共通処理。画像も一緒に移す。
+/// original が空いていればそこへ、seq かファイル名が埋まっていれば新しい seq で戻す。
+/// edit でフロントマターを書き換えられる。戻したパスを返す。
+/// is_shared が true の画像は移さずにコピーする（plan_asset_moves と同じ）。
+/// seq を振り直すとき、id の無い古いノートには id を振って seq で分けていた履歴を引き継ぐ。
+pub fn move_note_back(
+    vault: &Path,
+    src: &Path,
+    original: &Path,
+    is_shared: impl Fn(&Path) -> bool,
+    edit: impl FnOnce(&mut NoteFrontmatter),
+) -> Result<PathBuf, String> {
+    let target_dir = original.parent().ok_or("No parent")?.to_path_buf();
+    fs::create_dir_all(&target_dir).map_err(|e| e.to_string())?;
+
+    let filename = original.file_name().ok_or("Invalid filename")?.to_string_lossy().to_string();
+    let (seq, date, context) = logic::parse_filename(&filename);
+    let (target, new_seq) = if original.exists() || seq_in_use(&target_dir, seq) {
+        let new_seq = get_next_seq(&target_dir.to_string_lossy());
+        (unique_path(&target_dir, &logic::generate_filename(new_seq, &date, &context)), Some(new_seq))
+    } else {
+        (original.to_path_buf(), None)
+    };
+
+    // 画像は新しい seq のフォルダ (assets/<seq>/) へ
+    let mut new_id = None;
+    move_note_with_assets(src, &target, is_shared, |fm| {
+        if let (Some(new_seq), Some(_)) = (new_seq, fm.seq) {
+            fm.seq = Some(new_seq);
+        }
+        if new_seq.is_some() && fm.id.is_none() {
+            new_id = Some(new_note_id());
+            fm.id = new_id.clone();
+        }
+        edit(fm);
+    })?;
+    if let Some(id) = new_id {
+        if let Err(e) = crate::history::adopt_legacy(vault, src, &id) {
+            eprintln!("[History] Failed to move revisions of {}: {}", src.display(), e);
         }
     }
-    Ok(())
+    Ok(target)
 }
 
-pub fn delete_associated_assets(note_path: &Path) -> Result<(), String> {
-    let content = fs::read_to_string(note_path).map_err(|e| e.to_string())?;
+/// 本文から参照している画像 (assets/...) の相対パス
+pub fn asset_refs(content: &str) -> Vec<String> {
     let re = regex::Regex::new(r"!\[[^\]]*\]\((assets/[^)]+)\)").unwrap();
+    re.captures_iter(content).map(|cap| cap[1].to_string()).collect()
+}
 
-    let note_dir = note_path.parent().ok_or("No parent")?;
-    
-    for cap in re.captures_iter(&content) {
-        let asset_rel_path = &cap[1];
-        let src_asset_path = note_dir.join(asset_rel_path);
-        
-        if src_asset_path.exists() {
-            fs::remove_file(&src_asset_path).map_err(|e| e.to_string())?;
-            // Optional: Try removing parent 'assets' dir if empty, but might be risky/noisy
-        }
+fn seq_in_use(dir: &Path, seq: i32) -> bool {
+    if seq <= 0 {
+        return false;
     }
-    Ok(())
+    let Ok(entries) = fs::read_dir(dir) else { return false };
+    entries
+        .filter_map(|e| e.ok())
+        .any(|e| logic::parse_filename(&e.file_name().to_string_lossy()).0 == seq)
 }
 
-pub fn open_in_explorer(path: &str) -> Result<(), String> {
-    #[cfg(target_os = "windows")]
-    {
-        use std::process::Command;
-        use std::path::Path;
+/// dir/name が空いていればそのまま、埋まっていれば "name-2.ext" のように番号を付ける
+/// （括弧や空白を使わない。画像リンク ![](assets/...) の ) で切れてしまうため）
+pub fn unique_path(dir: &Path, name: &str) -> PathBuf {
+    unique_path_avoiding(dir, name, &[])
+}
 
-        // Convert forward slashes to backslashes for Windows
-        let windows_path = path.replace('/', "\\");
+// unique_path と同じだが、これから作る予定のパス (reserved) も埋まっているものとして扱う
+fn unique_path_avoiding(dir: &Path, name: &str, reserved: &[PathBuf]) -> PathBuf {
+    let is_free = |p: &PathBuf| !p.exists() && !reserved.contains(p);
+    let candidate = dir.join(name);
+    if is_free(&candidate) {
+        return candidate;
+    }
+    let path = Path::new(name);
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let ext = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    (2..)
+        .map(|n| dir.join(format!("{}-{}{}", stem, n, ext)))
+        .find(is_free)
+        .unwrap()
+}
 
-        // [DEBUG] Log path
-        crate::logger::log_info(&format!("[DEBUG] open_in_explorer called with: '{}'", path));
-        
-        let path_obj = Path::new(&windows_path);
-        
-        if path_obj.exists() {
-            // Plan A: File exists, select it
-            Command::new("explorer")
-                .arg("/select,")
-                .arg(&windows_path)
-                .spawn()
-                .map_err(|e| e.to_string())?;
-        } else {
-            // Plan B: File missing, open parent folder (Fallback)
-            crate::logger::log_warn(&format!("[WARN] File not found: '{}'. Opening parent folder.", windows_path));
-            if let Some(parent) = path_obj.parent() {
-                 Command::new("explorer")
-                    .arg(parent)
-                    .spawn()
-                    .map_err(|e| e.to_string())?;
-            } else {
-                 // Fallback if parent lookup fails (e.g. root), try opening path directly
-                  Command::new("explorer")
-                    .arg(&windows_path)
-                    .spawn()
-                    .map_err(|e| e.to_string())?;
+/// 本文から参照している assets/ の画像を from_dir から to_dir へ移す手順を作る（まだ何も動かさない）。
+/// 移し先はノートごとのフォルダ (to_dir/assets/<seq>/)。
+/// 移し先に別の内容の同名ファイルがあれば名前を変え、リンクを書き換えた本文と手順を返す。
+/// is_shared が true の画像（他のノートも使っているもの）は元の場所に残してコピーする。
+pub fn plan_asset_moves(
+    content: &str,
+    from_dir: &Path,
+    to_dir: &Path,
+    seq: i32,
+    is_shared: impl Fn(&Path) -> bool,
+) -> Result<(String, Vec<Effect>), String> {
+    let dest_rel_dir = note_assets_rel(seq);
+    let dest_dir = to_dir.join(&dest_rel_dir);
+    let mut relinks: Vec<(String, String)> = Vec::new();
+    let mut seen: Vec<String> = Vec::new();
+    let mut effects: Vec<Effect> = Vec::new();
+    // この手順で作る予定のファイル
+    let mut planned: Vec<PathBuf> = Vec::new();
+
+    for rel in asset_refs(content) {
+        if seen.contains(&rel) {
+            continue;
+        }
+        seen.push(rel.clone());
+        let src = from_dir.join(&rel);
+        if !src.is_file() {
+            continue;
+        }
+        let name = src.file_name().ok_or("No asset filename")?.to_string_lossy().to_string();
+        let mut dest = dest_dir.join(&name);
+        // すでにノートのフォルダにある（同じフォルダ内での整理）
+        if normalize_path(&src) == normalize_path(&dest) {
+            continue;
+        }
+
+        let shared = is_shared(&src);
+        let same = dest.exists() && !planned.contains(&dest) && fs::read(&dest).ok() == fs::read(&src).ok();
+        if same {
+            // 同じ内容がもうあるのでそれを使う
+            if !shared {
+                effects.push(Effect::DeleteFile { path: path_string(&src) });
             }
+        } else {
+            dest = unique_path_avoiding(&dest_dir, &name, &planned);
+            let (from, to) = (path_string(&src), path_string(&dest));
+            effects.push(if shared { Effect::CopyFile { from, to } } else { Effect::MoveFile { from, to } });
+            planned.push(dest.clone());
+        }
+
+        let new_rel = format!("{}/{}", dest_rel_dir, dest.file_name().unwrap_or_default().to_string_lossy());
+        if new_rel != rel {
+            relinks.push((rel, new_rel));
         }
     }
-    #[cfg(not(target_os = "windows"))]
-    {
-        // Fallback for non-windows
-        return Err("Not implemented for this platform".to_string());
+
+    let mut content = content.to_string();
+    for (old, new) in relinks {
+        content = content.replace(&format!("]({})", old), &format!("]({})", new));
     }
-    Ok(())
+    Ok((content, effects))
 }
 
-pub fn open_file(path: &str) -> Result<(), String> {
-    #[cfg(target_os = "windows")]
-    {
-        use std::process::Command;
-        // Convert forward slashes to backslashes for Windows
-        let windows_path = path.replace('/', "\\");
-        
-        // Open file or folder with default application (explorer handles both)
-        Command::new("explorer")
-            .arg(&windows_path)
-            .spawn()
-            .map_err(|e| e.to_string())?;
+/// ".." を畳んだパス（存在しないパスも比較できるように canonicalize は使わない）
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => { out.pop(); }
+            Component::CurDir => {}
+            other => out.push(other),
+        }
     }
-    #[cfg(not(target_os = "windows"))]
-    {
-        return Err("Not implemented for this platform".to_string());
+    out
+}
+
+pub(crate) fn move_file(src: &Path, dest: &Path) -> Result<(), String> {
+    if fs::rename(src, dest).is_ok() {
+        return Ok(());
     }
-    Ok(())
+    // 別ドライブなど rename できない場合はコピーしてから消す
+    fs::copy(src, dest).map_err(|e| e.to_string())?;
+    fs::remove_file(src).map_err(|e| e.to_string())
+}
+
+pub fn open_in_explorer(path: &str) -> Result<(), String> {
+    use crate::launcher::{self, Platform, SystemRunner};
+    launcher::reveal_in_file_manager(&SystemRunner, Platform::current(), path)
+}
+
+pub fn open_file(path: &str) -> Result<(), String> {
+    use crate::launcher::{self, Platform, SystemRunner};
+    launcher::open_path(&SystemRunner, Platform::current(), path)
 }
 
 #[cfg(test)]
@@ -400,6 +621,96 @@ mod tests {
         assert_eq!(note.body, "上書きされた内容");
     }
 
+    #[test]
+    fn test_write_note_leaves_no_temp_files() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("0001_2026-01-12_Atomic.md");
+
+        write_note(&file_path.to_string_lossy(), "一回目").unwrap();
+        write_note(&file_path.to_string_lossy(), "二回目").unwrap();
+
+        let names: Vec<String> = fs::read_dir(dir.path()).unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["0001_2026-01-12_Atomic.md"]);
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "二回目");
+    }
+
+    // === note_version のテスト ===
+
+    #[test]
+    fn test_note_version_changes_with_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.md");
+        let path_str = path.to_string_lossy().to_string();
+        write_note(&path_str, "one").unwrap();
+
+        let read = read_note(&path_str).unwrap().version.unwrap();
+        assert_eq!(read, note_version(&path_str).unwrap());
+
+        fs::write(&path, "two").unwrap();
+        assert_ne!(read.hash, note_version(&path_str).unwrap().hash);
+    }
+
+    // === recover_temp_files のテスト ===
+
+    #[test]
+    fn test_recover_restores_missing_target() {
+        let dir = tempdir().unwrap();
+        let temp = dir.path().join(".0001_2026-01-12_Lost.md.123-0.fusen-tmp");
+        fs::write(&temp, "中身").unwrap();
+
+        let report = recover_temp_files(dir.path());
+
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].action, RecoveryAction::Restored);
+        assert!(!temp.exists());
+        assert_eq!(fs::read_to_string(dir.path().join("0001_2026-01-12_Lost.md")).unwrap(), "中身");
+    }
+
+    #[test]
+    fn test_recover_sets_aside_when_target_exists() {
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("0001_2026-01-12_Note.md");
+        fs::write(&target, "無事な本体").unwrap();
+        fs::write(dir.path().join(".0001_2026-01-12_Note.md.123-4.fusen-tmp"), "途中まで").unwrap();
+        fs::write(dir.path().join(".0001_2026-01-12_Note.md.123-5.fusen-tmp"), "").unwrap();
+
+        let mut report = recover_temp_files(dir.path());
+        report.sort_by(|a, b| a.temp_path.cmp(&b.temp_path));
+
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].action, RecoveryAction::SetAside);
+        assert_eq!(report[1].action, RecoveryAction::Removed);
+        // 本体は変更されない
+        assert_eq!(fs::read_to_string(&target).unwrap(), "無事な本体");
+        // 退避したファイルはノートとして一覧に出ない
+        assert_eq!(list_notes(&dir.path().to_string_lossy()).len(), 1);
+        assert!(Path::new(report[0].kept_as.as_ref().unwrap()).exists());
+    }
+
+    #[test]
+    fn test_recover_staged_deletions() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("assets/1")).unwrap();
+        // 本体が無い -> 戻す / 本体がある（削除を取り消した後など）-> 退避分を消す
+        let lost = dir.path().join(".0001_2026-01-12_Lost.md.123-0.fusen-del");
+        fs::write(&lost, "中身").unwrap();
+        let image = dir.path().join("assets/1/.a.png.123-1.fusen-del");
+        fs::write(&image, "png").unwrap();
+        fs::write(dir.path().join("assets/1/a.png"), "png").unwrap();
+
+        let mut report = recover_temp_files(dir.path());
+        report.sort_by(|a, b| a.target_path.cmp(&b.target_path));
+
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].action, RecoveryAction::Restored);
+        assert_eq!(fs::read_to_string(dir.path().join("0001_2026-01-12_Lost.md")).unwrap(), "中身");
+        assert_eq!(report[1].action, RecoveryAction::Removed);
+        assert!(!lost.exists() && !image.exists());
+        assert!(dir.path().join("assets/1/a.png").exists());
+    }
+
     // === rename_note のテスト ===
     
     #[test]
@@ -546,6 +857,24 @@ mod tests {
         assert!(archive_dir.ends_with("Archive"));
     }
 
+    #[test]
+    fn test_move_note_with_assets_rolls_back_when_note_cannot_be_written() {
+        let dir = tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir_all(vault.join("assets/1")).unwrap();
+        fs::write(vault.join("assets/1/a.png"), b"png").unwrap();
+        let src = vault.join("0001_2026-01-01_Memo.md");
+        fs::write(&src, "Memo\n![img](assets/1/a.png)").unwrap();
+        // 移し先にフォルダがあってノートを書けない
+        let dest = vault.join("Trash/0001_2026-01-01_Memo.md");
+        fs::create_dir_all(&dest).unwrap();
+
+        assert!(move_note_with_assets(&src, &dest, |_| false, |_| {}).is_err());
+        assert!(src.exists());
+        assert!(vault.join("assets/1/a.png").exists());
+        assert!(!vault.join("Trash/assets").exists());
+    }
+
 
     #[test]
     fn test_read_note_should_parse_metadata() {
@@ -0,0 +1,202 @@
+// [NEW] Effect のインタプリタ
+// 手順を順に実行し、途中で失敗したら実行済みの手順を逆順に戻す（全部成功するか、何も起きなかったことにする）。
+// 削除はすぐには消さず、同じフォルダの隠しファイル (.{name}.{pid}-{n}.fusen-del) へ退避しておき、
+// 全部成功してから消す。移し先のために作ったフォルダも、戻すときに空なら消す。
+// 確定前にクラッシュして残った退避ファイルは、起動時に storage::recover_temp_files が戻すか消す。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::logic::Effect;
+use crate::storage::{self, STAGED_SUFFIX};
+
+static STAGED_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// 実行済みの手順の戻し方
+enum Undo {
+    /// 上書きする前の内容に戻す
+    Restore { path: PathBuf, content: Vec<u8> },
+    /// 新しく作ったファイルを消す
+    Remove(PathBuf),
+    /// 移動を戻す
+    MoveBack { from: PathBuf, to: PathBuf },
+    /// 新しく作ったフォルダを消す（空のときだけ）
+    RemoveDir(PathBuf),
+}
+
+/// Effect を実行する。失敗したら実行済みの手順を戻してからエラーを返す。
+pub fn run(effect: Effect) -> Result<(), String> {
+    let mut done: Vec<Undo> = Vec::new();
+    let mut staged: Vec<PathBuf> = Vec::new();
+
+    if let Err(e) = apply(effect, &mut done, &mut staged) {
+        let failures = rollback(done);
+        if failures.is_empty() {
+            return Err(e);
+        }
+        crate::logger::log_error(&format!("[Transaction] Rollback incomplete after '{}': {}", e, failures.join(", ")));
+        return Err(format!("{}（元に戻せなかった手順があります: {}）", e, failures.join(", ")));
+    }
+
+    // 確定したので退避しておいた削除分を消す
+    for path in staged {
+        if let Err(e) = fs::remove_file(&path) {
+            eprintln!("[Transaction] Failed to remove staged file {}: {}", path.display(), e);
+        }
+    }
+    Ok(())
+}
+
+fn apply(effect: Effect, done: &mut Vec<Undo>, staged: &mut Vec<PathBuf>) -> Result<(), String> {
+    match effect {
+        Effect::WriteNote { path, content } => {
+            let path = PathBuf::from(path);
+            create_parent(&path, done)?;
+            let undo = match fs::read(&path) {
+                Ok(old) => Undo::Restore { path: path.clone(), content: old },
+                Err(_) => Undo::Remove(path.clone()),
+            };
+            storage::atomic_write(&path, content.as_bytes())?;
+            done.push(undo);
+        }
+        Effect::RenameNote { old_path, new_path } => {
+            storage::rename_note(&old_path, &new_path)?;
+            done.push(Undo::MoveBack { from: new_path.into(), to: old_path.into() });
+        }
+        Effect::MoveFile { from, to } => {
+            let (from, to) = (PathBuf::from(from), PathBuf::from(to));
+            ensure_vacant(&to)?;
+            create_parent(&to, done)?;
+            storage::move_file(&from, &to)?;
+            done.push(Undo::MoveBack { from: to, to: from });
+        }
+        Effect::CopyFile { from, to } => {
+            let to = PathBuf::from(to);
+            ensure_vacant(&to)?;
+            create_parent(&to, done)?;
+            if let Err(e) = fs::copy(&from, &to) {
+                let _ = fs::remove_file(&to);
+                return Err(format!("{} をコピーできません: {}", from, e));
+            }
+            done.push(Undo::Remove(to));
+        }
+        Effect::DeleteFile { path } => {
+            let path = PathBuf::from(path);
+            let aside = staged_path(&path)?;
+            fs::rename(&path, &aside).map_err(|e| format!("{} を削除できません: {}", path.display(), e))?;
+            done.push(Undo::MoveBack { from: aside.clone(), to: path });
+            staged.push(aside);
+        }
+        Effect::Batch(effects) => {
+            for effect in effects {
+                apply(effect, done, staged)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// 戻せなかった手順の説明を返す
+fn rollback(done: Vec<Undo>) -> Vec<String> {
+    let mut failures = Vec::new();
+    for undo in done.into_iter().rev() {
+        let (result, target) = match &undo {
+            Undo::Restore { path, content } => (storage::atomic_write(path, content), path),
+            Undo::Remove(path) => (fs::remove_file(path).map_err(|e| e.to_string()), path),
+            Undo::MoveBack { from, to } => (storage::move_file(from, to), to),
+            Undo::RemoveDir(dir) => {
+                let _ = fs::remove_dir(dir);
+                continue;
+            }
+        };
+        if let Err(e) = result {
+            failures.push(format!("{} ({})", target.display(), e));
+        }
+    }
+    failures
+}
+
+fn ensure_vacant(path: &Path) -> Result<(), String> {
+    if path.exists() {
+        return Err(format!("既にファイルがあります: {}", path.display()));
+    }
+    Ok(())
+}
+
+// 親フォルダを作る。作ったフォルダは内側から消えるように外側から積む
+fn create_parent(path: &Path, done: &mut Vec<Undo>) -> Result<(), String> {
+    let Some(parent) = path.parent() else { return Ok(()) };
+    let missing: Vec<PathBuf> = parent
+        .ancestors()
+        .take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists())
+        .map(Path::to_path_buf)
+        .collect();
+    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    done.extend(missing.into_iter().rev().map(Undo::RemoveDir));
+    Ok(())
+}
+
+fn staged_path(path: &Path) -> Result<PathBuf, String> {
+    let filename = path.file_name().ok_or("Invalid filename")?.to_string_lossy().to_string();
+    let n = STAGED_COUNTER.fetch_add(1, Ordering::SeqCst);
+    Ok(path.with_file_name(format!(".{}.{}-{}{}", filename, std::process::id(), n, STAGED_SUFFIX)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(path: &Path) -> String {
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn failed_step_rolls_back_everything_done_so_far() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for name in ["a.png", "b.png", "c.png", "note.md"] {
+            fs::write(root.join(name), name).unwrap();
+        }
+        let sub = root.join("Trash/assets/1");
+
+        let result = run(Effect::Batch(vec![
+            Effect::MoveFile { from: s(&root.join("a.png")), to: s(&sub.join("a.png")) },
+            Effect::CopyFile { from: s(&root.join("b.png")), to: s(&sub.join("b.png")) },
+            Effect::DeleteFile { path: s(&root.join("c.png")) },
+            Effect::WriteNote { path: s(&root.join("note.md")), content: "changed".into() },
+            Effect::WriteNote { path: s(&root.join("Trash/new.md")), content: "new".into() },
+            Effect::MoveFile { from: s(&root.join("missing.md")), to: s(&root.join("Trash/missing.md")) },
+        ]));
+
+        assert!(result.is_err());
+        for name in ["a.png", "b.png", "c.png", "note.md"] {
+            assert_eq!(fs::read_to_string(root.join(name)).unwrap(), name);
+        }
+        assert!(!root.join("Trash").exists());
+        assert_eq!(fs::read_dir(root).unwrap().count(), 4);
+    }
+
+    #[test]
+    fn successful_batch_commits_and_never_overwrites() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("a.png"), "a").unwrap();
+        fs::write(root.join("b.png"), "b").unwrap();
+
+        run(Effect::Batch(vec![
+            Effect::CopyFile { from: s(&root.join("a.png")), to: s(&root.join("x/a.png")) },
+            Effect::DeleteFile { path: s(&root.join("a.png")) },
+        ]))
+        .unwrap();
+        assert!(!root.join("a.png").exists());
+        assert_eq!(fs::read_to_string(root.join("x/a.png")).unwrap(), "a");
+        // 退避したファイルは残らない
+        assert_eq!(fs::read_dir(root).unwrap().count(), 2);
+
+        let err = run(Effect::MoveFile { from: s(&root.join("b.png")), to: s(&root.join("x/a.png")) });
+        assert!(err.is_err());
+        assert_eq!(fs::read_to_string(root.join("x/a.png")).unwrap(), "a");
+        assert!(root.join("b.png").exists());
+    }
+}
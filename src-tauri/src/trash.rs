@@ -0,0 +1,367 @@
+// [NEW] ゴミ箱
+// ノートは Vault 直下の Trash/ に移し、元の場所 (trashedFrom) と日時 (trashedAt) を
+// フロントマターに記録しておく。復元時はそこへ戻し、画像 (assets/) も一緒に移す。
+// 他のノートも使っている画像は移さずにコピーする（assets::AssetRefs）。
+// 戻し先で seq やファイル名がぶつかる場合は新しい seq を振り直す。
+// 保持日数 (Settings.trash_retention_days) を過ぎたノートは起動時と定期的に完全削除する。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, FixedOffset};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::assets::AssetRefs;
+use crate::frontmatter::NoteFrontmatter;
+use crate::logic;
+use crate::state::{AppState, NoteMeta};
+use crate::storage;
+
+// 期限切れの確認間隔（起動時にも1回実行する）
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(serde::Serialize, Clone)]
+pub struct TrashedNote {
+    /// ゴミ箱内のパス
+    pub path: String,
+    pub meta: NoteMeta,
+    /// 復元先（Vault からの相対パス）。記録が無い古いノートは None
+    pub trashed_from: Option<String>,
+    pub trashed_at: Option<String>,
+}
+
+pub fn trash_dir(vault: &Path) -> PathBuf {
+    vault.join("Trash")
+}
+
+/// ゴミ箱の中身。新しく捨てたものから順に返す。
+pub fn list_trashed(vault: &Path) -> Vec<TrashedNote> {
+    let Ok(entries) = fs::read_dir(trash_dir(vault)) else { return Vec::new() };
+    let mut notes: Vec<TrashedNote> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().and_then(|ext| ext.to_str()) == Some("md"))
+        .filter_map(|p| {
+            let content = fs::read_to_string(&p).ok()?;
+            let path = p.to_string_lossy().to_string();
+            let fm = NoteFrontmatter::from_content(&content);
+            Some(TrashedNote {
+                meta: logic::build_note_meta(&path, &content),
+                path,
+                trashed_from: fm.trashed_from,
+                trashed_at: fm.trashed_at,
+            })
+        })
+        .collect();
+    // RFC 3339 なので文字列のまま比較できる（日時の無いものは最後）
+    notes.sort_by(|a, b| b.trashed_at.cmp(&a.trashed_at).then(a.path.cmp(&b.path)));
+    notes
+}
+
+/// ノートと画像をゴミ箱へ移す。ゴミ箱内のパスを返す。
+pub fn move_to_trash(vault: &Path, note_path: &Path, trashed_at: &str) -> Result<PathBuf, String> {
+    let filename = note_path.file_name().ok_or("Invalid filename")?.to_string_lossy().to_string();
+
+    let trash = storage::ensure_trash_dir(vault)?;
+    // 同じ名前のノートを2回捨てても上書きしない
+    let dest = storage::unique_path(&trash, &filename);
+
+    let refs = AssetRefs::scan(vault);
+    let origin = note_path.strip_prefix(vault).unwrap_or(note_path).to_string_lossy().replace('\\', "/");
+    storage::move_note_with_assets(note_path, &dest, |asset| refs.shared_with_others(asset, note_path), |fm| {
+        fm.trashed_from = Some(origin);
+        fm.trashed_at = Some(trashed_at.to_string());
+    })?;
+    Ok(dest)
+}
+
+/// ゴミ箱のノートを元の場所へ戻す。戻したパスを返す。
+pub fn restore(vault: &Path, trashed_path: &Path) -> Result<PathBuf, String> {
+    // ゴミ箱の外のノートを渡されると、そのノート自身の上に「戻して」seq を振り直してしまう
+    let parent = trashed_path.parent().map(storage::normalize_path);
+    if parent != Some(storage::normalize_path(&trash_dir(vault))) {
+        return Err(format!("ゴミ箱内のノートではありません: {}", trashed_path.display()));
+    }
+    let content = fs::read_to_string(trashed_path).map_err(|e| e.to_string())?;
+    let original = match NoteFrontmatter::from_content(&content).trashed_from {
+        Some(rel) => vault.join(rel),
+        // 記録が無ければ Vault 直下へ
+        None => vault.join(trashed_path.file_name().ok_or("Invalid filename")?),
+    };
+    let refs = AssetRefs::scan(vault);
+    let is_shared = |asset: &Path| refs.shared_with_others(asset, trashed_path);
+    storage::move_note_back(vault, trashed_path, &original, is_shared, |fm| {
+        fm.trashed_from = None;
+        fm.trashed_at = None;
+    })
+}
+
+/// 完全に削除したもの
+#[derive(serde::Serialize, Clone, Debug, Default)]
+pub struct PurgeReport {
+    pub notes: Vec<String>,
+    /// 他のノートから参照されていなかったので一緒に消した画像
+    pub assets: Vec<String>,
+}
+
+/// ゴミ箱を空にする
+pub fn empty_trash(vault: &Path) -> Result<PurgeReport, String> {
+    purge(vault, None)
+}
+
+/// trashedAt が保持日数より前のノートを完全に削除する（0日なら何もしない）
+pub fn purge_expired(vault: &Path, retention_days: u32, now: DateTime<FixedOffset>) -> Result<PurgeReport, String> {
+    if retention_days == 0 {
+        return Ok(PurgeReport::default());
+    }
+    purge(vault, Some(now - chrono::Duration::days(retention_days as i64)))
+}
+
+// cutoff より前に捨てたもの（None なら全部）を削除する。
+// trashedAt が無い古いノートはファイルの更新日時で判断する。
+fn purge(vault: &Path, cutoff: Option<DateTime<FixedOffset>>) -> Result<PurgeReport, String> {
+    let mut report = PurgeReport::default();
+    let mut candidate_assets: Vec<PathBuf> = Vec::new();
+
+    for note in list_trashed(vault) {
+        let path = Path::new(&note.path);
+        if let Some(cutoff) = cutoff {
+            let trashed_at = note
+                .trashed_at
+                .as_deref()
+                .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                .or_else(|| {
+                    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+                    Some(DateTime::<chrono::Local>::from(modified).fixed_offset())
+                });
+            if !matches!(trashed_at, Some(t) if t < cutoff) {
+                continue;
+            }
+        }
+        let content = fs::read_to_string(path).unwrap_or_default();
+        let dir = path.parent().ok_or("No parent")?;
+        candidate_assets.extend(storage::asset_refs(&content).into_iter().map(|rel| dir.join(rel)));
+
+        fs::remove_file(path).map_err(|e| e.to_string())?;
+        report.notes.push(note.path);
+    }
+    if report.notes.is_empty() {
+        return Ok(report);
+    }
+
+    // 残っているノート（ゴミ箱内も含む）が参照している画像は消さない
+    let refs = AssetRefs::scan(vault);
+    for asset in candidate_assets {
+        if !asset.is_file() || !refs.users(&asset).is_empty() {
+            continue;
+        }
+        fs::remove_file(&asset).map_err(|e| e.to_string())?;
+        report.assets.push(asset.to_string_lossy().to_string());
+    }
+    report.assets.sort();
+    report.assets.dedup();
+    Ok(report)
+}
+
+/// 起動時と SWEEP_INTERVAL ごとに期限切れのノートを削除するスレッドを開始する
+pub fn start_retention_sweeper<R: Runtime>(app: AppHandle<R>) {
+    std::thread::spawn(move || loop {
+        let base_path = {
+            let state = app.state::<Mutex<AppState>>();
+            let app_state = state.lock().unwrap();
+            app_state.base_path.clone().or(app_state.folder_path.clone())
+        };
+        let retention_days = storage::load_settings().map(|s| s.trash_retention_days).unwrap_or_default();
+        if let Some(base) = base_path {
+            match purge_expired(Path::new(&base), retention_days, chrono::Local::now().fixed_offset()) {
+                Ok(report) if !report.notes.is_empty() => crate::logger::log_info(&format!(
+                    "[Trash] 期限切れのノートを {} 件、画像を {} 件削除しました",
+                    report.notes.len(),
+                    report.assets.len()
+                )),
+                Ok(_) => {}
+                Err(e) => crate::logger::log_warn(&format!("[Trash] 期限切れの削除に失敗しました: {}", e)),
+            }
+        }
+        std::thread::sleep(SWEEP_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTE: &str = "---\nseq: 5\ntags: [work]\n---\n\nMemo\n![img](assets/5/a.png)";
+
+    #[test]
+    fn trash_and_restore_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir_all(vault.join("assets/5")).unwrap();
+        fs::write(vault.join("assets/5/a.png"), b"png").unwrap();
+        let note = vault.join("0005_2026-01-10_Memo.md");
+        fs::write(&note, NOTE).unwrap();
+
+        let trashed = move_to_trash(vault, &note, "2026-02-01T10:00:00+09:00").unwrap();
+        assert!(!note.exists());
+        assert!(vault.join("Trash/assets/5/a.png").exists());
+        assert!(!vault.join("assets/5/a.png").exists());
+
+        let listed = list_trashed(vault);
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].trashed_from.as_deref(), Some("0005_2026-01-10_Memo.md"));
+        assert_eq!(listed[0].meta.tags, vec!["work".to_string()]);
+
+        let restored = restore(vault, &trashed).unwrap();
+        assert_eq!(restored, note);
+        assert_eq!(fs::read_to_string(&note).unwrap(), NOTE);
+        assert!(vault.join("assets/5/a.png").exists());
+        assert!(list_trashed(vault).is_empty());
+    }
+
+    #[test]
+    fn shared_assets_are_copied_not_moved() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir_all(vault.join("assets/5")).unwrap();
+        fs::write(vault.join("assets/5/a.png"), b"png").unwrap();
+        let note = vault.join("0005_2026-01-10_Memo.md");
+        fs::write(&note, NOTE).unwrap();
+        // コピー&ペーストで同じ画像を貼ったノート
+        fs::write(vault.join("0006_2026-01-11_Copy.md"), "Copy\n![img](assets/5/a.png)").unwrap();
+
+        let trashed = move_to_trash(vault, &note, "2026-02-01T10:00:00+09:00").unwrap();
+        assert!(vault.join("assets/5/a.png").exists());
+        assert!(vault.join("Trash/assets/5/a.png").exists());
+
+        // 戻すときも元の画像はそのまま（同じ内容なので名前も変わらない）
+        let restored = restore(vault, &trashed).unwrap();
+        assert_eq!(fs::read_to_string(&restored).unwrap(), NOTE);
+        assert!(vault.join("assets/5/a.png").exists());
+    }
+
+    #[test]
+    fn flat_assets_move_into_the_note_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir_all(vault.join("assets")).unwrap();
+        fs::write(vault.join("assets/old.png"), b"png").unwrap();
+        let note = vault.join("0005_2026-01-10_Memo.md");
+        fs::write(&note, "Memo\n![img](assets/old.png)").unwrap();
+
+        let trashed = move_to_trash(vault, &note, "2026-02-01T10:00:00+09:00").unwrap();
+        assert!(fs::read_to_string(&trashed).unwrap().contains("](assets/5/old.png)"));
+        assert!(vault.join("Trash/assets/5/old.png").exists());
+        assert!(!vault.join("assets/old.png").exists());
+    }
+
+    #[test]
+    fn restore_renumbers_when_seq_is_taken() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir_all(vault.join("assets/5")).unwrap();
+        fs::write(vault.join("assets/5/a.png"), b"png").unwrap();
+        let note = vault.join("0005_2026-01-10_Memo.md");
+        fs::write(&note, NOTE).unwrap();
+        let trashed = move_to_trash(vault, &note, "2026-02-01T10:00:00+09:00").unwrap();
+
+        // 捨てている間に seq 5 と 6 が使われた
+        fs::write(vault.join("0005_2026-01-20_Other.md"), "other").unwrap();
+        fs::write(vault.join("0006_2026-01-21_Next.md"), "next").unwrap();
+
+        let restored = restore(vault, &trashed).unwrap();
+        assert_eq!(restored, vault.join("0007_2026-01-10_Memo.md"));
+        let fm = NoteFrontmatter::from_content(&fs::read_to_string(&restored).unwrap());
+        assert_eq!(fm.seq, Some(7));
+        assert_eq!(fm.trashed_from, None);
+        // 画像のフォルダも新しい seq へ
+        assert!(fs::read_to_string(&restored).unwrap().contains("](assets/7/a.png)"));
+        assert!(vault.join("assets/7/a.png").exists());
+    }
+
+    #[test]
+    fn restore_rejects_notes_outside_trash() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir_all(vault.join("assets/5")).unwrap();
+        fs::write(vault.join("assets/5/a.png"), b"png").unwrap();
+        let note = vault.join("0005_2026-01-10_Memo.md");
+        fs::write(&note, NOTE).unwrap();
+
+        assert!(restore(vault, &note).is_err());
+        fs::create_dir_all(vault.join("Trash/sub")).unwrap();
+        fs::write(vault.join("Trash/sub/0005_2026-01-10_Memo.md"), NOTE).unwrap();
+        assert!(restore(vault, &vault.join("Trash/sub/0005_2026-01-10_Memo.md")).is_err());
+
+        // 何も動いていない
+        assert_eq!(fs::read_to_string(&note).unwrap(), NOTE);
+        assert!(vault.join("assets/5/a.png").exists());
+        assert!(!vault.join("0006_2026-01-10_Memo.md").exists());
+    }
+
+    #[test]
+    fn clashing_names_are_kept_apart() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir_all(vault.join("assets/5")).unwrap();
+
+        // 同じファイル名のノートを2回捨てる（画像の中身は別）
+        let note = vault.join("0005_2026-01-10_Memo.md");
+        fs::write(vault.join("assets/5/a.png"), b"first").unwrap();
+        fs::write(&note, NOTE).unwrap();
+        let first = move_to_trash(vault, &note, "2026-02-01T10:00:00+09:00").unwrap();
+        fs::write(vault.join("assets/5/a.png"), b"second").unwrap();
+        fs::write(&note, NOTE).unwrap();
+        let second = move_to_trash(vault, &note, "2026-02-02T10:00:00+09:00").unwrap();
+
+        assert_ne!(first, second);
+        let second_content = fs::read_to_string(&second).unwrap();
+        assert!(second_content.contains("](assets/5/a-2.png)"));
+        assert_eq!(fs::read(vault.join("Trash/assets/5/a-2.png")).unwrap(), b"second");
+        // 新しいものが先
+        assert_eq!(list_trashed(vault)[0].path, second.to_string_lossy());
+
+        // 名前を変えた画像も参照として認識され、復元で一緒に戻る
+        assert_eq!(storage::asset_refs(&second_content), vec!["assets/5/a-2.png".to_string()]);
+        let restored = restore(vault, &second).unwrap();
+        let restored_content = fs::read_to_string(&restored).unwrap();
+        let link = storage::asset_refs(&restored_content).pop().unwrap();
+        assert_eq!(fs::read(vault.join(&link)).unwrap(), b"second");
+        assert!(!vault.join("Trash/assets/5/a-2.png").exists());
+    }
+
+    #[test]
+    fn purge_expired_keeps_recent_notes_and_shared_assets() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        let trash = vault.join("Trash");
+        fs::create_dir_all(trash.join("assets")).unwrap();
+        fs::write(trash.join("assets/old.png"), b"old").unwrap();
+        fs::write(trash.join("assets/shared.png"), b"shared").unwrap();
+        fs::write(
+            trash.join("0001_2026-01-01_Old.md"),
+            "---\ntrashedAt: 2026-01-01T00:00:00+09:00\n---\n\n![a](assets/old.png)\n![b](assets/shared.png)",
+        ).unwrap();
+        fs::write(
+            trash.join("0002_2026-01-01_New.md"),
+            "---\ntrashedAt: 2026-02-25T00:00:00+09:00\n---\n\n![b](assets/shared.png)",
+        ).unwrap();
+
+        let now = DateTime::parse_from_rfc3339("2026-03-01T00:00:00+09:00").unwrap();
+        assert!(purge_expired(vault, 0, now).unwrap().notes.is_empty());
+
+        let report = purge_expired(vault, 30, now).unwrap();
+        assert_eq!(report.notes.len(), 1);
+        assert!(report.notes[0].ends_with("0001_2026-01-01_Old.md"));
+        assert_eq!(report.assets.len(), 1);
+        assert!(!trash.join("assets/old.png").exists());
+        // 残っているノートが使っている画像は消さない
+        assert!(trash.join("assets/shared.png").exists());
+
+        let report = empty_trash(vault).unwrap();
+        assert_eq!((report.notes.len(), report.assets.len()), (1, 1));
+        assert!(list_trashed(vault).is_empty());
+    }
+}
@@ -6,6 +6,8 @@ use tauri::{
 use crate::state::AppState;
 use crate::logic;
 use crate::storage;
+use crate::journal;
+use std::path::Path;
 use std::sync::Mutex;
 
 pub fn create_tray<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
@@ -29,6 +31,7 @@ pub fn refresh_tray_menu<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
     let label_search = if is_en { "Search" } else { "検索 (Search)" }; // [NEW] 全文検索
     let label_filter = if is_en { "Filter by Tags" } else { "タグで絞り込む (Filter by Tags)" };
     let label_quit = if is_en { "Quit" } else { "終了 (Quit)" };
+    let label_undo = if is_en { "Undo" } else { "元に戻す (Undo)" }; // [NEW] 操作の取り消し
 
     let hide_i = MenuItem::with_id(app, "hide_all", label_hide, true, None::<&str>)?;
     let show_i = MenuItem::with_id(app, "show_all", label_show, true, None::<&str>)?;
@@ -50,6 +53,17 @@ pub fn refresh_tray_menu<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
     
     let tags = logic::get_all_unique_tags(&*app_state);
     let active_tags = app_state.active_tags.clone(); // 選択中のタグ
+
+    // [NEW] 元に戻す（次に取り消される操作を表示する）
+    let vault_root = app_state.base_path.clone().or(app_state.folder_path.clone());
+    let last_operation = vault_root.as_deref().and_then(|vault| journal::last(Path::new(vault)));
+    let undo_i = match &last_operation {
+        Some(entry) => {
+            let label = if is_en { format!("Undo: {}", entry.operation.describe(true)) } else { format!("元に戻す: {}", entry.operation.describe(false)) };
+            MenuItem::with_id(app, "undo_last", label, true, None::<&str>)?
+        }
+        None => MenuItem::with_id(app, "undo_last", label_undo, false, None::<&str>)?,
+    };
     
     for tag in tags {
         let is_selected = active_tags.contains(&tag);
@@ -63,6 +77,7 @@ pub fn refresh_tray_menu<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
     let menu = Menu::with_items(app, &[
         &new_note_i, // [NEW] 最上部に配置
         &search_i, // [NEW] 全文検索
+        &undo_i, // [NEW] 元に戻す
         &tauri::menu::PredefinedMenuItem::separator(app)?, 
         &hide_i, 
         &show_i, 
@@ -131,6 +146,12 @@ pub fn refresh_tray_menu<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
                             eprintln!("[Tray] Failed to apply tag filter: {}", e);
                         }
                     },
+                    "undo_last" => { // [NEW] 元に戻す
+                        eprintln!("[Tray] Undoing last operation...");
+                        if let Err(e) = crate::undo_last_operation(app) {
+                            eprintln!("[Tray] Failed to undo: {}", e);
+                        }
+                    },
                     "quit" => {
                         app.exit(0);
                     },
@@ -0,0 +1,111 @@
+// [NEW] Vault監視
+// 同期クライアント・git pull・他のエディタなど、アプリ外での追加/削除/編集を
+// デバウンスして AppState に差分反映し、フロントエンドへイベントで通知する。
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::logger;
+use crate::logic::{self, VaultChange};
+use crate::state::AppState;
+use crate::storage;
+
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+// 監視中の Watcher。差し替えると古い方は drop されて停止する。
+static WATCHER: Mutex<Option<Debouncer<RecommendedWatcher>>> = Mutex::new(None);
+
+/// base_path の監視を開始する（既に監視中なら差し替える）
+pub fn watch_vault<R: Runtime>(app: &AppHandle<R>, base_path: &str) -> Result<(), String> {
+    let app_handle = app.clone();
+    let root = base_path.to_string();
+
+    let mut debouncer = new_debouncer(DEBOUNCE, move |result: DebounceEventResult| match result {
+        Ok(events) => {
+            let paths: Vec<PathBuf> = events.into_iter().map(|e| e.path).collect();
+            handle_events(&app_handle, &root, &paths);
+        }
+        Err(e) => logger::log_warn(&format!("[Watcher] 監視エラー: {}", e)),
+    })
+    .map_err(|e| e.to_string())?;
+
+    // list_notes と同じく直下の .md のみが対象
+    debouncer
+        .watcher()
+        .watch(Path::new(base_path), RecursiveMode::NonRecursive)
+        .map_err(|e| e.to_string())?;
+
+    *WATCHER.lock().unwrap() = Some(debouncer);
+    logger::log_info(&format!("[Watcher] 監視を開始しました: {}", base_path));
+    Ok(())
+}
+
+// イベントのパスを list_notes と同じ形式 (base_path + ファイル名) に揃える
+fn note_path_in(root: &str, path: &Path) -> Option<String> {
+    if path.extension().and_then(|ext| ext.to_str()) != Some("md") {
+        return None;
+    }
+    let name = path.file_name()?.to_string_lossy();
+    if name.starts_with('.') {
+        return None;
+    }
+    Some(Path::new(root).join(name.as_ref()).to_string_lossy().to_string())
+}
+
+fn handle_events<R: Runtime>(app: &AppHandle<R>, root: &str, paths: &[PathBuf]) {
+    let mut note_paths: Vec<String> = paths.iter().filter_map(|p| note_path_in(root, p)).collect();
+    note_paths.sort();
+    note_paths.dedup();
+    if note_paths.is_empty() {
+        return;
+    }
+
+    let state = app.state::<Mutex<AppState>>();
+    let mut changes = Vec::new();
+    {
+        let mut app_state = state.lock().unwrap();
+        for path in note_paths {
+            let on_disk = storage::note_version(&path).ok();
+            let Some(change) = logic::classify_vault_change(&app_state, &path, on_disk.as_ref()) else {
+                continue;
+            };
+            match change {
+                VaultChange::Created => match storage::read_note(&path) {
+                    Ok(note) => logic::apply_add_note(&mut app_state, note.meta),
+                    Err(_) => continue,
+                },
+                VaultChange::Modified => match storage::read_note(&path) {
+                    Ok(note) => logic::apply_update_note(&mut app_state, &path, note.meta),
+                    Err(_) => continue,
+                },
+                VaultChange::Removed => logic::apply_remove_note(&mut app_state, &path),
+            }
+            changes.push((change, path));
+        }
+    }
+    if changes.is_empty() {
+        return;
+    }
+
+    for (change, path) in &changes {
+        logger::log_info(&format!("[Watcher] {:?}: {}", change, path));
+        let event = match change {
+            VaultChange::Created => "fusen:note_created",
+            VaultChange::Modified => "fusen:reload_note",
+            VaultChange::Removed => "fusen:note_deleted",
+        };
+        let _ = app.emit(event, path);
+    }
+    let _ = app.emit("fusen:notes_updated", ());
+
+    // トレイメニュー更新はメインスレッドで行う
+    let app_handle = app.clone();
+    let _ = app.run_on_main_thread(move || {
+        let _ = crate::tray::refresh_tray_menu(&app_handle);
+    });
+}